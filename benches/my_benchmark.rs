@@ -1,14 +1,8 @@
-mod data;
-
-use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
-use data::{long_comp_strings, WORDS};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
 use std::collections::BinaryHeap;
+use weakheap::bench_utils::{long_comp_strings, words as get_words};
 use weakheap::WeakHeap;
 
-fn get_words(count: usize) -> Vec<String> {
-    WORDS[0..count].iter().map(|&w| w.to_string()).collect()
-}
-
 fn weakheap_sort(size: usize) -> Vec<String> {
     let heap = WeakHeap::from(get_words(size));
     heap.into_sorted_vec()
@@ -111,6 +105,95 @@ fn bench_sorting(c: &mut Criterion) {
     group.finish();
 }
 
+// Pseudo-random integers, reproducible between runs.
+fn numbers(size: usize) -> Vec<u64> {
+    let mut x: u64 = 0x9E37_79B9_7F4A_7C15;
+    (0..size)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        })
+        .collect()
+}
+
+fn bench_large_sorting(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Sorting large heaps");
+    group.sample_size(10);
+
+    for size in [100_000, 1_000_000, 10_000_000] {
+        let data = numbers(size);
+        group.bench_with_input(BenchmarkId::new("Binary Heap", size), &data, |b, d| {
+            b.iter_batched(
+                || BinaryHeap::from(d.clone()),
+                BinaryHeap::into_sorted_vec,
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("Weak Heap", size), &data, |b, d| {
+            b.iter_batched(
+                || WeakHeap::from(d.clone()),
+                WeakHeap::into_sorted_vec,
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+// Elements of 512 bytes, ordered by their first word, which make the cost of
+// moving elements during sifts dominate the cost of comparing them.
+fn large_elements(size: usize) -> Vec<[u64; 64]> {
+    numbers(size)
+        .into_iter()
+        .map(|x| {
+            let mut element = [x; 64];
+            element[0] = x % 1_000_000;
+            element
+        })
+        .collect()
+}
+
+fn bench_large_elements(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Pop & sort large elements");
+
+    for size in [1_000, 10_000, 100_000] {
+        let data = large_elements(size);
+        group.bench_with_input(BenchmarkId::new("Binary Heap pop", size), &data, |b, d| {
+            b.iter_batched(
+                || BinaryHeap::from(d.clone()),
+                |mut heap| while heap.pop().is_some() {},
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("Weak Heap pop", size), &data, |b, d| {
+            b.iter_batched(
+                || WeakHeap::from(d.clone()),
+                |mut heap| while heap.pop().is_some() {},
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("Binary Heap sort", size), &data, |b, d| {
+            b.iter_batched(
+                || BinaryHeap::from(d.clone()),
+                BinaryHeap::into_sorted_vec,
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("Weak Heap sort", size), &data, |b, d| {
+            b.iter_batched(
+                || WeakHeap::from(d.clone()),
+                WeakHeap::into_sorted_vec,
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_basics(c: &mut Criterion) {
     let mut group = c.benchmark_group("Push & Pop");
 
@@ -179,6 +262,8 @@ fn bench_long_comp(c: &mut Criterion) {
 criterion_group!(
     benches,
     bench_sorting,
+    bench_large_sorting,
+    bench_large_elements,
     bench_basics,
     bench_append,
     bench_long_comp
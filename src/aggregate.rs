@@ -0,0 +1,294 @@
+//! A priority queue maintaining an aggregate of its elements.
+
+use crate::{Iter, WeakHeap};
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FromIterator;
+use core::ops::{AddAssign, SubAssign};
+
+/// A value summarizing the elements of an [`AggregateWeakHeap`], updated
+/// incrementally on every insertion and removal.
+///
+/// The aggregate must be invertible: [`remove`] has to undo the effect of an
+/// earlier [`insert`] of the same item without looking at the other elements,
+/// as for a sum or a count, which form a group. Monoids without an inverse,
+/// such as a minimum, a maximum or a greatest common divisor, cannot be kept
+/// up to date this way, since removing an element could require a scan of
+/// the remaining ones. The maximum is available as
+/// [`AggregateWeakHeap::peek`] anyway.
+///
+/// # Examples
+///
+/// Keeping the total cost of pending tasks:
+///
+/// ```
+/// use weakheap::{Aggregate, AggregateWeakHeap};
+///
+/// #[derive(Default)]
+/// struct TotalCost(u64);
+///
+/// impl Aggregate<(u32, u64)> for TotalCost {
+///     fn insert(&mut self, item: &(u32, u64)) {
+///         self.0 += item.1;
+///     }
+///     fn remove(&mut self, item: &(u32, u64)) {
+///         self.0 -= item.1;
+///     }
+/// }
+///
+/// let mut heap: AggregateWeakHeap<_, TotalCost> = AggregateWeakHeap::new();
+/// heap.push((2, 100));
+/// heap.push((7, 30));
+/// assert_eq!(heap.aggregate().0, 130);
+///
+/// heap.pop();
+/// assert_eq!(heap.aggregate().0, 100);
+/// ```
+///
+/// [`insert`]: Aggregate::insert
+/// [`remove`]: Aggregate::remove
+pub trait Aggregate<T> {
+    /// Accounts for an element added to the heap.
+    fn insert(&mut self, item: &T);
+
+    /// Accounts for an element removed from the heap, undoing its earlier
+    /// [`insert`](Aggregate::insert).
+    fn remove(&mut self, item: &T);
+}
+
+/// An [`Aggregate`] counting the elements.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Count(pub usize);
+
+impl<T> Aggregate<T> for Count {
+    #[inline]
+    fn insert(&mut self, _item: &T) {
+        self.0 += 1;
+    }
+
+    #[inline]
+    fn remove(&mut self, _item: &T) {
+        self.0 -= 1;
+    }
+}
+
+/// An [`Aggregate`] summing the elements.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sum<S>(pub S);
+
+impl<T, S> Aggregate<T> for Sum<S>
+where
+    S: for<'a> AddAssign<&'a T> + for<'a> SubAssign<&'a T>,
+{
+    #[inline]
+    fn insert(&mut self, item: &T) {
+        self.0 += item;
+    }
+
+    #[inline]
+    fn remove(&mut self, item: &T) {
+        self.0 -= item;
+    }
+}
+
+impl<T, A: Aggregate<T>, B: Aggregate<T>> Aggregate<T> for (A, B) {
+    #[inline]
+    fn insert(&mut self, item: &T) {
+        self.0.insert(item);
+        self.1.insert(item);
+    }
+
+    #[inline]
+    fn remove(&mut self, item: &T) {
+        self.0.remove(item);
+        self.1.remove(item);
+    }
+}
+
+/// A priority queue implemented with a weak heap, which maintains an
+/// [`Aggregate`] of its elements.
+///
+/// The aggregate is updated in *O*(1) on every push and pop, so values like
+/// "total pending cost" can be reported without scanning the heap. This
+/// requires the aggregate to be invertible, as described for [`Aggregate`].
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::{AggregateWeakHeap, Count, Sum};
+///
+/// let mut heap: AggregateWeakHeap<i64, (Sum<i64>, Count)> = AggregateWeakHeap::new();
+/// heap.push(3);
+/// heap.push(10);
+/// heap.push(-1);
+/// assert_eq!(heap.aggregate(), &(Sum(12), Count(3)));
+///
+/// assert_eq!(heap.pop(), Some(10));
+/// assert_eq!(heap.aggregate(), &(Sum(2), Count(2)));
+/// ```
+pub struct AggregateWeakHeap<T, A> {
+    heap: WeakHeap<T>,
+    aggregate: A,
+}
+
+impl<T: Clone, A: Clone> Clone for AggregateWeakHeap<T, A> {
+    fn clone(&self) -> Self {
+        AggregateWeakHeap {
+            heap: self.heap.clone(),
+            aggregate: self.aggregate.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, A: fmt::Debug> fmt::Debug for AggregateWeakHeap<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AggregateWeakHeap")
+            .field("heap", &self.heap)
+            .field("aggregate", &self.aggregate)
+            .finish()
+    }
+}
+
+impl<T: Ord, A: Aggregate<T> + Default> Default for AggregateWeakHeap<T, A> {
+    /// Creates an empty `AggregateWeakHeap` with the default aggregate.
+    #[inline]
+    fn default() -> AggregateWeakHeap<T, A> {
+        AggregateWeakHeap::new()
+    }
+}
+
+impl<T: Ord, A: Aggregate<T> + Default> AggregateWeakHeap<T, A> {
+    /// Creates an empty `AggregateWeakHeap` with the default aggregate.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::{AggregateWeakHeap, Count};
+    /// let heap: AggregateWeakHeap<i32, Count> = AggregateWeakHeap::new();
+    /// assert_eq!(heap.aggregate(), &Count(0));
+    /// ```
+    #[must_use]
+    pub fn new() -> AggregateWeakHeap<T, A> {
+        AggregateWeakHeap::with_aggregate(A::default())
+    }
+
+    /// Drops all items from the heap and resets the aggregate to its default.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.aggregate = A::default();
+    }
+}
+
+impl<T: Ord, A: Aggregate<T>> AggregateWeakHeap<T, A> {
+    /// Creates an empty `AggregateWeakHeap` starting with the given aggregate.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::{AggregateWeakHeap, Sum};
+    /// let mut heap = AggregateWeakHeap::with_aggregate(Sum(100));
+    /// heap.push(5);
+    /// assert_eq!(heap.aggregate(), &Sum(105));
+    /// ```
+    #[must_use]
+    pub fn with_aggregate(aggregate: A) -> AggregateWeakHeap<T, A> {
+        AggregateWeakHeap {
+            heap: WeakHeap::new(),
+            aggregate,
+        }
+    }
+
+    /// Pushes an item onto the heap, updating the aggregate.
+    ///
+    /// # Time complexity
+    ///
+    /// The cost is that of [`WeakHeap::push`] plus one aggregate update.
+    pub fn push(&mut self, item: T) {
+        self.aggregate.insert(&item);
+        self.heap.push(item);
+    }
+
+    /// Removes the greatest item from the heap and returns it, or `None` if it
+    /// is empty, updating the aggregate.
+    ///
+    /// # Time complexity
+    ///
+    /// The cost is that of [`WeakHeap::pop`] plus one aggregate update.
+    pub fn pop(&mut self) -> Option<T> {
+        let item = self.heap.pop()?;
+        self.aggregate.remove(&item);
+        Some(item)
+    }
+
+    /// Consumes the heap and returns a vector in sorted (ascending) order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.heap.into_sorted_vec()
+    }
+}
+
+impl<T, A> AggregateWeakHeap<T, A> {
+    /// Returns the aggregate of all elements in the heap.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1).
+    #[must_use]
+    pub fn aggregate(&self) -> &A {
+        &self.aggregate
+    }
+
+    /// Returns the greatest item in the heap, or `None` if it is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.peek()
+    }
+
+    /// Returns an iterator visiting all values in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.heap.iter()
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Consumes the heap and returns the underlying vector in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        self.heap.into_vec()
+    }
+}
+
+impl<T: Ord, A: Aggregate<T> + Default> FromIterator<T> for AggregateWeakHeap<T, A> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> AggregateWeakHeap<T, A> {
+        let mut aggregate = A::default();
+        let heap = iter
+            .into_iter()
+            .inspect(|item| aggregate.insert(item))
+            .collect();
+        AggregateWeakHeap { heap, aggregate }
+    }
+}
+
+impl<T: Ord, A: Aggregate<T>> Extend<T> for AggregateWeakHeap<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
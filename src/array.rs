@@ -0,0 +1,237 @@
+//! A fixed-capacity priority queue stored inline.
+
+use crate::{raw, Iter};
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+/// A priority queue implemented with a weak heap of at most `N` elements,
+/// stored inline together with the reverse bits.
+///
+/// An `ArrayWeakHeap` never allocates, so it can live on the stack or in a
+/// `static` of firmware without a global allocator. Pushing into a full heap
+/// fails instead of growing it.
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::ArrayWeakHeap;
+///
+/// let mut heap: ArrayWeakHeap<u32, 4> = ArrayWeakHeap::new();
+/// for x in [3, 1, 4, 1] {
+///     heap.try_push(x).unwrap();
+/// }
+/// assert_eq!(heap.try_push(5), Err(5));
+///
+/// assert_eq!(heap.pop(), Some(4));
+/// assert_eq!(heap.pop(), Some(3));
+/// assert_eq!(heap.len(), 2);
+/// ```
+pub struct ArrayWeakHeap<T, const N: usize> {
+    // The elements `data[..len]` are initialized.
+    data: [MaybeUninit<T>; N],
+    bit: [bool; N],
+    len: usize,
+}
+
+impl<T, const N: usize> Drop for ArrayWeakHeap<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for ArrayWeakHeap<T, N> {
+    fn clone(&self) -> Self {
+        let mut heap = ArrayWeakHeap {
+            data: [const { MaybeUninit::uninit() }; N],
+            bit: self.bit,
+            len: 0,
+        };
+        for item in self.as_slice() {
+            heap.data[heap.len].write(item.clone());
+            heap.len += 1;
+        }
+        heap
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for ArrayWeakHeap<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<T: Ord, const N: usize> Default for ArrayWeakHeap<T, N> {
+    /// Creates an empty `ArrayWeakHeap`.
+    #[inline]
+    fn default() -> ArrayWeakHeap<T, N> {
+        ArrayWeakHeap::new()
+    }
+}
+
+impl<T: Ord, const N: usize> ArrayWeakHeap<T, N> {
+    /// Creates an empty `ArrayWeakHeap` as a max-heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::ArrayWeakHeap;
+    /// let mut heap = ArrayWeakHeap::<i32, 8>::new();
+    /// heap.try_push(4).unwrap();
+    /// ```
+    #[must_use]
+    pub const fn new() -> ArrayWeakHeap<T, N> {
+        ArrayWeakHeap {
+            data: [const { MaybeUninit::uninit() }; N],
+            bit: [false; N],
+            len: 0,
+        }
+    }
+
+    /// Pushes an item onto the heap. The item is returned back if the heap is full.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::ArrayWeakHeap;
+    /// let mut heap = ArrayWeakHeap::<i32, 2>::new();
+    /// assert_eq!(heap.try_push(3), Ok(()));
+    /// assert_eq!(heap.try_push(5), Ok(()));
+    /// assert_eq!(heap.try_push(1), Err(1));
+    ///
+    /// assert_eq!(heap.peek(), Some(&5));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost of `try_push` is *O*(1), the worst case is *O*(log(*n*)).
+    pub fn try_push(&mut self, item: T) -> Result<(), T> {
+        if self.len == N {
+            return Err(item);
+        }
+        let pos = self.len;
+        self.data[pos].write(item);
+        self.bit[pos] = false;
+        self.len += 1;
+        let (data, bit) = self.parts_mut();
+        // SAFETY: pos < data.len() == bit.len().
+        unsafe { raw::sift_up(data, &*bit, pos, &mut raw::lt) };
+        Ok(())
+    }
+
+    /// Removes the greatest item from the heap and returns it, or `None` if it
+    /// is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::ArrayWeakHeap;
+    /// let mut heap = ArrayWeakHeap::<i32, 4>::new();
+    /// heap.try_push(1).unwrap();
+    /// heap.try_push(3).unwrap();
+    ///
+    /// assert_eq!(heap.pop(), Some(3));
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost of `pop` on a heap containing *n* elements is *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let len = self.len - 1;
+        self.as_mut_slice().swap(0, len);
+        self.len = len;
+        // SAFETY: data[len] was initialized and is not part of the heap anymore.
+        let item = unsafe { self.data[len].assume_init_read() };
+        if len > 0 {
+            let (data, bit) = self.parts_mut();
+            // SAFETY: 0 < len == data.len() == bit.len().
+            unsafe { raw::sift_down_range(data, bit, 0, len, &mut raw::lt) };
+        }
+        Some(item)
+    }
+}
+
+impl<T, const N: usize> ArrayWeakHeap<T, N> {
+    /// Returns the greatest item in the heap, or `None` if it is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.as_slice().first()
+    }
+
+    /// Returns an iterator visiting all values in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            iter: self.as_slice().iter(),
+        }
+    }
+
+    /// Returns a slice of all values in the underlying array, in arbitrary order.
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: data[..len] is initialized.
+        unsafe { &*(ptr::addr_of!(self.data[..self.len]) as *const [T]) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        self.parts_mut().0
+    }
+
+    // Returns the elements and the reverse bits of the heap.
+    fn parts_mut(&mut self) -> (&mut [T], &mut [bool]) {
+        let len = self.len;
+        // SAFETY: data[..len] is initialized.
+        let data = unsafe { &mut *(ptr::addr_of_mut!(self.data[..len]) as *mut [T]) };
+        (data, &mut self.bit[..len])
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Checks if the heap holds `N` elements.
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Returns the maximum number of elements, `N`.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Drops all items from the heap.
+    pub fn clear(&mut self) {
+        let items: *mut [T] = self.as_mut_slice();
+        // Forget the items first, so that a panicking destructor leaks the
+        // rest instead of dropping them twice.
+        self.len = 0;
+        // SAFETY: the items were initialized and are not reachable anymore.
+        unsafe { ptr::drop_in_place(items) };
+    }
+}
@@ -0,0 +1,219 @@
+//! A priority queue whose `pop` waits for an element asynchronously.
+
+use crate::{ignore_poison, WeakHeap};
+use alloc::vec::Vec;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use std::sync::{Mutex, MutexGuard};
+
+struct Inner<T> {
+    heap: WeakHeap<T>,
+    // The tasks waiting in `pop` for an element.
+    waiters: Vec<Waker>,
+}
+
+/// A priority queue implemented with a weak heap, shared between tasks, whose
+/// [`pop`] returns a future which resolves when an element is available.
+///
+/// The queue is `Send + Sync` and all the methods take `&self`, so it can be
+/// shared through an `Arc`. It doesn't depend on any async runtime: a push
+/// wakes all the tasks waiting in `pop`, and those which don't get an element
+/// wait again. Dropping a `pop` future before it resolves loses no element.
+///
+/// Available with the `asyncqueue` feature.
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use std::future::Future;
+/// use std::pin::pin;
+/// use std::task::{Context, Poll, Waker};
+/// use weakheap::AsyncWeakHeap;
+///
+/// let queue = AsyncWeakHeap::new();
+/// let mut cx = Context::from_waker(Waker::noop());
+///
+/// let mut pop = pin!(queue.pop());
+/// assert_eq!(pop.as_mut().poll(&mut cx), Poll::Pending);
+///
+/// queue.push(3);
+/// queue.push(8);
+/// assert_eq!(pop.poll(&mut cx), Poll::Ready(8));
+/// assert_eq!(queue.try_pop(), Some(3));
+/// ```
+///
+/// [`pop`]: AsyncWeakHeap::pop
+pub struct AsyncWeakHeap<T> {
+    inner: Mutex<Inner<T>>,
+}
+
+impl<T> fmt::Debug for AsyncWeakHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncWeakHeap")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Ord> Default for AsyncWeakHeap<T> {
+    /// Creates an empty `AsyncWeakHeap`.
+    #[inline]
+    fn default() -> AsyncWeakHeap<T> {
+        AsyncWeakHeap::new()
+    }
+}
+
+impl<T: Ord> AsyncWeakHeap<T> {
+    /// Creates an empty `AsyncWeakHeap`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::AsyncWeakHeap;
+    /// let queue = AsyncWeakHeap::new();
+    /// queue.push(4);
+    /// ```
+    #[must_use]
+    pub fn new() -> AsyncWeakHeap<T> {
+        AsyncWeakHeap::from(WeakHeap::new())
+    }
+
+    /// Pushes an item onto the queue, and wakes the tasks waiting in
+    /// [`pop`](AsyncWeakHeap::pop).
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::AsyncWeakHeap;
+    /// let queue = AsyncWeakHeap::new();
+    /// queue.push(3);
+    /// queue.push(5);
+    ///
+    /// assert_eq!(queue.len(), 2);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(log(*n*)) in the worst case, plus one wake-up per waiting task.
+    pub fn push(&self, item: T) {
+        let waiters = {
+            let mut inner = self.lock();
+            inner.heap.push(item);
+            core::mem::take(&mut inner.waiters)
+        };
+        for waker in waiters {
+            waker.wake();
+        }
+    }
+
+    /// Returns a future which removes the greatest item from the queue and
+    /// resolves to it, waiting until the queue is not empty.
+    ///
+    /// See the [type documentation](AsyncWeakHeap) for an example.
+    pub fn pop(&self) -> AsyncPop<'_, T> {
+        AsyncPop { queue: self }
+    }
+
+    /// Removes the greatest item from the queue and returns it, or `None` if
+    /// it is empty, without waiting.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::AsyncWeakHeap;
+    /// let queue = AsyncWeakHeap::new();
+    /// assert_eq!(queue.try_pop(), None);
+    ///
+    /// queue.push(1);
+    /// assert_eq!(queue.try_pop(), Some(1));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn try_pop(&self) -> Option<T> {
+        self.lock().heap.pop()
+    }
+}
+
+impl<T> AsyncWeakHeap<T> {
+    /// Returns the number of elements in the queue.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().heap.len()
+    }
+
+    /// Checks if the queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().heap.is_empty()
+    }
+
+    /// Consumes the queue and returns the underlying heap.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_inner(self) -> WeakHeap<T> {
+        ignore_poison(self.inner.into_inner()).heap
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner<T>> {
+        ignore_poison(self.inner.lock())
+    }
+}
+
+impl<T> From<WeakHeap<T>> for AsyncWeakHeap<T> {
+    /// Shares the elements of `heap` between tasks.
+    fn from(heap: WeakHeap<T>) -> AsyncWeakHeap<T> {
+        AsyncWeakHeap {
+            inner: Mutex::new(Inner {
+                heap,
+                waiters: Vec::new(),
+            }),
+        }
+    }
+}
+
+/// A future resolving to the greatest item of an `AsyncWeakHeap`.
+///
+/// This `struct` is created by [`AsyncWeakHeap::pop`]. See its documentation
+/// for more.
+#[must_use = "futures do nothing unless polled"]
+pub struct AsyncPop<'a, T> {
+    queue: &'a AsyncWeakHeap<T>,
+}
+
+impl<T> fmt::Debug for AsyncPop<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncPop").finish_non_exhaustive()
+    }
+}
+
+impl<T: Ord> Future for AsyncPop<'_, T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut inner = self.queue.lock();
+        match inner.heap.pop() {
+            Some(item) => Poll::Ready(item),
+            None => {
+                if !inner
+                    .waiters
+                    .iter()
+                    .any(|waker| waker.will_wake(cx.waker()))
+                {
+                    inner.waiters.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
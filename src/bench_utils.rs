@@ -1,4 +1,21 @@
-pub const WORDS: [&str; 5000] = [
+//! Workloads used by the crate's benchmarks.
+//!
+//! Enabled with the `bench-utils` feature, so that downstream crates and bug
+//! reports can reproduce the same comparison-heavy scenarios.
+//!
+//! ```
+//! use weakheap::bench_utils::{long_comp_strings, words};
+//! use weakheap::WeakHeap;
+//!
+//! let heap = WeakHeap::from(words(100));
+//! assert_eq!(heap.len(), 100);
+//!
+//! let heap = WeakHeap::from(long_comp_strings());
+//! assert_eq!(heap.len(), 54);
+//! ```
+
+/// The first 5000 words of an excerpt from the novel "Martin Eden".
+pub static WORDS: [&str; 5000] = [
     "book",
     "on",
     "his",
@@ -5001,6 +5018,17 @@ pub const WORDS: [&str; 5000] = [
     "expectant,",
 ];
 
+/// Returns the first `count` words of [`WORDS`] as owned strings.
+///
+/// # Panics
+///
+/// Panics if `count` is greater than `WORDS.len()`.
+pub fn words(count: usize) -> Vec<String> {
+    WORDS[0..count].iter().map(|&w| w.to_string()).collect()
+}
+
+/// Returns strings whose comparison is relatively expensive, since many
+/// of them share long common prefixes.
 pub fn long_comp_strings() -> Vec<String> {
     vec![
         String::from("truhdkiufyhnsryfsjnf"),
@@ -0,0 +1,394 @@
+//! Storage for the reverse bits.
+
+use alloc::collections::TryReserveError;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FromIterator;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+// Number of words needed to store `len` bits.
+#[inline]
+const fn words_for(len: usize) -> usize {
+    len.div_ceil(WORD_BITS)
+}
+
+/// Access to a sequence of reverse bits, as required by the sifting code.
+pub(crate) trait Bits {
+    fn len(&self) -> usize;
+
+    /// Returns the bit at `pos`.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that `pos < self.len()`.
+    unsafe fn get_unchecked(&self, pos: usize) -> bool;
+
+    /// Flips the bit at `pos`.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that `pos < self.len()`.
+    unsafe fn flip_unchecked(&mut self, pos: usize);
+
+    /// Sets all bits to `false`.
+    fn reset(&mut self);
+}
+
+impl Bits for [bool] {
+    #[inline]
+    fn len(&self) -> usize {
+        <[bool]>::len(self)
+    }
+
+    #[inline]
+    unsafe fn get_unchecked(&self, pos: usize) -> bool {
+        *<[bool]>::get_unchecked(self, pos)
+    }
+
+    #[inline]
+    unsafe fn flip_unchecked(&mut self, pos: usize) {
+        *<[bool]>::get_unchecked_mut(self, pos) ^= true;
+    }
+
+    fn reset(&mut self) {
+        self.fill(false);
+    }
+}
+
+impl Bits for Vec<bool> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    #[inline]
+    unsafe fn get_unchecked(&self, pos: usize) -> bool {
+        Bits::get_unchecked(self.as_slice(), pos)
+    }
+
+    #[inline]
+    unsafe fn flip_unchecked(&mut self, pos: usize) {
+        Bits::flip_unchecked(self.as_mut_slice(), pos);
+    }
+
+    fn reset(&mut self) {
+        self.fill(false);
+    }
+}
+
+/// A growable array of bits packed into 64-bit words.
+///
+/// The bits past `len` in the last word are always zero.
+#[derive(Default, PartialEq, Eq, Hash)]
+pub(crate) struct BitVec {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl Clone for BitVec {
+    fn clone(&self) -> Self {
+        BitVec {
+            words: self.words.clone(),
+            len: self.len,
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.words.clone_from(&source.words);
+        self.len = source.len;
+    }
+}
+
+impl fmt::Debug for BitVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl BitVec {
+    pub(crate) const fn new() -> BitVec {
+        BitVec {
+            words: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub(crate) fn with_capacity(capacity: usize) -> BitVec {
+        BitVec {
+            words: Vec::with_capacity(words_for(capacity)),
+            len: 0,
+        }
+    }
+
+    /// Creates a `BitVec` of `len` zero bits.
+    pub(crate) fn zeros(len: usize) -> BitVec {
+        BitVec {
+            words: vec![0; words_for(len)],
+            len,
+        }
+    }
+
+    pub(crate) fn get(&self, pos: usize) -> Option<bool> {
+        // SAFETY: pos < self.len.
+        (pos < self.len).then(|| unsafe { Bits::get_unchecked(self, pos) })
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(move |pos| {
+            // SAFETY: pos < self.len.
+            unsafe { Bits::get_unchecked(self, pos) }
+        })
+    }
+
+    pub(crate) fn push(&mut self, value: bool) {
+        if self.len.is_multiple_of(WORD_BITS) {
+            self.words.push(0);
+        }
+        self.len += 1;
+        if value {
+            // SAFETY: self.len - 1 < self.len.
+            unsafe { self.flip_unchecked(self.len - 1) };
+        }
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<bool> {
+        let value = self.get(self.len.checked_sub(1)?)?;
+        self.truncate(self.len - 1);
+        Some(value)
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        self.len = len;
+        self.words.truncate(words_for(len));
+        if !len.is_multiple_of(WORD_BITS) {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << (len % WORD_BITS)) - 1;
+            }
+        }
+    }
+
+    /// Shortens the vector to its first bit, which must be clear, while
+    /// keeping the other bits in the buffer, and returns what
+    /// [`BitVec::unhide_tail`] needs to bring them back.
+    pub(crate) fn hide_tail(&mut self) -> (usize, u64) {
+        debug_assert_eq!(self.get(0), Some(false));
+        let hidden = (self.len, self.words[0]);
+        self.words[0] = 0;
+        // SAFETY: the vector is not empty, so 1 <= self.words.len(). The
+        // hidden words need no drop and stay initialized in the buffer.
+        unsafe { self.words.set_len(1) };
+        self.len = 1;
+        hidden
+    }
+
+    /// Restores the bits hidden by [`BitVec::hide_tail`].
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that the vector wasn't modified since
+    /// `hide_tail` returned `hidden`.
+    pub(crate) unsafe fn unhide_tail(&mut self, hidden: (usize, u64)) {
+        let (len, first) = hidden;
+        self.words.set_len(words_for(len));
+        self.words[0] = first;
+        self.len = len;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.words.clear();
+        self.len = 0;
+    }
+
+    pub(crate) fn resize(&mut self, len: usize, value: bool) {
+        if len <= self.len {
+            self.truncate(len);
+        } else if value {
+            for _ in self.len..len {
+                self.push(true);
+            }
+        } else {
+            self.words.resize(words_for(len), 0);
+            self.len = len;
+        }
+    }
+
+    /// Moves all the bits of `other` into `self`, leaving `other` empty.
+    pub(crate) fn append(&mut self, other: &mut BitVec) {
+        if self.len.is_multiple_of(WORD_BITS) {
+            self.words.append(&mut other.words);
+            self.len += other.len;
+        } else {
+            self.reserve(other.len);
+            for value in other.iter() {
+                self.push(value);
+            }
+        }
+        other.clear();
+    }
+
+    // Number of additional words needed to hold `additional` more bits.
+    fn additional_words(&self, additional: usize) -> usize {
+        words_for(self.len.saturating_add(additional)) - self.words.len()
+    }
+
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.words.reserve(self.additional_words(additional));
+    }
+
+    pub(crate) fn reserve_exact(&mut self, additional: usize) {
+        self.words.reserve_exact(self.additional_words(additional));
+    }
+
+    pub(crate) fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.words
+            .try_reserve_exact(self.additional_words(additional))
+    }
+
+    /// Number of bits the vector can hold without reallocating.
+    pub(crate) fn capacity(&self) -> usize {
+        self.words.capacity().saturating_mul(WORD_BITS)
+    }
+
+    /// Number of bytes allocated for the words.
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        self.words.capacity() * core::mem::size_of::<u64>()
+    }
+
+    pub(crate) fn shrink_to(&mut self, min_capacity: usize) {
+        self.words.shrink_to(words_for(min_capacity));
+    }
+}
+
+impl FromIterator<bool> for BitVec {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> BitVec {
+        let iter = iter.into_iter();
+        let mut bits = BitVec::with_capacity(iter.size_hint().0);
+        for value in iter {
+            bits.push(value);
+        }
+        bits
+    }
+}
+
+impl Bits for BitVec {
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    unsafe fn get_unchecked(&self, pos: usize) -> bool {
+        debug_assert!(pos < self.len);
+        (*self.words.get_unchecked(pos / WORD_BITS) >> (pos % WORD_BITS)) & 1 != 0
+    }
+
+    #[inline]
+    unsafe fn flip_unchecked(&mut self, pos: usize) {
+        debug_assert!(pos < self.len);
+        *self.words.get_unchecked_mut(pos / WORD_BITS) ^= 1 << (pos % WORD_BITS);
+    }
+
+    fn reset(&mut self) {
+        self.words.fill(0);
+    }
+}
+
+/// The reverse bits of a [`WeakHeap`], packed into 64-bit words.
+///
+/// Returned by [`WeakHeap::into_raw_parts`] and taken back by
+/// [`WeakHeap::from_raw_parts`], so that a heap can be taken apart and put
+/// back together without unpacking its bits. Bit `i` is stored in bit
+/// `i % 64` of word `i / 64`.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::ReverseBits;
+///
+/// let bits: ReverseBits = [false, true, false].into_iter().collect();
+/// assert_eq!(bits.len(), 3);
+/// assert_eq!(bits.get(1), Some(true));
+/// assert_eq!(bits.as_words(), [0b010]);
+/// assert_eq!(ReverseBits::from_words(vec![0b010], 3), bits);
+/// ```
+///
+/// [`WeakHeap`]: crate::WeakHeap
+/// [`WeakHeap::into_raw_parts`]: crate::WeakHeap::into_raw_parts
+/// [`WeakHeap::from_raw_parts`]: crate::WeakHeap::from_raw_parts
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct ReverseBits {
+    pub(crate) bit: BitVec,
+}
+
+impl fmt::Debug for ReverseBits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.bit.fmt(f)
+    }
+}
+
+impl ReverseBits {
+    /// Creates `ReverseBits` of length `len` from packed words, such as
+    /// the ones returned by [`ReverseBits::as_words`]. Words and bits past
+    /// `len` are discarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `words` holds fewer than `len` bits.
+    #[must_use]
+    pub fn from_words(words: Vec<u64>, len: usize) -> ReverseBits {
+        assert!(
+            words_for(len) <= words.len(),
+            "{} words cannot hold {} bits",
+            words.len(),
+            len
+        );
+        let mut bit = BitVec {
+            len: words.len() * WORD_BITS,
+            words,
+        };
+        bit.truncate(len);
+        ReverseBits { bit }
+    }
+
+    /// Returns the bits packed into 64-bit words.
+    #[must_use]
+    pub fn as_words(&self) -> &[u64] {
+        &self.bit.words
+    }
+
+    /// Returns the number of bits.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bit.len
+    }
+
+    /// Checks if there are no bits.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bit.len == 0
+    }
+
+    /// Returns the bit at `pos`, or `None` if it is out of bounds.
+    #[must_use]
+    pub fn get(&self, pos: usize) -> Option<bool> {
+        self.bit.get(pos)
+    }
+
+    /// Returns an iterator over the bits.
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        self.bit.iter()
+    }
+}
+
+impl FromIterator<bool> for ReverseBits {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> ReverseBits {
+        ReverseBits {
+            bit: iter.into_iter().collect(),
+        }
+    }
+}
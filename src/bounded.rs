@@ -0,0 +1,249 @@
+//! A priority queue retaining only the greatest elements.
+
+use crate::{raw, Iter};
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem::swap;
+
+// Orders the internal min-heap.
+#[inline]
+fn gt<T: Ord>(a: &T, b: &T) -> bool {
+    raw::lt(b, a)
+}
+
+/// A priority queue implemented with a weak heap, which never holds more than
+/// `bound` elements and retains the greatest elements pushed into it.
+///
+/// The elements are kept in a weak min-heap, so the smallest retained element
+/// is at the root. Pushing into a full heap costs a single comparison if the
+/// new element is rejected, and *O*(log(*k*)) if it evicts the smallest one,
+/// which makes the heap suitable for streaming top-*k* selection.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::BoundedWeakHeap;
+///
+/// let mut best = BoundedWeakHeap::new(3);
+/// for x in [5, 1, 8, 3, 9, 2, 7] {
+///     best.push(x);
+/// }
+///
+/// assert_eq!(best.peek_min(), Some(&7));
+/// assert_eq!(best.into_sorted_vec(), [7, 8, 9]);
+/// ```
+pub struct BoundedWeakHeap<T> {
+    data: Vec<T>,
+    bit: Vec<bool>,
+    bound: usize,
+}
+
+impl<T: Clone> Clone for BoundedWeakHeap<T> {
+    fn clone(&self) -> Self {
+        BoundedWeakHeap {
+            data: self.data.clone(),
+            bit: self.bit.clone(),
+            bound: self.bound,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for BoundedWeakHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.data.iter()).finish()
+    }
+}
+
+impl<T: Ord> BoundedWeakHeap<T> {
+    /// Creates an empty `BoundedWeakHeap` which retains at most `bound` elements.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::BoundedWeakHeap;
+    /// let mut heap = BoundedWeakHeap::new(10);
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn new(bound: usize) -> BoundedWeakHeap<T> {
+        BoundedWeakHeap {
+            data: Vec::new(),
+            bit: Vec::new(),
+            bound,
+        }
+    }
+
+    /// Pushes an item onto the heap. If the heap is full, the smallest of the
+    /// retained elements and `item` is returned and does not take part in the
+    /// heap anymore.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::BoundedWeakHeap;
+    /// let mut heap = BoundedWeakHeap::new(2);
+    ///
+    /// assert_eq!(heap.push(3), None);
+    /// assert_eq!(heap.push(5), None);
+    /// // 1 is rejected.
+    /// assert_eq!(heap.push(1), Some(1));
+    /// // 4 evicts 3.
+    /// assert_eq!(heap.push(4), Some(3));
+    /// assert_eq!(heap.len(), 2);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Rejecting an item costs one comparison. Otherwise the expected cost of
+    /// `push` is *O*(1) while the heap is not full, and the worst case is
+    /// *O*(log(*k*)).
+    pub fn push(&mut self, mut item: T) -> Option<T> {
+        if self.data.len() < self.bound {
+            let old_len = self.data.len();
+            self.data.push(item);
+            self.bit.push(false);
+            // SAFETY: old_len < self.data.len().
+            unsafe { raw::sift_up(&mut self.data, &self.bit, old_len, &mut gt) };
+            return None;
+        }
+
+        match self.data.first_mut() {
+            Some(min) if raw::lt(min, &item) => {
+                swap(min, &mut item);
+                let len = self.data.len();
+                // SAFETY: 0 < len == self.data.len().
+                unsafe { raw::sift_down_range(&mut self.data, &mut self.bit, 0, len, &mut gt) };
+                Some(item)
+            }
+            _ => Some(item),
+        }
+    }
+
+    /// Removes the smallest retained item and returns it, or `None` if the
+    /// heap is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::BoundedWeakHeap;
+    /// let mut heap = BoundedWeakHeap::new(5);
+    /// heap.extend(vec![3, 1]);
+    ///
+    /// assert_eq!(heap.pop_min(), Some(1));
+    /// assert_eq!(heap.pop_min(), Some(3));
+    /// assert_eq!(heap.pop_min(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*k*)).
+    pub fn pop_min(&mut self) -> Option<T> {
+        self.bit.pop();
+        let mut item = self.data.pop()?;
+        if !self.data.is_empty() {
+            swap(&mut item, &mut self.data[0]);
+            let len = self.data.len();
+            // SAFETY: 0 < len == self.data.len().
+            unsafe { raw::sift_down_range(&mut self.data, &mut self.bit, 0, len, &mut gt) };
+        }
+        Some(item)
+    }
+
+    /// Consumes the heap and returns the retained elements in sorted
+    /// (ascending) order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::BoundedWeakHeap;
+    /// let mut heap = BoundedWeakHeap::new(3);
+    /// heap.extend(vec![4, 1, 5, 3, 2]);
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [3, 4, 5]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut end = self.data.len();
+        while end > 1 {
+            end -= 1;
+            self.data.swap(0, end);
+            // SAFETY: 0 < end < self.data.len().
+            unsafe { raw::sift_down_range(&mut self.data, &mut self.bit, 0, end, &mut gt) };
+        }
+        self.data.reverse();
+        self.data
+    }
+}
+
+impl<T> BoundedWeakHeap<T> {
+    /// Returns the smallest retained item, the next one to be evicted, or
+    /// `None` if the heap is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek_min(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Returns the maximum number of elements retained by the heap.
+    #[must_use]
+    pub fn bound(&self) -> usize {
+        self.bound
+    }
+
+    /// Checks if the heap holds `bound` elements, so that any further push
+    /// evicts or rejects an element.
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.data.len() >= self.bound
+    }
+
+    /// Returns an iterator visiting all retained values in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            iter: self.data.iter(),
+        }
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Drops all items from the heap.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.bit.clear();
+    }
+
+    /// Consumes the heap and returns the retained elements in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+}
+
+impl<T: Ord> Extend<T> for BoundedWeakHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! A priority queue ordered by a custom comparator.
+
+use crate::{raw, Iter};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::mem::swap;
+
+/// A priority queue implemented with a weak heap, ordered by a comparator
+/// closure instead of the `Ord` implementation of the elements.
+///
+/// This will be a max-heap with respect to the comparator: the element
+/// which compares greatest is popped first.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::WeakHeapBy;
+///
+/// // A min-heap of floats.
+/// let mut heap = WeakHeapBy::new_by(|a: &f64, b: &f64| b.total_cmp(a));
+/// heap.push(2.5);
+/// heap.push(-1.0);
+/// heap.push(7.0);
+///
+/// assert_eq!(heap.pop(), Some(-1.0));
+/// assert_eq!(heap.pop(), Some(2.5));
+/// assert_eq!(heap.pop(), Some(7.0));
+/// ```
+pub struct WeakHeapBy<T, F> {
+    data: Vec<T>,
+    bit: Vec<bool>,
+    cmp: F,
+}
+
+impl<T: Clone, F: Clone> Clone for WeakHeapBy<T, F> {
+    fn clone(&self) -> Self {
+        WeakHeapBy {
+            data: self.data.clone(),
+            bit: self.bit.clone(),
+            cmp: self.cmp.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for WeakHeapBy<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.data.iter()).finish()
+    }
+}
+
+// Turns a comparator into the `is_less` predicate used by the sifting code.
+#[inline]
+fn less<T, F: FnMut(&T, &T) -> Ordering>(cmp: &mut F) -> impl FnMut(&T, &T) -> bool + '_ {
+    move |a, b| cmp(a, b) == Ordering::Less
+}
+
+impl<T, F: FnMut(&T, &T) -> Ordering> WeakHeapBy<T, F> {
+    /// Creates an empty `WeakHeapBy` ordered by `cmp`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapBy;
+    /// let mut heap = WeakHeapBy::new_by(|a: &i32, b: &i32| a.cmp(b));
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn new_by(cmp: F) -> WeakHeapBy<T, F> {
+        WeakHeapBy {
+            data: Vec::new(),
+            bit: Vec::new(),
+            cmp,
+        }
+    }
+
+    /// Creates an empty `WeakHeapBy` ordered by `cmp`, with a specific capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapBy;
+    /// let mut heap = WeakHeapBy::with_capacity_by(10, |a: &i32, b: &i32| a.cmp(b));
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn with_capacity_by(capacity: usize, cmp: F) -> WeakHeapBy<T, F> {
+        WeakHeapBy {
+            data: Vec::with_capacity(capacity),
+            bit: Vec::with_capacity(capacity),
+            cmp,
+        }
+    }
+
+    /// Converts a `Vec<T>` into a `WeakHeapBy` ordered by `cmp`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapBy;
+    ///
+    /// let heap = WeakHeapBy::from_vec_by(vec!["bb", "a", "ccc"], |a: &&str, b: &&str| a.len().cmp(&b.len()));
+    /// assert_eq!(heap.peek(), Some(&"ccc"));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Building takes *n* - 1 comparisons.
+    pub fn from_vec_by(vec: Vec<T>, cmp: F) -> WeakHeapBy<T, F> {
+        let mut heap = WeakHeapBy {
+            bit: vec![false; vec.len()],
+            data: vec,
+            cmp,
+        };
+        raw::rebuild(&mut heap.data, &mut heap.bit, &mut less(&mut heap.cmp));
+        heap
+    }
+
+    /// Pushes an item onto the heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapBy;
+    /// let mut heap = WeakHeapBy::new_by(|a: &i32, b: &i32| b.cmp(a));
+    /// heap.push(3);
+    /// heap.push(5);
+    /// heap.push(1);
+    ///
+    /// assert_eq!(heap.len(), 3);
+    /// assert_eq!(heap.peek(), Some(&1));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost of `push` is *O*(1), the worst case is *O*(log(*n*)).
+    pub fn push(&mut self, item: T) {
+        let old_len = self.data.len();
+        self.data.push(item);
+        self.bit.push(false);
+        // SAFETY: old_len < self.data.len().
+        unsafe { raw::sift_up(&mut self.data, &self.bit, old_len, &mut less(&mut self.cmp)) };
+    }
+
+    /// Removes the greatest item with respect to the comparator and returns
+    /// it, or `None` if the heap is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapBy;
+    /// let mut heap = WeakHeapBy::from_vec_by(vec![1, 3], |a: &i32, b: &i32| a.cmp(b));
+    ///
+    /// assert_eq!(heap.pop(), Some(3));
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost of `pop` on a heap containing *n* elements is *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        self.bit.pop();
+        let mut item = self.data.pop()?;
+        if !self.data.is_empty() {
+            swap(&mut item, &mut self.data[0]);
+            let len = self.data.len();
+            // SAFETY: 0 < len == self.data.len().
+            unsafe {
+                raw::sift_down_range(
+                    &mut self.data,
+                    &mut self.bit,
+                    0,
+                    len,
+                    &mut less(&mut self.cmp),
+                )
+            };
+        }
+        Some(item)
+    }
+
+    /// Consumes the heap and returns a vector sorted in ascending order with
+    /// respect to the comparator.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapBy;
+    /// let heap = WeakHeapBy::from_vec_by(vec![4, 1, 3, 2], |a: &i32, b: &i32| b.cmp(a));
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [4, 3, 2, 1]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut end = self.data.len();
+        while end > 1 {
+            end -= 1;
+            self.data.swap(0, end);
+            // SAFETY: 0 < end < self.data.len().
+            unsafe {
+                raw::sift_down_range(
+                    &mut self.data,
+                    &mut self.bit,
+                    0,
+                    end,
+                    &mut less(&mut self.cmp),
+                )
+            };
+        }
+        self.data
+    }
+}
+
+impl<T, F> WeakHeapBy<T, F> {
+    /// Returns the greatest item with respect to the comparator, or `None` if
+    /// the heap is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Returns an iterator visiting all values in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            iter: self.data.iter(),
+        }
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Drops all items from the heap.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.bit.clear();
+    }
+
+    /// Consumes the heap and returns the underlying vector in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+}
+
+impl<T, F: FnMut(&T, &T) -> Ordering> Extend<T> for WeakHeapBy<T, F> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let start = self.data.len();
+        self.data.extend(iter);
+        self.bit.resize(self.data.len(), false);
+        raw::rebuild_tail(
+            &mut self.data,
+            &mut self.bit,
+            start,
+            &mut less(&mut self.cmp),
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! A priority queue ordered by cached keys.
+
+use crate::raw;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem::swap;
+
+// Compares the cached keys of two entries.
+#[inline]
+fn key_lt<K: Ord, T>(a: &(K, T), b: &(K, T)) -> bool {
+    raw::lt(&a.0, &b.0)
+}
+
+/// A priority queue implemented with a weak heap, ordered by a key extracted
+/// from every element.
+///
+/// The key of an element is computed once, when the element is inserted, and
+/// stored alongside it, like [`slice::sort_by_cached_key`]. Only the cached
+/// keys are compared while sifting, so this pays off when computing the key
+/// is expensive, for instance a collation key of a string.
+///
+/// This will be a max-heap with respect to the keys.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::WeakHeapByKey;
+///
+/// let mut heap = WeakHeapByKey::new(|s: &String| s.to_lowercase());
+/// heap.push(String::from("banana"));
+/// heap.push(String::from("Cherry"));
+/// heap.push(String::from("apple"));
+///
+/// assert_eq!(heap.pop().as_deref(), Some("Cherry"));
+/// assert_eq!(heap.peek_key().map(String::as_str), Some("banana"));
+/// ```
+///
+/// [`slice::sort_by_cached_key`]: https://doc.rust-lang.org/std/primitive.slice.html#method.sort_by_cached_key
+pub struct WeakHeapByKey<T, K, F> {
+    data: Vec<(K, T)>,
+    bit: Vec<bool>,
+    key: F,
+}
+
+impl<T: Clone, K: Clone, F: Clone> Clone for WeakHeapByKey<T, K, F> {
+    fn clone(&self) -> Self {
+        WeakHeapByKey {
+            data: self.data.clone(),
+            bit: self.bit.clone(),
+            key: self.key.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, K: fmt::Debug, F> fmt::Debug for WeakHeapByKey<T, K, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.data.iter().map(|(k, v)| (k, v)))
+            .finish()
+    }
+}
+
+impl<T, K: Ord, F: FnMut(&T) -> K> WeakHeapByKey<T, K, F> {
+    /// Creates an empty `WeakHeapByKey` ordered by the keys extracted with `key`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapByKey;
+    /// let mut heap = WeakHeapByKey::new(|x: &i32| x.abs());
+    /// heap.push(-4);
+    /// ```
+    #[must_use]
+    pub fn new(key: F) -> WeakHeapByKey<T, K, F> {
+        WeakHeapByKey {
+            data: Vec::new(),
+            bit: Vec::new(),
+            key,
+        }
+    }
+
+    /// Creates an empty `WeakHeapByKey` with a specific capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapByKey;
+    /// let mut heap = WeakHeapByKey::with_capacity(10, |x: &i32| x.abs());
+    /// heap.push(-4);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize, key: F) -> WeakHeapByKey<T, K, F> {
+        WeakHeapByKey {
+            data: Vec::with_capacity(capacity),
+            bit: Vec::with_capacity(capacity),
+            key,
+        }
+    }
+
+    /// Builds a `WeakHeapByKey` from the elements of `vec`, extracting the key
+    /// of every element once.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapByKey;
+    ///
+    /// let heap = WeakHeapByKey::from_vec(vec![-7, 3, 5], |x: &i32| x.abs());
+    /// assert_eq!(heap.peek(), Some(&-7));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *n* key extractions and *n* - 1 comparisons.
+    pub fn from_vec(vec: Vec<T>, mut key: F) -> WeakHeapByKey<T, K, F> {
+        let mut data: Vec<(K, T)> = vec.into_iter().map(|item| (key(&item), item)).collect();
+        let mut bit = vec![false; data.len()];
+        raw::rebuild(&mut data, &mut bit, &mut key_lt);
+        WeakHeapByKey { data, bit, key }
+    }
+
+    /// Pushes an item onto the heap, extracting its key once.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapByKey;
+    /// let mut heap = WeakHeapByKey::new(|x: &i32| x.abs());
+    /// heap.push(3);
+    /// heap.push(-5);
+    ///
+    /// assert_eq!(heap.len(), 2);
+    /// assert_eq!(heap.peek(), Some(&-5));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost of `push` is *O*(1), the worst case is *O*(log(*n*)).
+    pub fn push(&mut self, item: T) {
+        let key = (self.key)(&item);
+        let old_len = self.data.len();
+        self.data.push((key, item));
+        self.bit.push(false);
+        // SAFETY: old_len < self.data.len().
+        unsafe { raw::sift_up(&mut self.data, &self.bit, old_len, &mut key_lt) };
+    }
+}
+
+impl<T, K: Ord, F> WeakHeapByKey<T, K, F> {
+    /// Removes the item with the greatest key and returns it, or `None` if
+    /// the heap is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapByKey;
+    /// let mut heap = WeakHeapByKey::from_vec(vec![1, -3], |x: &i32| x.abs());
+    ///
+    /// assert_eq!(heap.pop(), Some(-3));
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost of `pop` on a heap containing *n* elements is *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        self.pop_with_key().map(|(_, item)| item)
+    }
+
+    /// Removes the item with the greatest key and returns it together with
+    /// its key, or `None` if the heap is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapByKey;
+    /// let mut heap = WeakHeapByKey::from_vec(vec!["aa", "b"], |s: &&str| s.len());
+    ///
+    /// assert_eq!(heap.pop_with_key(), Some((2, "aa")));
+    /// ```
+    pub fn pop_with_key(&mut self) -> Option<(K, T)> {
+        self.bit.pop();
+        let mut entry = self.data.pop()?;
+        if !self.data.is_empty() {
+            swap(&mut entry, &mut self.data[0]);
+            let len = self.data.len();
+            // SAFETY: 0 < len == self.data.len().
+            unsafe { raw::sift_down_range(&mut self.data, &mut self.bit, 0, len, &mut key_lt) };
+        }
+        Some(entry)
+    }
+
+    /// Consumes the heap and returns a vector sorted in ascending order of the keys.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapByKey;
+    /// let heap = WeakHeapByKey::from_vec(vec![4, -1, 3, -2], |x: &i32| x.abs());
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [-1, -2, 3, 4]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut end = self.data.len();
+        while end > 1 {
+            end -= 1;
+            self.data.swap(0, end);
+            // SAFETY: 0 < end < self.data.len().
+            unsafe { raw::sift_down_range(&mut self.data, &mut self.bit, 0, end, &mut key_lt) };
+        }
+        self.into_vec()
+    }
+}
+
+impl<T, K, F> WeakHeapByKey<T, K, F> {
+    /// Returns the item with the greatest key, or `None` if the heap is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first().map(|(_, item)| item)
+    }
+
+    /// Returns the greatest cached key, or `None` if the heap is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek_key(&self) -> Option<&K> {
+        self.data.first().map(|(key, _)| key)
+    }
+
+    /// Returns an iterator visiting all values in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.data.iter().map(|(_, item)| item)
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Drops all items from the heap.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.bit.clear();
+    }
+
+    /// Consumes the heap and returns the elements in arbitrary order, without
+    /// their keys.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        self.data.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+impl<T, K: Ord, F: FnMut(&T) -> K> Extend<T> for WeakHeapByKey<T, K, F> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! A priority queue shared between threads, split into locked shards.
+
+use crate::{ignore_poison, WeakHeap};
+use alloc::vec::Vec;
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, TryLockError};
+
+/// A priority queue implemented with weak heaps, which can be shared between
+/// threads without wrapping it in a `Mutex`.
+///
+/// The elements are spread over several shards, each a `WeakHeap` behind its
+/// own lock. [`push`] takes the first shard it can lock without waiting, so
+/// concurrent pushes rarely contend. [`pop`] locks every shard and removes the
+/// greatest element of all, so it is exact but serializes the callers.
+/// [`pop_relaxed`] only looks at two shards and may return an element smaller
+/// than the greatest one, in exchange for much less contention.
+///
+/// This will be a max-heap.
+///
+/// Available with the `std` feature.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use weakheap::ConcurrentWeakHeap;
+///
+/// let queue = Arc::new(ConcurrentWeakHeap::new());
+/// let workers: Vec<_> = (0..4)
+///     .map(|id| {
+///         let queue = Arc::clone(&queue);
+///         thread::spawn(move || {
+///             for x in 0..100 {
+///                 queue.push(id * 100 + x);
+///             }
+///         })
+///     })
+///     .collect();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+///
+/// assert_eq!(queue.len(), 400);
+/// assert_eq!(queue.pop(), Some(399));
+/// ```
+///
+/// [`push`]: ConcurrentWeakHeap::push
+/// [`pop`]: ConcurrentWeakHeap::pop
+/// [`pop_relaxed`]: ConcurrentWeakHeap::pop_relaxed
+pub struct ConcurrentWeakHeap<T> {
+    shards: Vec<Mutex<WeakHeap<T>>>,
+    // Rotates the shard tried first, to spread the elements.
+    next: AtomicUsize,
+}
+
+impl<T> fmt::Debug for ConcurrentWeakHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConcurrentWeakHeap")
+            .field("shards", &self.shards.len())
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Ord> Default for ConcurrentWeakHeap<T> {
+    /// Creates an empty `ConcurrentWeakHeap`.
+    #[inline]
+    fn default() -> ConcurrentWeakHeap<T> {
+        ConcurrentWeakHeap::new()
+    }
+}
+
+impl<T: Ord> ConcurrentWeakHeap<T> {
+    /// Creates an empty `ConcurrentWeakHeap` with two shards per available
+    /// thread.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::ConcurrentWeakHeap;
+    /// let queue = ConcurrentWeakHeap::new();
+    /// queue.push(4);
+    /// ```
+    #[must_use]
+    pub fn new() -> ConcurrentWeakHeap<T> {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        ConcurrentWeakHeap::with_shards(2 * threads)
+    }
+
+    /// Creates an empty `ConcurrentWeakHeap` with `shards` shards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::ConcurrentWeakHeap;
+    /// let queue: ConcurrentWeakHeap<u32> = ConcurrentWeakHeap::with_shards(8);
+    /// assert_eq!(queue.shards(), 8);
+    /// ```
+    #[must_use]
+    pub fn with_shards(shards: usize) -> ConcurrentWeakHeap<T> {
+        assert!(shards > 0, "a ConcurrentWeakHeap needs at least one shard");
+        ConcurrentWeakHeap {
+            shards: (0..shards).map(|_| Mutex::new(WeakHeap::new())).collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Pushes an item onto the queue.
+    ///
+    /// The item goes to the first shard which can be locked without waiting,
+    /// or waits for one if all of them are locked.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::ConcurrentWeakHeap;
+    /// let queue = ConcurrentWeakHeap::new();
+    /// queue.push(3);
+    /// queue.push(5);
+    ///
+    /// assert_eq!(queue.len(), 2);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost is *O*(1), the worst case is *O*(log(*n*)), not
+    /// counting the wait for a lock.
+    pub fn push(&self, item: T) {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let len = self.shards.len();
+        for i in 0..len {
+            if let Some(mut shard) = self.try_lock((start + i) % len) {
+                shard.push(item);
+                return;
+            }
+        }
+        self.lock(start % len).push(item);
+    }
+
+    /// Removes the greatest item from the queue and returns it, or `None` if
+    /// it is empty.
+    ///
+    /// Every shard is locked until the greatest item is found, so concurrent
+    /// calls to `pop` and `push` wait for each other.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::ConcurrentWeakHeap;
+    /// let queue = ConcurrentWeakHeap::with_shards(4);
+    /// queue.push(1);
+    /// queue.push(7);
+    /// queue.push(3);
+    ///
+    /// assert_eq!(queue.pop(), Some(7));
+    /// assert_eq!(queue.pop(), Some(3));
+    /// assert_eq!(queue.pop(), Some(1));
+    /// assert_eq!(queue.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*s* + log(*n*)) for *s* shards.
+    pub fn pop(&self) -> Option<T> {
+        // Locking in index order can't deadlock with another `pop`.
+        let mut shards: Vec<_> = (0..self.shards.len()).map(|i| self.lock(i)).collect();
+        let mut best: Option<usize> = None;
+        for (i, shard) in shards.iter().enumerate() {
+            let greater = match (shard.peek(), best) {
+                (None, _) => false,
+                (Some(_), None) => true,
+                (Some(top), Some(best)) => shards[best].peek().is_some_and(|b| b < top),
+            };
+            if greater {
+                best = Some(i);
+            }
+        }
+        best.and_then(|i| shards[i].pop())
+    }
+
+    /// Removes a large item from the queue and returns it, or `None` if it
+    /// is empty.
+    ///
+    /// Only two shards are compared, and the greater of their tops is
+    /// removed, so the item is not necessarily the greatest of the queue.
+    /// Shards locked by other threads are skipped. If the two shards are
+    /// empty or locked, this falls back to [`pop`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::ConcurrentWeakHeap;
+    /// let queue = ConcurrentWeakHeap::with_shards(4);
+    /// queue.extend_from([1, 5, 2, 4]);
+    ///
+    /// let mut popped = Vec::new();
+    /// while let Some(x) = queue.pop_relaxed() {
+    ///     popped.push(x);
+    /// }
+    /// popped.sort();
+    /// assert_eq!(popped, [1, 2, 4, 5]);
+    /// ```
+    ///
+    /// [`pop`]: ConcurrentWeakHeap::pop
+    pub fn pop_relaxed(&self) -> Option<T> {
+        let len = self.shards.len();
+        let first = self.next.fetch_add(1, Ordering::Relaxed) % len;
+        let second = (first + len / 2) % len;
+
+        let a = self.try_lock(first).filter(|shard| !shard.is_empty());
+        let b = self
+            .try_lock(second)
+            .filter(|shard| second != first && !shard.is_empty());
+        match (a, b) {
+            (Some(mut a), Some(mut b)) => {
+                if a.peek() < b.peek() {
+                    b.pop()
+                } else {
+                    a.pop()
+                }
+            }
+            (Some(mut shard), None) | (None, Some(mut shard)) => shard.pop(),
+            (None, None) => self.pop(),
+        }
+    }
+
+    /// Pushes all the items of `iter` onto the queue, locking a single shard.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::ConcurrentWeakHeap;
+    /// let queue = ConcurrentWeakHeap::new();
+    /// queue.extend_from(vec![1, 2, 3]);
+    ///
+    /// assert_eq!(queue.len(), 3);
+    /// ```
+    pub fn extend_from<I: IntoIterator<Item = T>>(&self, iter: I) {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        self.lock(start % self.shards.len()).extend(iter);
+    }
+
+    /// Consumes the queue and returns a single heap holding all its elements.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::ConcurrentWeakHeap;
+    /// let queue = ConcurrentWeakHeap::new();
+    /// queue.extend_from(vec![4, 1, 3]);
+    ///
+    /// assert_eq!(queue.into_heap().into_sorted_vec(), [1, 3, 4]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_heap(self) -> WeakHeap<T> {
+        let mut heap = WeakHeap::new();
+        for shard in self.shards {
+            heap.append(&mut ignore_poison(shard.into_inner()));
+        }
+        heap
+    }
+}
+
+impl<T> ConcurrentWeakHeap<T> {
+    /// Returns the number of elements in the queue.
+    ///
+    /// The shards are counted one after another, so the result may be
+    /// outdated when other threads modify the queue meanwhile.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        (0..self.shards.len()).map(|i| self.lock(i).len()).sum()
+    }
+
+    /// Checks if the queue is empty, with the same caveat as [`len`].
+    ///
+    /// [`len`]: ConcurrentWeakHeap::len
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        (0..self.shards.len()).all(|i| self.lock(i).is_empty())
+    }
+
+    /// Returns the number of shards.
+    #[must_use]
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Drops all items from the queue.
+    pub fn clear(&self) {
+        for i in 0..self.shards.len() {
+            self.lock(i).clear();
+        }
+    }
+
+    fn lock(&self, shard: usize) -> MutexGuard<'_, WeakHeap<T>> {
+        ignore_poison(self.shards[shard].lock())
+    }
+
+    fn try_lock(&self, shard: usize) -> Option<MutexGuard<'_, WeakHeap<T>>> {
+        match self.shards[shard].try_lock() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(err)) => Some(ignore_poison(Err(err))),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+}
@@ -0,0 +1,354 @@
+//! A priority queue that merges duplicate elements into counted entries.
+
+use crate::WeakHeap;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+use core::iter::FromIterator;
+use std::collections::HashMap;
+
+/// A priority queue implemented with a weak heap, which stores every distinct
+/// element once together with the number of its occurrences.
+///
+/// Pushing a value that is already present only increments its count, and
+/// popping it only decrements the count until the last occurrence is removed,
+/// so the heap is restructured only when the set of distinct values changes.
+/// For workloads with massive duplication this shrinks both memory usage and
+/// the number of comparisons.
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::CountingWeakHeap;
+///
+/// let mut heap = CountingWeakHeap::new();
+/// heap.push("b");
+/// heap.push("a");
+/// heap.push("b");
+///
+/// assert_eq!(heap.len(), 3);
+/// assert_eq!(heap.distinct_len(), 2);
+/// assert_eq!(heap.peek(), Some((&"b", 2)));
+///
+/// assert_eq!(heap.pop(), Some("b"));
+/// assert_eq!(heap.pop(), Some("b"));
+/// assert_eq!(heap.pop(), Some("a"));
+/// assert_eq!(heap.pop(), None);
+/// ```
+pub struct CountingWeakHeap<T> {
+    heap: WeakHeap<T>,
+    counts: HashMap<T, usize>,
+    len: usize,
+}
+
+impl<T: Clone> Clone for CountingWeakHeap<T> {
+    fn clone(&self) -> Self {
+        CountingWeakHeap {
+            heap: self.heap.clone(),
+            counts: self.counts.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T: fmt::Debug + Hash + Eq> fmt::Debug for CountingWeakHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.heap.iter().map(|item| (item, self.counts[item])))
+            .finish()
+    }
+}
+
+impl<T: Ord + Hash + Clone> Default for CountingWeakHeap<T> {
+    /// Creates an empty `CountingWeakHeap`.
+    #[inline]
+    fn default() -> CountingWeakHeap<T> {
+        CountingWeakHeap::new()
+    }
+}
+
+impl<T: Ord + Hash + Clone> CountingWeakHeap<T> {
+    /// Creates an empty `CountingWeakHeap` as a max-heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::CountingWeakHeap;
+    /// let mut heap = CountingWeakHeap::new();
+    /// assert!(heap.is_empty());
+    ///
+    /// heap.push(4);
+    /// assert_eq!(heap.len(), 1);
+    /// ```
+    #[must_use]
+    pub fn new() -> CountingWeakHeap<T> {
+        CountingWeakHeap {
+            heap: WeakHeap::new(),
+            counts: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    /// Creates an empty `CountingWeakHeap` with space for at least `capacity`
+    /// distinct elements.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::CountingWeakHeap;
+    /// let mut heap = CountingWeakHeap::with_capacity(10);
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> CountingWeakHeap<T> {
+        CountingWeakHeap {
+            heap: WeakHeap::with_capacity(capacity),
+            counts: HashMap::with_capacity(capacity),
+            len: 0,
+        }
+    }
+
+    /// Pushes an item onto the heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::CountingWeakHeap;
+    /// let mut heap = CountingWeakHeap::new();
+    /// heap.push(3);
+    /// heap.push(3);
+    ///
+    /// assert_eq!(heap.count(&3), 2);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Pushing a value which is already present is *O*(1), otherwise the
+    /// cost is that of [`WeakHeap::push`].
+    pub fn push(&mut self, item: T) {
+        self.push_n(item, 1);
+    }
+
+    /// Pushes `count` occurrences of an item onto the heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::CountingWeakHeap;
+    /// let mut heap = CountingWeakHeap::new();
+    /// heap.push_n(7, 3);
+    ///
+    /// assert_eq!(heap.len(), 3);
+    /// assert_eq!(heap.distinct_len(), 1);
+    /// ```
+    pub fn push_n(&mut self, item: T, count: usize) {
+        if count == 0 {
+            return;
+        }
+        self.len += count;
+        if let Some(c) = self.counts.get_mut(&item) {
+            *c += count;
+        } else {
+            self.counts.insert(item.clone(), count);
+            self.heap.push(item);
+        }
+    }
+
+    /// Removes one occurrence of the greatest item from the heap and returns it,
+    /// or `None` if it is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::CountingWeakHeap;
+    /// let mut heap: CountingWeakHeap<_> = vec![1, 3, 3].into_iter().collect();
+    ///
+    /// assert_eq!(heap.pop(), Some(3));
+    /// assert_eq!(heap.pop(), Some(3));
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) while the greatest item has more than one occurrence,
+    /// otherwise *O*(log(*n*)), where *n* is the number of distinct items.
+    pub fn pop(&mut self) -> Option<T> {
+        let top = self.heap.peek()?;
+        let count = self.counts.get_mut(top)?;
+        self.len -= 1;
+        if *count > 1 {
+            *count -= 1;
+            Some(top.clone())
+        } else {
+            let item = self.heap.pop()?;
+            self.counts.remove(&item);
+            Some(item)
+        }
+    }
+
+    /// Removes all occurrences of the greatest item from the heap and returns
+    /// the item with its count, or `None` if it is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::CountingWeakHeap;
+    /// let mut heap: CountingWeakHeap<_> = vec![1, 3, 3].into_iter().collect();
+    ///
+    /// assert_eq!(heap.pop_entry(), Some((3, 2)));
+    /// assert_eq!(heap.pop_entry(), Some((1, 1)));
+    /// assert_eq!(heap.pop_entry(), None);
+    /// ```
+    pub fn pop_entry(&mut self) -> Option<(T, usize)> {
+        let item = self.heap.pop()?;
+        let count = self.counts.remove(&item)?;
+        self.len -= count;
+        Some((item, count))
+    }
+
+    /// Returns the number of occurrences of `value` in the heap.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1).
+    #[must_use]
+    pub fn count<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.counts.get(value).copied().unwrap_or(0)
+    }
+
+    /// Returns `true` if the heap contains `value`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::CountingWeakHeap;
+    /// let heap: CountingWeakHeap<_> = vec![1, 3, 3].into_iter().collect();
+    ///
+    /// assert!(heap.contains(&3));
+    /// assert!(!heap.contains(&2));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1).
+    #[must_use]
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.counts.contains_key(value)
+    }
+
+    /// Returns the greatest item in the heap with the number of its
+    /// occurrences, or `None` if it is empty.
+    #[must_use]
+    pub fn peek(&self) -> Option<(&T, usize)> {
+        let top = self.heap.peek()?;
+        Some((top, self.counts[top]))
+    }
+
+    /// Returns an iterator visiting all distinct values with their counts,
+    /// in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&T, usize)> + '_ {
+        self.heap.iter().map(move |item| (item, self.counts[item]))
+    }
+
+    /// Consumes the heap and returns the distinct values with their counts in
+    /// sorted (ascending) order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::CountingWeakHeap;
+    /// let heap: CountingWeakHeap<_> = vec![4, 1, 4, 3].into_iter().collect();
+    /// assert_eq!(heap.into_sorted_vec(), [(1, 1), (3, 1), (4, 2)]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<(T, usize)> {
+        let counts = &mut self.counts;
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|item| {
+                let count = counts.remove(&item).unwrap_or(0);
+                (item, count)
+            })
+            .collect()
+    }
+}
+
+impl<T> CountingWeakHeap<T> {
+    /// Returns the total number of elements in the heap, counting duplicates.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the number of distinct elements in the heap.
+    #[must_use]
+    pub fn distinct_len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Drops all items from the heap.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.counts.clear();
+        self.len = 0;
+    }
+}
+
+impl<T: Ord + Hash + Clone> FromIterator<T> for CountingWeakHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> CountingWeakHeap<T> {
+        let mut counts = HashMap::new();
+        let mut len = 0;
+        for item in iter {
+            *counts.entry(item).or_insert(0) += 1;
+            len += 1;
+        }
+        CountingWeakHeap {
+            heap: counts.keys().cloned().collect(),
+            counts,
+            len,
+        }
+    }
+}
+
+impl<T: Ord + Hash + Clone> Extend<T> for CountingWeakHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! The error type of the fallible operations.
+
+use alloc::collections::TryReserveError;
+use core::fmt;
+
+/// The error type for fallible operations on weak heaps, and for the
+/// violations of the weak heap invariants found by
+/// [`WeakHeap::check_integrity`](crate::WeakHeap::check_integrity).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WeakHeapError {
+    /// Memory could not be reserved, either because the capacity would
+    /// overflow or because the allocator reported a failure.
+    TryReserve(TryReserveError),
+    /// The elements and the reverse bits of a heap have different lengths.
+    LengthMismatch {
+        /// Number of elements.
+        len: usize,
+        /// Number of reverse bits.
+        bits: usize,
+    },
+    /// The reverse bit of the root is set. The root has a single child, so
+    /// its bit is always clear.
+    RootBitSet,
+    /// An element is greater than its distinguished ancestor.
+    HeapOrder {
+        /// Index of the element.
+        pos: usize,
+        /// Index of its distinguished ancestor.
+        ancestor: usize,
+    },
+}
+
+impl fmt::Display for WeakHeapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeakHeapError::TryReserve(err) => err.fmt(f),
+            WeakHeapError::LengthMismatch { len, bits } => {
+                write!(f, "the heap has {} elements but {} reverse bits", len, bits)
+            }
+            WeakHeapError::RootBitSet => f.write_str("the reverse bit of the root is set"),
+            WeakHeapError::HeapOrder { pos, ancestor } => write!(
+                f,
+                "the element at {} is greater than its distinguished ancestor at {}",
+                pos, ancestor
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WeakHeapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WeakHeapError::TryReserve(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<TryReserveError> for WeakHeapError {
+    fn from(err: TryReserveError) -> Self {
+        WeakHeapError::TryReserve(err)
+    }
+}
@@ -0,0 +1,323 @@
+//! A priority queue ordered by a comparator which can fail.
+
+use crate::{raw, Iter};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+
+/// A comparison which can fail, for instance because it consults external
+/// data that may be missing.
+///
+/// It is implemented for every closure `FnMut(&T, &T) -> Result<Ordering, E>`.
+pub trait TryCompare<T> {
+    /// The error returned when the comparison fails.
+    type Error;
+
+    /// Compares `a` with `b`.
+    fn try_cmp(&mut self, a: &T, b: &T) -> Result<Ordering, Self::Error>;
+}
+
+impl<T, E, F> TryCompare<T> for F
+where
+    F: FnMut(&T, &T) -> Result<Ordering, E>,
+{
+    type Error = E;
+
+    #[inline]
+    fn try_cmp(&mut self, a: &T, b: &T) -> Result<Ordering, E> {
+        self(a, b)
+    }
+}
+
+/// A priority queue implemented with a weak heap, ordered by a fallible
+/// comparator.
+///
+/// When a comparison fails, the error is propagated and the heap is left
+/// exactly as it was before the operation: all comparisons are carried out
+/// before any element is moved.
+///
+/// This will be a max-heap with respect to the comparator.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use weakheap::TryWeakHeap;
+///
+/// let priority: HashMap<&str, u32> = [("low", 1), ("high", 9)].into_iter().collect();
+/// let cmp = |a: &&str, b: &&str| match (priority.get(a), priority.get(b)) {
+///     (Some(x), Some(y)) => Ok(x.cmp(y)),
+///     _ => Err("unknown task"),
+/// };
+///
+/// let mut heap = TryWeakHeap::new(cmp);
+/// heap.try_push("low").unwrap();
+/// heap.try_push("high").unwrap();
+/// assert_eq!(heap.try_push("other"), Err(("other", "unknown task")));
+///
+/// assert_eq!(heap.len(), 2);
+/// assert_eq!(heap.try_pop(), Ok(Some("high")));
+/// assert_eq!(heap.try_pop(), Ok(Some("low")));
+/// assert_eq!(heap.try_pop(), Ok(None));
+/// ```
+pub struct TryWeakHeap<T, C> {
+    data: Vec<T>,
+    bit: Vec<bool>,
+    cmp: C,
+}
+
+impl<T: Clone, C: Clone> Clone for TryWeakHeap<T, C> {
+    fn clone(&self) -> Self {
+        TryWeakHeap {
+            data: self.data.clone(),
+            bit: self.bit.clone(),
+            cmp: self.cmp.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for TryWeakHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.data.iter()).finish()
+    }
+}
+
+impl<T, C: TryCompare<T>> TryWeakHeap<T, C> {
+    /// Creates an empty `TryWeakHeap` ordered by `cmp`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::TryWeakHeap;
+    /// let mut heap = TryWeakHeap::new(|a: &i32, b: &i32| Ok::<_, ()>(a.cmp(b)));
+    /// heap.try_push(4).unwrap();
+    /// ```
+    #[must_use]
+    pub fn new(cmp: C) -> TryWeakHeap<T, C> {
+        TryWeakHeap {
+            data: Vec::new(),
+            bit: Vec::new(),
+            cmp,
+        }
+    }
+
+    /// Builds a `TryWeakHeap` ordered by `cmp` from the elements of `vec`.
+    ///
+    /// If a comparison fails, the error is returned together with the
+    /// elements, in arbitrary order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::TryWeakHeap;
+    ///
+    /// let cmp = |a: &i32, b: &i32| if *a < 0 || *b < 0 { Err("negative") } else { Ok(a.cmp(b)) };
+    /// let heap = TryWeakHeap::try_from_vec(vec![3, 1, 2], cmp).unwrap();
+    /// assert_eq!(heap.peek(), Some(&3));
+    ///
+    /// let (vec, err) = TryWeakHeap::try_from_vec(vec![3, -1, 2], cmp).unwrap_err();
+    /// assert_eq!(err, "negative");
+    /// assert_eq!(vec.len(), 3);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Building takes *n* - 1 comparisons.
+    pub fn try_from_vec(vec: Vec<T>, mut cmp: C) -> Result<TryWeakHeap<T, C>, (Vec<T>, C::Error)> {
+        let mut error = None;
+        let mut data = vec;
+        let mut bit = vec![false; data.len()];
+        raw::rebuild(&mut data, &mut bit, &mut |a, b| {
+            if error.is_some() {
+                return false;
+            }
+            match cmp.try_cmp(a, b) {
+                Ok(ord) => ord == Ordering::Less,
+                Err(err) => {
+                    error = Some(err);
+                    false
+                }
+            }
+        });
+        match error {
+            Some(err) => Err((data, err)),
+            None => Ok(TryWeakHeap { data, bit, cmp }),
+        }
+    }
+
+    /// Pushes an item onto the heap.
+    ///
+    /// If a comparison fails, the error is returned together with `item`, and
+    /// the heap is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::TryWeakHeap;
+    ///
+    /// let cmp = |a: &i32, b: &i32| if *a < 0 || *b < 0 { Err("negative") } else { Ok(a.cmp(b)) };
+    /// let mut heap = TryWeakHeap::new(cmp);
+    /// heap.try_push(3).unwrap();
+    /// heap.try_push(5).unwrap();
+    /// assert_eq!(heap.try_push(-1), Err((-1, "negative")));
+    ///
+    /// assert_eq!(heap.len(), 2);
+    /// assert_eq!(heap.peek(), Some(&5));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn try_push(&mut self, item: T) -> Result<(), (T, C::Error)> {
+        let end = self.data.len();
+        self.bit.push(false);
+
+        // Find the final position of `item` without moving anything.
+        let mut target = end;
+        while target > 0 {
+            // SAFETY: 0 < target <= end < self.bit.len().
+            let ancestor = unsafe { raw::d_ancestor(&self.bit, target) };
+            match self.cmp.try_cmp(&self.data[ancestor], &item) {
+                Ok(Ordering::Less) => target = ancestor,
+                Ok(_) => break,
+                Err(err) => {
+                    self.bit.pop();
+                    return Err((item, err));
+                }
+            }
+        }
+
+        self.data.push(item);
+        let mut pos = end;
+        while pos != target {
+            // SAFETY: 0 < pos < self.bit.len().
+            let ancestor = unsafe { raw::d_ancestor(&self.bit, pos) };
+            self.data.swap(ancestor, pos);
+            pos = ancestor;
+        }
+        Ok(())
+    }
+
+    /// Removes the greatest item from the heap and returns it, or `None` if it
+    /// is empty.
+    ///
+    /// If a comparison fails, the error is returned and the heap is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::cell::Cell;
+    /// use weakheap::TryWeakHeap;
+    ///
+    /// let fail = Cell::new(false);
+    /// let cmp = |a: &i32, b: &i32| if fail.get() { Err("poisoned") } else { Ok(a.cmp(b)) };
+    /// let mut heap = TryWeakHeap::try_from_vec(vec![1, 5, 2, 4], cmp).unwrap();
+    ///
+    /// fail.set(true);
+    /// assert_eq!(heap.try_pop(), Err("poisoned"));
+    /// assert_eq!(heap.len(), 4);
+    ///
+    /// fail.set(false);
+    /// assert_eq!(heap.try_pop(), Ok(Some(5)));
+    /// assert_eq!(heap.try_pop(), Ok(Some(4)));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn try_pop(&mut self) -> Result<Option<T>, C::Error> {
+        let len = self.data.len();
+        if len <= 1 {
+            self.bit.pop();
+            return Ok(self.data.pop());
+        }
+
+        // The last element replaces the root and is sifted down. Record the
+        // positions the sifting would swap with the root.
+        let last = len - 1;
+        let mut bottom = if last > 1 { 1 } else { 0 };
+        while bottom > 0 && bottom * 2 + (self.bit[bottom] as usize) < last {
+            bottom = 2 * bottom + (self.bit[bottom] as usize);
+        }
+        // Bit `level` of `swaps` is set if the root is swapped with the
+        // element at `bottom >> level`. The path is shorter than
+        // `usize::BITS`, since its nodes are distinct positive `usize` values.
+        let mut swaps: usize = 0;
+        let mut root = last;
+        let mut level = 0;
+        while bottom >> level > 0 {
+            let cur = bottom >> level;
+            match self.cmp.try_cmp(&self.data[root], &self.data[cur]) {
+                Ok(Ordering::Less) => {
+                    swaps |= 1 << level;
+                    root = cur;
+                }
+                Ok(_) => {}
+                Err(err) => return Err(err),
+            }
+            level += 1;
+        }
+
+        self.bit.pop();
+        let item = self.data.swap_remove(0);
+        while swaps != 0 {
+            let pos = bottom >> swaps.trailing_zeros();
+            swaps &= swaps - 1;
+            self.bit[pos] ^= true;
+            self.data.swap(0, pos);
+        }
+        Ok(Some(item))
+    }
+}
+
+impl<T, C> TryWeakHeap<T, C> {
+    /// Returns the greatest item in the heap, or `None` if it is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Returns an iterator visiting all values in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            iter: self.data.iter(),
+        }
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Drops all items from the heap.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.bit.clear();
+    }
+
+    /// Consumes the heap and returns the underlying vector in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+}
@@ -0,0 +1,113 @@
+//! A trait abstracting over priority queues.
+
+use crate::WeakHeap;
+use alloc::collections::BinaryHeap;
+
+/// A max-priority queue, implemented by [`WeakHeap`] and by the standard
+/// [`BinaryHeap`], so that code can be generic over the backing heap.
+///
+/// Elements are added in bulk through the [`Extend`] supertrait.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BinaryHeap;
+/// use weakheap::{Heap, WeakHeap};
+///
+/// fn top_two<H: Heap<u32>>(mut heap: H, items: &[u32]) -> (Option<u32>, Option<u32>) {
+///     heap.extend(items.iter().copied());
+///     (heap.pop(), heap.pop())
+/// }
+///
+/// let items = [4, 9, 1, 7];
+/// assert_eq!(top_two(WeakHeap::new(), &items), (Some(9), Some(7)));
+/// assert_eq!(top_two(BinaryHeap::new(), &items), (Some(9), Some(7)));
+/// ```
+///
+/// [`BinaryHeap`]: alloc::collections::BinaryHeap
+pub trait Heap<T>: Extend<T> {
+    /// Pushes an item onto the heap.
+    fn push(&mut self, item: T);
+
+    /// Removes the greatest item from the heap and returns it, or `None` if
+    /// it is empty.
+    fn pop(&mut self) -> Option<T>;
+
+    /// Returns the greatest item in the heap, or `None` if it is empty.
+    fn peek(&self) -> Option<&T>;
+
+    /// Returns the number of elements in the heap.
+    fn len(&self) -> usize;
+
+    /// Checks if the heap is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all items from the heap.
+    fn clear(&mut self);
+}
+
+impl<T: Ord> Heap<T> for WeakHeap<T> {
+    #[inline]
+    fn push(&mut self, item: T) {
+        WeakHeap::push(self, item);
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<T> {
+        WeakHeap::pop(self)
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&T> {
+        WeakHeap::peek(self)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        WeakHeap::len(self)
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        WeakHeap::is_empty(self)
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        WeakHeap::clear(self);
+    }
+}
+
+impl<T: Ord> Heap<T> for BinaryHeap<T> {
+    #[inline]
+    fn push(&mut self, item: T) {
+        BinaryHeap::push(self, item);
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<T> {
+        BinaryHeap::pop(self)
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&T> {
+        BinaryHeap::peek(self)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        BinaryHeap::len(self)
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        BinaryHeap::is_empty(self)
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        BinaryHeap::clear(self);
+    }
+}
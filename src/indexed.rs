@@ -0,0 +1,425 @@
+//! An addressable priority queue.
+
+use crate::raw;
+use crate::tracked::{Slab, Tracked};
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+
+/// A handle to an element of an [`IndexedWeakHeap`] or a [`RelaxedWeakQueue`],
+/// returned by their `push` methods.
+///
+/// A handle stays valid until its element is removed from the heap. After
+/// that, it may be reused for a newly pushed element.
+///
+/// [`RelaxedWeakQueue`]: crate::RelaxedWeakQueue
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Handle(pub(crate) usize);
+
+/// A priority queue implemented with a weak heap, whose elements can be
+/// located, reprioritized and removed after insertion through a [`Handle`].
+///
+/// Changing or removing an element re-sifts only the path it lies on, in
+/// *O*(log(*n*)), which is what algorithms such as Dijkstra's or Prim's and
+/// cancellable event queues need.
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::IndexedWeakHeap;
+///
+/// let mut heap = IndexedWeakHeap::new();
+/// let a = heap.push(10);
+/// let b = heap.push(20);
+/// let c = heap.push(30);
+///
+/// heap.increase_key(a, 40);
+/// assert_eq!(heap.remove(c), Some(30));
+///
+/// assert_eq!(heap.pop(), Some(40));
+/// assert_eq!(heap.get(b), Some(&20));
+/// ```
+pub struct IndexedWeakHeap<T> {
+    items: Slab<T>,
+    heap: Tracked,
+}
+
+impl<T: Clone> Clone for IndexedWeakHeap<T> {
+    fn clone(&self) -> Self {
+        IndexedWeakHeap {
+            items: self.items.clone(),
+            heap: self.heap.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for IndexedWeakHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> Default for IndexedWeakHeap<T> {
+    /// Creates an empty `IndexedWeakHeap`.
+    #[inline]
+    fn default() -> IndexedWeakHeap<T> {
+        IndexedWeakHeap::new()
+    }
+}
+
+// Compares the elements of two handles.
+#[inline]
+fn less<T: Ord>(items: &Slab<T>) -> impl FnMut(usize, usize) -> bool + '_ {
+    move |a, b| raw::lt(items.at(a), items.at(b))
+}
+
+impl<T: Ord> IndexedWeakHeap<T> {
+    /// Creates an empty `IndexedWeakHeap` as a max-heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::IndexedWeakHeap;
+    /// let mut heap = IndexedWeakHeap::new();
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn new() -> IndexedWeakHeap<T> {
+        IndexedWeakHeap {
+            items: Slab::default(),
+            heap: Tracked::default(),
+        }
+    }
+
+    /// Creates an empty `IndexedWeakHeap` with a specific capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::IndexedWeakHeap;
+    /// let mut heap = IndexedWeakHeap::with_capacity(10);
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> IndexedWeakHeap<T> {
+        IndexedWeakHeap {
+            items: Slab::with_capacity(capacity),
+            heap: Tracked::with_capacity(capacity),
+        }
+    }
+
+    /// Pushes an item onto the heap and returns its handle.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::IndexedWeakHeap;
+    /// let mut heap = IndexedWeakHeap::new();
+    /// let handle = heap.push(3);
+    /// heap.push(5);
+    ///
+    /// assert_eq!(heap.get(handle), Some(&3));
+    /// assert_eq!(heap.peek(), Some(&5));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost of `push` is *O*(1), the worst case is *O*(log(*n*)).
+    pub fn push(&mut self, item: T) -> Handle {
+        let handle = self.items.insert(item);
+        self.heap.push(handle, &mut less(&self.items));
+        Handle(handle)
+    }
+
+    /// Removes the greatest item from the heap and returns it, or `None` if it
+    /// is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::IndexedWeakHeap;
+    /// let mut heap = IndexedWeakHeap::new();
+    /// heap.push(1);
+    /// heap.push(3);
+    ///
+    /// assert_eq!(heap.pop(), Some(3));
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost of `pop` on a heap containing *n* elements is *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        self.pop_with_handle().map(|(_, item)| item)
+    }
+
+    /// Removes the greatest item from the heap and returns it together with
+    /// its handle, or `None` if the heap is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::IndexedWeakHeap;
+    /// let mut heap = IndexedWeakHeap::new();
+    /// let handle = heap.push(7);
+    ///
+    /// assert_eq!(heap.pop_with_handle(), Some((handle, 7)));
+    /// ```
+    pub fn pop_with_handle(&mut self) -> Option<(Handle, T)> {
+        let handle = Handle(self.heap.top()?);
+        self.remove(handle).map(|item| (handle, item))
+    }
+
+    /// Removes the element of `handle` from the heap and returns it, or `None`
+    /// if the handle does not refer to an element of the heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::IndexedWeakHeap;
+    /// let mut heap = IndexedWeakHeap::new();
+    /// let handle = heap.push(3);
+    /// heap.push(5);
+    ///
+    /// assert_eq!(heap.remove(handle), Some(3));
+    /// assert_eq!(heap.remove(handle), None);
+    /// assert_eq!(heap.len(), 1);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        if !self.contains(handle) {
+            return None;
+        }
+        self.heap.remove(handle.0, &mut less(&self.items));
+        Some(self.items.remove(handle.0))
+    }
+
+    /// Replaces the element of `handle` with `item`, restoring the heap
+    /// property, and returns the old element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handle does not refer to an element of the heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::IndexedWeakHeap;
+    /// let mut heap = IndexedWeakHeap::new();
+    /// let a = heap.push(3);
+    /// let b = heap.push(5);
+    ///
+    /// assert_eq!(heap.update(b, 1), 5);
+    /// assert_eq!(heap.peek(), Some(&3));
+    /// assert_eq!(heap.update(a, 0), 3);
+    /// assert_eq!(heap.peek(), Some(&1));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn update(&mut self, handle: Handle, item: T) -> T {
+        let old = self.replace(handle, item);
+        self.heap.update(handle.0, &mut less(&self.items));
+        old
+    }
+
+    /// Replaces the element of `handle` with the not smaller `item` and returns
+    /// the old element. Only the path above the element is re-sifted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handle does not refer to an element of the heap, or if
+    /// `item` is less than the current element.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::IndexedWeakHeap;
+    /// let mut heap = IndexedWeakHeap::new();
+    /// let handle = heap.push(3);
+    /// heap.push(5);
+    ///
+    /// heap.increase_key(handle, 8);
+    /// assert_eq!(heap.peek(), Some(&8));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn increase_key(&mut self, handle: Handle, item: T) -> T {
+        assert!(
+            !raw::lt(&item, self.get(handle).expect("invalid handle")),
+            "new element is less than the current one"
+        );
+        let old = self.replace(handle, item);
+        self.heap.increased(handle.0, &mut less(&self.items));
+        old
+    }
+
+    /// Replaces the element of `handle` with the not greater `item` and returns
+    /// the old element. Only the subtree below the element is re-sifted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handle does not refer to an element of the heap, or if
+    /// `item` is greater than the current element.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::IndexedWeakHeap;
+    /// let mut heap = IndexedWeakHeap::new();
+    /// let handle = heap.push(5);
+    /// heap.push(3);
+    ///
+    /// heap.decrease_key(handle, 1);
+    /// assert_eq!(heap.peek(), Some(&3));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn decrease_key(&mut self, handle: Handle, item: T) -> T {
+        assert!(
+            !raw::lt(self.get(handle).expect("invalid handle"), &item),
+            "new element is greater than the current one"
+        );
+        let old = self.replace(handle, item);
+        self.heap.decreased(handle.0, &mut less(&self.items));
+        old
+    }
+
+    /// Consumes the heap and returns a vector in sorted (ascending) order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::IndexedWeakHeap;
+    /// let mut heap = IndexedWeakHeap::new();
+    /// heap.extend(vec![4, 1, 3, 2]);
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut vec = Vec::with_capacity(self.len());
+        while let Some(item) = self.pop() {
+            vec.push(item);
+        }
+        vec.reverse();
+        vec
+    }
+
+    fn replace(&mut self, handle: Handle, item: T) -> T {
+        let slot = match self.items.get_mut(handle.0) {
+            Some(slot) if self.heap.contains(handle.0) => slot,
+            _ => panic!("invalid handle"),
+        };
+        mem::replace(slot, item)
+    }
+}
+
+impl<T> IndexedWeakHeap<T> {
+    /// Returns the greatest item in the heap, or `None` if it is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.peek_with_handle().map(|(_, item)| item)
+    }
+
+    /// Returns the greatest item in the heap together with its handle, or
+    /// `None` if the heap is empty.
+    #[must_use]
+    pub fn peek_with_handle(&self) -> Option<(Handle, &T)> {
+        self.heap
+            .top()
+            .map(|handle| (Handle(handle), self.items.at(handle)))
+    }
+
+    /// Returns the element of `handle`, or `None` if the handle does not
+    /// refer to an element of the heap.
+    #[must_use]
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.items.get(handle.0)
+    }
+
+    /// Checks if `handle` refers to an element of the heap.
+    #[must_use]
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.heap.contains(handle.0)
+    }
+
+    /// Returns an iterator visiting all handles and values in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (Handle, &T)> + '_ {
+        self.heap
+            .handles()
+            .iter()
+            .map(move |&handle| (Handle(handle), self.items.at(handle)))
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all items from the heap, invalidating all handles.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.heap.clear();
+    }
+
+    /// Consumes the heap and returns its elements in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(mut self) -> Vec<T> {
+        let handles = mem::take(&mut self.heap).handles().to_vec();
+        handles
+            .into_iter()
+            .map(|handle| self.items.remove(handle))
+            .collect()
+    }
+}
+
+impl<T: Ord> Extend<T> for IndexedWeakHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//! A weak heap storing every element next to its reverse bit.
+
+use crate::raw;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FromIterator;
+use core::mem::swap;
+
+#[derive(Clone)]
+struct Node<T> {
+    item: T,
+    bit: bool,
+}
+
+/// A priority queue implemented with a weak heap whose elements and reverse
+/// bits share a single allocation, each element stored next to its bit.
+///
+/// [`WeakHeap`] keeps the elements and the packed reverse bits in two
+/// separate buffers, so visiting a node touches two cache lines. Here a node
+/// is a single `(T, bool)` slot, which saves a cache miss per visited node
+/// when `T` is small and the heap doesn't fit in the cache.
+///
+/// The price is memory: the bit occupies a whole byte and the slot is padded
+/// to the alignment of `T`, so a heap of `u64` takes 16 bytes per element
+/// instead of about 8. For large `T`, or for comparisons that dominate the
+/// memory traffic anyway, [`WeakHeap`] is the better choice. The sifting
+/// algorithms and the number of comparisons are the same.
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::InterleavedWeakHeap;
+///
+/// let mut heap = InterleavedWeakHeap::from(vec![3u32, 1, 4, 1, 5]);
+/// heap.push(9);
+///
+/// assert_eq!(heap.pop(), Some(9));
+/// assert_eq!(heap.peek(), Some(&5));
+/// assert_eq!(heap.into_sorted_vec(), [1, 1, 3, 4, 5]);
+/// ```
+///
+/// [`WeakHeap`]: crate::WeakHeap
+pub struct InterleavedWeakHeap<T> {
+    nodes: Vec<Node<T>>,
+}
+
+impl<T: Clone> Clone for InterleavedWeakHeap<T> {
+    fn clone(&self) -> Self {
+        InterleavedWeakHeap {
+            nodes: self.nodes.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for InterleavedWeakHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> Default for InterleavedWeakHeap<T> {
+    /// Creates an empty `InterleavedWeakHeap`.
+    #[inline]
+    fn default() -> InterleavedWeakHeap<T> {
+        InterleavedWeakHeap::new()
+    }
+}
+
+impl<T: Ord> InterleavedWeakHeap<T> {
+    /// Creates an empty `InterleavedWeakHeap` as a max-heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::InterleavedWeakHeap;
+    /// let mut heap = InterleavedWeakHeap::new();
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn new() -> InterleavedWeakHeap<T> {
+        InterleavedWeakHeap { nodes: Vec::new() }
+    }
+
+    /// Creates an empty `InterleavedWeakHeap` with a specific capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::InterleavedWeakHeap;
+    /// let mut heap = InterleavedWeakHeap::with_capacity(10);
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> InterleavedWeakHeap<T> {
+        InterleavedWeakHeap {
+            nodes: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Pushes an item onto the heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::InterleavedWeakHeap;
+    /// let mut heap = InterleavedWeakHeap::new();
+    /// heap.push(3);
+    /// heap.push(5);
+    /// heap.push(1);
+    ///
+    /// assert_eq!(heap.len(), 3);
+    /// assert_eq!(heap.peek(), Some(&5));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost of `push` is *O*(1), the worst case is *O*(log(*n*)).
+    pub fn push(&mut self, item: T) {
+        self.nodes.push(Node { item, bit: false });
+        self.sift_up(self.nodes.len() - 1);
+    }
+
+    /// Removes the greatest item from the heap and returns it, or `None` if it
+    /// is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::InterleavedWeakHeap;
+    /// let mut heap = InterleavedWeakHeap::from(vec![1, 3]);
+    ///
+    /// assert_eq!(heap.pop(), Some(3));
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost of `pop` on a heap containing *n* elements is *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        let mut item = self.nodes.pop()?.item;
+        if !self.nodes.is_empty() {
+            swap(&mut item, &mut self.nodes[0].item);
+            self.sift_down(self.nodes.len());
+        }
+        Some(item)
+    }
+
+    /// Consumes the heap and returns a vector in sorted (ascending) order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::InterleavedWeakHeap;
+    /// let heap = InterleavedWeakHeap::from(vec![4, 1, 3, 2]);
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut end = self.nodes.len();
+        while end > 1 {
+            end -= 1;
+            self.swap_items(0, end);
+            self.sift_down(end);
+        }
+        self.into_vec()
+    }
+
+    fn d_ancestor(&self, pos: usize) -> usize {
+        let mut cur = pos;
+        while cur / 2 > 0 && cur % 2 == self.nodes[cur / 2].bit as usize {
+            cur /= 2;
+        }
+        cur / 2
+    }
+
+    fn sift_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let ancestor = self.d_ancestor(pos);
+            if raw::lt(&self.nodes[ancestor].item, &self.nodes[pos].item) {
+                self.swap_items(ancestor, pos);
+                pos = ancestor;
+            } else {
+                break;
+            }
+        }
+    }
+
+    // Sifts the root down the heap formed by `nodes[..end]`.
+    fn sift_down(&mut self, end: usize) {
+        if end < 2 {
+            return;
+        }
+        let mut cur = 1;
+        while cur * 2 + (self.nodes[cur].bit as usize) < end {
+            cur = 2 * cur + (self.nodes[cur].bit as usize);
+        }
+
+        while cur > 0 {
+            if raw::lt(&self.nodes[0].item, &self.nodes[cur].item) {
+                self.nodes[cur].bit ^= true;
+                self.swap_items(0, cur);
+            }
+            cur /= 2;
+        }
+    }
+
+    // Swaps the elements of two nodes, leaving their bits in place.
+    fn swap_items(&mut self, a: usize, b: usize) {
+        debug_assert!(a < b);
+        let (head, tail) = self.nodes.split_at_mut(b);
+        swap(&mut head[a].item, &mut tail[0].item);
+    }
+}
+
+impl<T> InterleavedWeakHeap<T> {
+    /// Returns the greatest item in the heap, or `None` if it is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.nodes.first().map(|node| &node.item)
+    }
+
+    /// Returns an iterator visiting all values in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.nodes.iter().map(|node| &node.item)
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Drops all items from the heap.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+
+    /// Consumes the heap and returns its elements in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        self.nodes.into_iter().map(|node| node.item).collect()
+    }
+}
+
+impl<T: Ord> From<Vec<T>> for InterleavedWeakHeap<T> {
+    /// Converts a `Vec<T>` into an `InterleavedWeakHeap<T>` with *n* - 1
+    /// comparisons.
+    fn from(vec: Vec<T>) -> InterleavedWeakHeap<T> {
+        let mut heap = InterleavedWeakHeap {
+            nodes: vec
+                .into_iter()
+                .map(|item| Node { item, bit: false })
+                .collect(),
+        };
+        for pos in (1..heap.nodes.len()).rev() {
+            let ancestor = heap.d_ancestor(pos);
+            if raw::lt(&heap.nodes[ancestor].item, &heap.nodes[pos].item) {
+                heap.nodes[pos].bit ^= true;
+                heap.swap_items(ancestor, pos);
+            }
+        }
+        heap
+    }
+}
+
+impl<T: Ord> FromIterator<T> for InterleavedWeakHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> InterleavedWeakHeap<T> {
+        InterleavedWeakHeap::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<T: Ord> Extend<T> for InterleavedWeakHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
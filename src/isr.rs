@@ -0,0 +1,154 @@
+//! A priority queue shared between interrupt handlers and the main loop.
+
+use crate::ArrayWeakHeap;
+use core::cell::RefCell;
+use core::fmt;
+use critical_section::Mutex;
+
+/// A fixed-capacity priority queue which can be shared between interrupt
+/// service routines and the main loop of bare-metal firmware.
+///
+/// The queue holds at most `N` elements inline in an [`ArrayWeakHeap`] and
+/// can be created by a `const fn`, so it can be declared as a `static`.
+/// Every operation runs inside a [`critical_section`], so the queue is used
+/// through a shared reference. The critical sections are short and never
+/// block: [`push_from_isr`] and [`pop`] make at most ⌊log2(`N`)⌋ + 1
+/// comparisons and never allocate, and a full queue rejects new items
+/// instead of waiting.
+///
+/// A critical section implementation must be provided for the target, as
+/// described in the documentation of the `critical-section` crate.
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::IsrQueue;
+///
+/// static EVENTS: IsrQueue<u32, 4> = IsrQueue::new();
+///
+/// // In an interrupt handler:
+/// EVENTS.push_from_isr(2).unwrap();
+/// EVENTS.push_from_isr(7).unwrap();
+///
+/// // In the main loop:
+/// while let Some(event) = EVENTS.pop() {
+///     println!("handling event {}", event);
+/// }
+/// ```
+///
+/// [`push_from_isr`]: IsrQueue::push_from_isr
+/// [`pop`]: IsrQueue::pop
+pub struct IsrQueue<T, const N: usize> {
+    heap: Mutex<RefCell<ArrayWeakHeap<T, N>>>,
+}
+
+impl<T, const N: usize> fmt::Debug for IsrQueue<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IsrQueue")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Ord, const N: usize> Default for IsrQueue<T, N> {
+    /// Creates an empty `IsrQueue`.
+    #[inline]
+    fn default() -> IsrQueue<T, N> {
+        IsrQueue::new()
+    }
+}
+
+impl<T: Ord, const N: usize> IsrQueue<T, N> {
+    /// Creates an empty `IsrQueue` holding up to `N` elements.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::IsrQueue;
+    /// static QUEUE: IsrQueue<u32, 16> = IsrQueue::new();
+    /// assert_eq!(QUEUE.capacity(), 16);
+    /// ```
+    #[must_use]
+    pub const fn new() -> IsrQueue<T, N> {
+        IsrQueue {
+            heap: Mutex::new(RefCell::new(ArrayWeakHeap::new())),
+        }
+    }
+
+    /// Pushes an item onto the queue. The item is returned back if the queue is full.
+    ///
+    /// Safe to call from an interrupt handler.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::IsrQueue;
+    /// let queue = IsrQueue::<i32, 1>::new();
+    /// assert_eq!(queue.push_from_isr(1), Ok(()));
+    /// assert_eq!(queue.push_from_isr(2), Err(2));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// At most ⌊log2(`N`)⌋ + 1 comparisons, no allocation.
+    pub fn push_from_isr(&self, item: T) -> Result<(), T> {
+        critical_section::with(|cs| self.heap.borrow_ref_mut(cs).try_push(item))
+    }
+
+    /// Removes the greatest item from the queue and returns it, or `None` if
+    /// it is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::IsrQueue;
+    /// let queue = IsrQueue::<i32, 4>::new();
+    /// queue.push_from_isr(1).unwrap();
+    /// queue.push_from_isr(3).unwrap();
+    ///
+    /// assert_eq!(queue.pop(), Some(3));
+    /// assert_eq!(queue.pop(), Some(1));
+    /// assert_eq!(queue.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// At most ⌊log2(`N`)⌋ + 1 comparisons, no allocation.
+    pub fn pop(&self) -> Option<T> {
+        critical_section::with(|cs| self.heap.borrow_ref_mut(cs).pop())
+    }
+}
+
+impl<T, const N: usize> IsrQueue<T, N> {
+    /// Returns the number of elements in the queue.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        critical_section::with(|cs| self.heap.borrow_ref(cs).len())
+    }
+
+    /// Checks if the queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the fixed number of elements the queue can hold, `N`.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Consumes the queue and returns the underlying heap.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_inner(self) -> ArrayWeakHeap<T, N> {
+        self.heap.into_inner().into_inner()
+    }
+}
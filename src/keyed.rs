@@ -0,0 +1,320 @@
+//! A priority queue storing keys and payloads separately.
+
+use crate::WeakHeap;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::iter::FromIterator;
+
+// A key together with the slot of its payload. Ordered by the key only.
+#[derive(Clone)]
+struct Slot<K> {
+    key: K,
+    index: usize,
+}
+
+impl<K: PartialEq> PartialEq for Slot<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<K: Eq> Eq for Slot<K> {}
+
+impl<K: PartialOrd> PartialOrd for Slot<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.key.partial_cmp(&other.key)
+    }
+}
+
+impl<K: Ord> Ord for Slot<K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// A priority queue implemented with a weak heap, which keeps the comparison
+/// keys and the payloads in separate arrays.
+///
+/// Only the keys (together with a payload index) are moved while sifting, the
+/// payloads stay in place until they are removed. When the elements are a small
+/// key attached to a large payload, this keeps the sifting cache-friendly and
+/// avoids copying the payload on every swap.
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::KeyedWeakHeap;
+///
+/// let mut heap = KeyedWeakHeap::new();
+/// heap.push(2, [0u8; 1024]);
+/// heap.push(5, [5u8; 1024]);
+/// heap.push(1, [1u8; 1024]);
+///
+/// assert_eq!(heap.peek().map(|(k, v)| (*k, v[0])), Some((5, 5)));
+/// assert_eq!(heap.pop().map(|(k, v)| (k, v[0])), Some((5, 5)));
+/// assert_eq!(heap.len(), 2);
+/// ```
+pub struct KeyedWeakHeap<K, V> {
+    heap: WeakHeap<Slot<K>>,
+    payloads: Vec<Option<V>>,
+    // Indices of the vacant entries of `payloads`.
+    free: Vec<usize>,
+}
+
+impl<K: Clone, V: Clone> Clone for KeyedWeakHeap<K, V> {
+    fn clone(&self) -> Self {
+        KeyedWeakHeap {
+            heap: self.heap.clone(),
+            payloads: self.payloads.clone(),
+            free: self.free.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for KeyedWeakHeap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Ord, V> Default for KeyedWeakHeap<K, V> {
+    /// Creates an empty `KeyedWeakHeap`.
+    #[inline]
+    fn default() -> KeyedWeakHeap<K, V> {
+        KeyedWeakHeap::new()
+    }
+}
+
+impl<K: Ord, V> KeyedWeakHeap<K, V> {
+    /// Creates an empty `KeyedWeakHeap` as a max-heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::KeyedWeakHeap;
+    /// let mut heap = KeyedWeakHeap::new();
+    /// heap.push(4, "four");
+    /// ```
+    #[must_use]
+    pub fn new() -> KeyedWeakHeap<K, V> {
+        KeyedWeakHeap {
+            heap: WeakHeap::new(),
+            payloads: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Creates an empty `KeyedWeakHeap` with a specific capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::KeyedWeakHeap;
+    /// let mut heap = KeyedWeakHeap::with_capacity(10);
+    /// heap.push(4, "four");
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> KeyedWeakHeap<K, V> {
+        KeyedWeakHeap {
+            heap: WeakHeap::with_capacity(capacity),
+            payloads: Vec::with_capacity(capacity),
+            free: Vec::new(),
+        }
+    }
+
+    /// Pushes a key with its payload onto the heap.
+    ///
+    /// The payload is moved once, into a vacant slot, and stays there until
+    /// it is popped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::KeyedWeakHeap;
+    /// let mut heap = KeyedWeakHeap::new();
+    /// heap.push(3, "three");
+    /// heap.push(5, "five");
+    ///
+    /// assert_eq!(heap.len(), 2);
+    /// assert_eq!(heap.peek(), Some((&5, &"five")));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost of `push` is *O*(1), the worst case is *O*(log(*n*)).
+    pub fn push(&mut self, key: K, value: V) {
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.payloads[index] = Some(value);
+                index
+            }
+            None => {
+                self.payloads.push(Some(value));
+                self.payloads.len() - 1
+            }
+        };
+        self.heap.push(Slot { key, index });
+    }
+
+    /// Removes the entry with the greatest key from the heap and returns it, or
+    /// `None` if it is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::KeyedWeakHeap;
+    /// let mut heap: KeyedWeakHeap<_, _> = vec![(1, 'a'), (3, 'c')].into_iter().collect();
+    ///
+    /// assert_eq!(heap.pop(), Some((3, 'c')));
+    /// assert_eq!(heap.pop(), Some((1, 'a')));
+    /// assert_eq!(heap.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost of `pop` on a heap containing *n* elements is *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<(K, V)> {
+        let Slot { key, index } = self.heap.pop()?;
+        let value = self.take_payload(index);
+        if self.heap.is_empty() {
+            self.payloads.clear();
+            self.free.clear();
+        }
+        Some((key, value))
+    }
+
+    /// Consumes the heap and returns its entries in sorted (ascending) order
+    /// of the keys.
+    ///
+    /// The keys are sorted first, the payloads are moved once, at the end.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::KeyedWeakHeap;
+    /// let heap: KeyedWeakHeap<_, _> = vec![(2, 'b'), (3, 'c'), (1, 'a')].into_iter().collect();
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [(1, 'a'), (2, 'b'), (3, 'c')]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<(K, V)> {
+        let slots = core::mem::take(&mut self.heap).into_sorted_vec();
+        slots
+            .into_iter()
+            .map(|Slot { key, index }| (key, self.take_payload(index)))
+            .collect()
+    }
+
+    fn take_payload(&mut self, index: usize) -> V {
+        self.free.push(index);
+        self.payloads[index]
+            .take()
+            .expect("every key refers to an occupied payload slot")
+    }
+}
+
+impl<K, V> KeyedWeakHeap<K, V> {
+    /// Returns the entry with the greatest key, or `None` if the heap is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::KeyedWeakHeap;
+    /// let mut heap = KeyedWeakHeap::new();
+    /// assert_eq!(heap.peek(), None);
+    ///
+    /// heap.push(1, "one");
+    /// heap.push(5, "five");
+    /// assert_eq!(heap.peek(), Some((&5, &"five")));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<(&K, &V)> {
+        self.heap.peek().map(|slot| self.entry(slot))
+    }
+
+    /// Returns an iterator visiting all entries in arbitrary order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::KeyedWeakHeap;
+    /// let heap: KeyedWeakHeap<_, _> = vec![(1, 'a'), (2, 'b')].into_iter().collect();
+    ///
+    /// let mut entries: Vec<_> = heap.iter().collect();
+    /// entries.sort();
+    /// assert_eq!(entries, [(&1, &'a'), (&2, &'b')]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.heap.iter().map(move |slot| self.entry(slot))
+    }
+
+    /// Returns the number of entries in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Drops all entries from the heap.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.payloads.clear();
+        self.free.clear();
+    }
+
+    fn entry<'a>(&'a self, slot: &'a Slot<K>) -> (&'a K, &'a V) {
+        let value = self.payloads[slot.index]
+            .as_ref()
+            .expect("every key refers to an occupied payload slot");
+        (&slot.key, value)
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for KeyedWeakHeap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> KeyedWeakHeap<K, V> {
+        let (slots, payloads): (Vec<_>, Vec<_>) = iter
+            .into_iter()
+            .enumerate()
+            .map(|(index, (key, value))| (Slot { key, index }, Some(value)))
+            .unzip();
+        KeyedWeakHeap {
+            heap: WeakHeap::from(slots),
+            payloads,
+            free: Vec::new(),
+        }
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for KeyedWeakHeap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.push(key, value);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! K-way merge of sorted iterators.
+
+use crate::bits::BitVec;
+use crate::raw;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::mem::replace;
+
+/// Merges iterators sorted in ascending order into a single iterator sorted
+/// in ascending order.
+///
+/// The current head of every iterator is kept in a weak heap, so producing
+/// an element takes at most about log2(*k*) comparisons for *k* iterators,
+/// which pays off when comparing is expensive, e.g. for strings.
+///
+/// If an input iterator is not sorted, the output is not sorted either, but
+/// it still yields every element exactly once.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::kmerge;
+///
+/// let a = vec!["apple", "kiwi", "pear"];
+/// let b = vec!["banana", "cherry"];
+/// let c = vec!["fig", "plum"];
+///
+/// let merged: Vec<_> = kmerge(vec![a, b, c]).collect();
+/// assert_eq!(merged, ["apple", "banana", "cherry", "fig", "kiwi", "pear", "plum"]);
+/// ```
+pub fn kmerge<I>(iters: I) -> KMerge<<I::Item as IntoIterator>::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: IntoIterator,
+    <I::Item as IntoIterator>::Item: Ord,
+{
+    let mut heads: Vec<_> = iters
+        .into_iter()
+        .filter_map(|iter| {
+            let mut iter = iter.into_iter();
+            iter.next().map(|head| (head, iter))
+        })
+        .collect();
+    let mut bit = BitVec::zeros(heads.len());
+    raw::rebuild(&mut heads, &mut bit, &mut head_gt);
+    KMerge { heads, bit }
+}
+
+// The heads form a min-heap: the "greatest" pair holds the smallest head.
+fn head_gt<T: Ord, I>(a: &(T, I), b: &(T, I)) -> bool {
+    raw::lt(&b.0, &a.0)
+}
+
+/// An iterator merging sorted iterators into one sorted iterator.
+///
+/// This `struct` is created by [`kmerge()`]. See its documentation for more.
+pub struct KMerge<I: Iterator> {
+    heads: Vec<(I::Item, I)>,
+    bit: BitVec,
+}
+
+impl<I> Clone for KMerge<I>
+where
+    I: Iterator + Clone,
+    I::Item: Clone,
+{
+    fn clone(&self) -> Self {
+        KMerge {
+            heads: self.heads.clone(),
+            bit: self.bit.clone(),
+        }
+    }
+}
+
+impl<I> fmt::Debug for KMerge<I>
+where
+    I: Iterator + fmt::Debug,
+    I::Item: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("KMerge").field(&self.heads).finish()
+    }
+}
+
+impl<I> Iterator for KMerge<I>
+where
+    I: Iterator,
+    I::Item: Ord,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let (head, iter) = self.heads.first_mut()?;
+        let item = match iter.next() {
+            Some(next) => replace(head, next),
+            None => {
+                self.bit.pop();
+                self.heads.swap_remove(0).0
+            }
+        };
+        let len = self.heads.len();
+        if len > 0 {
+            // SAFETY: 0 < len == self.heads.len() == self.bit.len().
+            unsafe { raw::sift_down_range(&mut self.heads, &mut self.bit, 0, len, &mut head_gt) };
+        }
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.heads
+            .iter()
+            .fold((0, Some(0)), |(low, high), (_, iter)| {
+                let (iter_low, iter_high) = iter.size_hint();
+                (
+                    low.saturating_add(iter_low).saturating_add(1),
+                    high.zip(iter_high)
+                        .and_then(|(high, iter_high)| high.checked_add(iter_high)?.checked_add(1)),
+                )
+            })
+    }
+}
+
+impl<I> FusedIterator for KMerge<I>
+where
+    I: Iterator,
+    I::Item: Ord,
+{
+}
@@ -0,0 +1,367 @@
+//! A priority queue with lazy deletion of arbitrary elements.
+
+use crate::WeakHeap;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::iter::FromIterator;
+
+// An element together with its tombstone. The tombstone takes no part in the
+// ordering, so it can be set without restoring the heap.
+#[derive(Clone)]
+struct Entry<T> {
+    item: T,
+    deleted: bool,
+}
+
+impl<T: Ord> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.item == other.item
+    }
+}
+
+impl<T: Ord> Eq for Entry<T> {}
+
+impl<T: Ord> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.item.cmp(&other.item)
+    }
+}
+
+/// A priority queue implemented with a weak heap, which deletes elements
+/// lazily.
+///
+/// [`mark_deleted`] only puts a tombstone on the matching elements. They are
+/// skipped when they reach the top of the heap, and the heap is compacted
+/// once they make up more than half of its entries. This suits workloads like
+/// Dijkstra's algorithm, where many entries become stale before they are
+/// popped.
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::LazyWeakHeap;
+///
+/// let mut heap: LazyWeakHeap<_> = vec![1, 5, 2, 8, 3].into_iter().collect();
+/// assert_eq!(heap.mark_deleted(|&x| x % 2 == 0), 2);
+///
+/// assert_eq!(heap.len(), 3);
+/// assert_eq!(heap.pop(), Some(5));
+/// assert_eq!(heap.pop(), Some(3));
+/// assert_eq!(heap.pop(), Some(1));
+/// assert_eq!(heap.pop(), None);
+/// ```
+///
+/// [`mark_deleted`]: LazyWeakHeap::mark_deleted
+pub struct LazyWeakHeap<T> {
+    // The root is never deleted.
+    heap: WeakHeap<Entry<T>>,
+    // The number of deleted entries in `heap`.
+    deleted: usize,
+}
+
+impl<T: Clone> Clone for LazyWeakHeap<T> {
+    fn clone(&self) -> Self {
+        LazyWeakHeap {
+            heap: self.heap.clone(),
+            deleted: self.deleted,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LazyWeakHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> Default for LazyWeakHeap<T> {
+    /// Creates an empty `LazyWeakHeap`.
+    #[inline]
+    fn default() -> LazyWeakHeap<T> {
+        LazyWeakHeap::new()
+    }
+}
+
+impl<T: Ord> LazyWeakHeap<T> {
+    /// Creates an empty `LazyWeakHeap` as a max-heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::LazyWeakHeap;
+    /// let mut heap = LazyWeakHeap::new();
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn new() -> LazyWeakHeap<T> {
+        LazyWeakHeap {
+            heap: WeakHeap::new(),
+            deleted: 0,
+        }
+    }
+
+    /// Creates an empty `LazyWeakHeap` with a specific capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::LazyWeakHeap;
+    /// let mut heap = LazyWeakHeap::with_capacity(10);
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> LazyWeakHeap<T> {
+        LazyWeakHeap {
+            heap: WeakHeap::with_capacity(capacity),
+            deleted: 0,
+        }
+    }
+
+    /// Pushes an item onto the heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::LazyWeakHeap;
+    /// let mut heap = LazyWeakHeap::new();
+    /// heap.push(3);
+    /// heap.push(5);
+    /// heap.push(1);
+    ///
+    /// assert_eq!(heap.len(), 3);
+    /// assert_eq!(heap.peek(), Some(&5));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost of `push` is *O*(1), the worst case is *O*(log(*n*)),
+    /// where *n* counts the deleted entries not yet removed.
+    pub fn push(&mut self, item: T) {
+        self.heap.push(Entry {
+            item,
+            deleted: false,
+        });
+    }
+
+    /// Removes the greatest item that is not deleted from the heap and
+    /// returns it, or `None` if there is none.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::LazyWeakHeap;
+    /// let mut heap: LazyWeakHeap<_> = vec![1, 3].into_iter().collect();
+    ///
+    /// assert_eq!(heap.pop(), Some(3));
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The cost is *O*(log(*n*)) for every deleted entry removed along with
+    /// the item, where *n* counts the deleted entries not yet removed.
+    pub fn pop(&mut self) -> Option<T> {
+        let Entry { item, .. } = self.heap.pop()?;
+        self.pop_deleted();
+        Some(item)
+    }
+
+    /// Marks every item for which `pred` returns `true` as deleted, and
+    /// returns the number of newly deleted items.
+    ///
+    /// The deleted items are removed when they reach the top of the heap, or
+    /// all at once when they make up more than half of the entries.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::LazyWeakHeap;
+    /// let mut heap: LazyWeakHeap<_> = vec![4, 1, 3, 2].into_iter().collect();
+    ///
+    /// assert_eq!(heap.mark_deleted(|&x| x > 2), 2);
+    /// assert_eq!(heap.mark_deleted(|&x| x > 2), 0);
+    /// assert_eq!(heap.into_sorted_vec(), [1, 2]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Marking costs *O*(*n*), where *n* counts the deleted entries not yet
+    /// removed, plus the removal of the deleted entries at the top.
+    pub fn mark_deleted<F>(&mut self, mut pred: F) -> usize
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut marked = 0;
+        // SAFETY: the tombstones take no part in the ordering.
+        for entry in unsafe { self.heap.as_mut_slice_unchecked() } {
+            if !entry.deleted && pred(&entry.item) {
+                entry.deleted = true;
+                marked += 1;
+            }
+        }
+        self.deleted += marked;
+
+        if self.deleted > self.heap.len() / 2 {
+            self.compact();
+        } else {
+            self.pop_deleted();
+        }
+        marked
+    }
+
+    /// Removes all deleted entries from the heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::LazyWeakHeap;
+    /// let mut heap: LazyWeakHeap<_> = vec![1, 2, 3, 4, 5].into_iter().collect();
+    /// heap.mark_deleted(|&x| x == 1);
+    /// assert_eq!(heap.deleted_len(), 1);
+    ///
+    /// heap.compact();
+    /// assert_eq!(heap.deleted_len(), 0);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(*n*), where *n* counts the deleted entries.
+    pub fn compact(&mut self) {
+        if self.deleted > 0 {
+            self.heap.retain(|entry| !entry.deleted);
+            self.deleted = 0;
+        }
+    }
+
+    /// Consumes the heap and returns the items that are not deleted in sorted
+    /// (ascending) order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::LazyWeakHeap;
+    /// let heap: LazyWeakHeap<_> = vec![4, 1, 3, 2].into_iter().collect();
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        self.compact();
+        let entries = self.heap.into_sorted_vec();
+        entries.into_iter().map(|entry| entry.item).collect()
+    }
+
+    // Removes the deleted entries from the top of the heap.
+    fn pop_deleted(&mut self) {
+        while self.heap.peek().is_some_and(|entry| entry.deleted) {
+            self.heap.pop();
+            self.deleted -= 1;
+        }
+    }
+}
+
+impl<T> LazyWeakHeap<T> {
+    /// Returns the greatest item in the heap that is not deleted, or `None` if
+    /// there is none.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.peek().map(|entry| &entry.item)
+    }
+
+    /// Returns an iterator visiting all values that are not deleted in
+    /// arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.heap
+            .iter()
+            .filter(|entry| !entry.deleted)
+            .map(|entry| &entry.item)
+    }
+
+    /// Returns the number of elements in the heap that are not deleted.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len() - self.deleted
+    }
+
+    /// Returns the number of deleted entries not yet removed from the heap.
+    #[must_use]
+    pub fn deleted_len(&self) -> usize {
+        self.deleted
+    }
+
+    /// Checks if the heap has no elements that are not deleted.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Drops all items from the heap.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.deleted = 0;
+    }
+
+    /// Consumes the heap and returns its elements that are not deleted in
+    /// arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        let entries = self.heap.into_vec();
+        entries
+            .into_iter()
+            .filter(|entry| !entry.deleted)
+            .map(|entry| entry.item)
+            .collect()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for LazyWeakHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> LazyWeakHeap<T> {
+        let entries: Vec<_> = iter
+            .into_iter()
+            .map(|item| Entry {
+                item,
+                deleted: false,
+            })
+            .collect();
+        LazyWeakHeap {
+            heap: WeakHeap::from(entries),
+            deleted: 0,
+        }
+    }
+}
+
+impl<T: Ord> Extend<T> for LazyWeakHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
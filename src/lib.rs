@@ -17,17 +17,201 @@
 //! with [`BinaryHeap`]
 //! from `std::collections`, and at the same time it has several new useful methods.
 //!
+//! # Fallible allocation
+//!
+//! The `try_*` methods, such as [`WeakHeap::try_push`] and [`WeakHeap::try_from_vec`],
+//! report allocation failures as a [`WeakHeapError`] instead of panicking or aborting.
+//! Apart from allocation, the basic operations (`push`, `pop`, `peek`, `pushpop`)
+//! contain no panicking code paths, as long as the `Ord` implementation doesn't panic.
+//!
+//! # Detecting broken `Ord` implementations
+//!
+//! An `Ord` implementation which is not a total order (for example, floats compared
+//! through `partial_cmp(..).unwrap_or(..)`, or keys that change while in the heap)
+//! silently breaks the ordering of the heap. In debug builds, or with the
+//! `paranoid` feature enabled in release builds, every comparison of the sifting
+//! code which finds `a < b` is cross-checked against `b < a`, and a panic reports
+//! the first inconsistency. This costs at most one extra comparison per element
+//! moved by the sifting code.
+//!
+//! The `debug-invariants` feature runs [`WeakHeap::check_integrity`] after every
+//! `push`, `pop`, `append`, `append_vec` and modification through `peek_mut`, in
+//! debug builds only, and panics right after the operation which left the heap
+//! corrupted. Each check takes *n* - 1 comparisons.
+//!
+//! Floats can be stored in a heap without a hand-written `Ord` by wrapping them
+//! in [`TotalOrd`], which compares them with `total_cmp`.
+//!
+//! # Operation statistics
+//!
+//! With the `stats` feature, the sifting code counts the sift-ups, sift-downs
+//! and their lengths, the element swaps and the reverse bit flips, on every
+//! container of this crate. [`stats`] returns the counters of the current thread
+//! and [`reset_stats`] sets them to zero, so a workload can be profiled without
+//! patching the crate. Without the feature, the counting code is not compiled.
+//!
+//! # Parallel sorting
+//!
+//! With the `rayon` feature, [`WeakHeap::par_into_sorted_vec`] sorts large heaps
+//! on the `rayon` thread pool.
+//!
+//! # Async
+//!
+//! With the `asyncqueue` feature, [`AsyncWeakHeap`] is a queue shared between
+//! tasks, whose `pop` returns a future resolving when an element is available.
+//! It works with any executor.
+//!
+//! # Property testing
+//!
+//! With the `proptest` feature, the [`proptest`](crate::proptest) module provides
+//! strategies generating valid heaps, with arbitrary, duplicate-heavy or
+//! adversarially ordered elements, and `WeakHeap` implements `Arbitrary`.
+//!
+//! With the `quickcheck` feature, `WeakHeap` implements `quickcheck::Arbitrary`.
+//! Shrinking removes or shrinks elements and rebuilds the heap, so every shrunk
+//! heap is valid too.
+//!
+//! # Serialization
+//!
+//! With the `serde` feature, `WeakHeap` implements `Serialize` and `Deserialize`.
+//! A heap is serialized as a plain sequence of its elements, and deserializing
+//! rebuilds the heap from that sequence, so no input can produce a heap which
+//! violates the weak heap property.
+//!
+//! # `no_std` support
+//!
+//! The crate builds on `core` and `alloc` when the default `std` feature is disabled.
+//! The hash-based containers ([`WeakHeapSet`], [`WeakHeapMap`], [`CountingWeakHeap`]),
+//! [`ConcurrentWeakHeap`], the priority channels of the [`sync`] module, [`WeakHeap::pop_until_deadline`]
+//! and the [`std::error::Error`] implementation of [`WeakHeapError`] are only
+//! available with `std`.
+//!
 //! # Read about weak heap:
 //! * [Wikipedia](https://en.wikipedia.org/wiki/Weak_heap)
 //! * [The weak-heap data structure: Variants and applications](https://www.sciencedirect.com/science/article/pii/S1570866712000792)
 //!
 //! [`BinaryHeap`]: std::collections::BinaryHeap
 //!
-use std::fmt;
-use std::iter::{FromIterator, FusedIterator};
-use std::mem::{swap, ManuallyDrop};
-use std::ops::{Deref, DerefMut};
-use std::ptr;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use bits::{BitVec, Bits};
+use core::cmp::{Ordering, Reverse};
+use core::fmt;
+use core::iter::{FromIterator, FusedIterator};
+use core::mem::{swap, take, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+mod aggregate;
+mod array;
+#[cfg(feature = "asyncqueue")]
+mod async_heap;
+#[cfg(feature = "bench-utils")]
+pub mod bench_utils;
+mod bits;
+mod bounded;
+mod by;
+mod by_key;
+#[cfg(feature = "std")]
+mod concurrent;
+#[cfg(feature = "std")]
+mod counting;
+mod error;
+mod fallible;
+mod heap;
+mod indexed;
+mod interleaved;
+#[cfg(feature = "critical-section")]
+mod isr;
+mod keyed;
+mod kmerge;
+mod lazy;
+mod macros;
+#[cfg(feature = "std")]
+mod map;
+mod median;
+mod memory;
+mod min;
+mod minmax;
+#[cfg(feature = "rayon")]
+mod par;
+mod progress;
+#[cfg(feature = "proptest")]
+pub mod proptest;
+mod queue;
+#[cfg(feature = "quickcheck")]
+mod quickcheck_impl;
+mod raw;
+mod realtime;
+mod relaxed;
+mod segmented;
+pub mod select;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "std")]
+mod set;
+pub mod slice;
+pub mod sort;
+mod sorter;
+mod stable;
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(feature = "std")]
+pub mod sync;
+mod total;
+mod tracked;
+#[cfg(any(kani, test))]
+mod verification;
+mod view;
+
+pub use aggregate::{Aggregate, AggregateWeakHeap, Count, Sum};
+pub use array::ArrayWeakHeap;
+#[cfg(feature = "asyncqueue")]
+pub use async_heap::{AsyncPop, AsyncWeakHeap};
+pub use bits::ReverseBits;
+pub use bounded::BoundedWeakHeap;
+pub use by::WeakHeapBy;
+pub use by_key::WeakHeapByKey;
+#[cfg(feature = "std")]
+pub use concurrent::ConcurrentWeakHeap;
+#[cfg(feature = "std")]
+pub use counting::CountingWeakHeap;
+pub use error::WeakHeapError;
+pub use fallible::{TryCompare, TryWeakHeap};
+pub use heap::Heap;
+pub use indexed::{Handle, IndexedWeakHeap};
+pub use interleaved::InterleavedWeakHeap;
+#[cfg(feature = "critical-section")]
+pub use isr::IsrQueue;
+pub use keyed::KeyedWeakHeap;
+pub use kmerge::{kmerge, KMerge};
+pub use lazy::LazyWeakHeap;
+#[cfg(feature = "std")]
+pub use map::WeakHeapMap;
+pub use median::MedianHeap;
+pub use memory::MemoryUsage;
+pub use min::MinWeakHeap;
+pub use minmax::MinMaxWeakHeap;
+pub use progress::Progress;
+pub use queue::{WeakQueue, WeakQueuePeekMut};
+pub use realtime::RealtimeWeakHeap;
+pub use relaxed::RelaxedWeakQueue;
+pub use segmented::SegmentedWeakHeap;
+#[cfg(feature = "std")]
+pub use set::WeakHeapSet;
+pub use sorter::IncrementalSorter;
+pub use stable::StableWeakHeap;
+#[cfg(feature = "stats")]
+pub use stats::{reset_stats, stats, Stats};
+pub use total::TotalOrd;
+pub use view::WeakHeapView;
 
 /// A priority queue implemented with a weak heap.
 ///
@@ -137,7 +321,7 @@ use std::ptr;
 /// [into_sorted_vec]: WeakHeap::into_sorted_vec
 pub struct WeakHeap<T> {
     data: Vec<T>,
-    bit: Vec<bool>,
+    bit: BitVec,
 }
 
 /// Structure wrapping a mutable reference to the greatest item on a
@@ -149,50 +333,162 @@ pub struct WeakHeap<T> {
 /// [`peek_mut`]: WeakHeap::peek_mut
 pub struct WeakHeapPeekMut<'a, T: 'a + Ord> {
     heap: &'a mut WeakHeap<T>,
-    sift: bool,
+    // Once the greatest item is borrowed mutably, the length of the heap and
+    // what is needed to restore its reverse bits: every element but the
+    // greatest one is hidden from the heap until the guard is dropped.
+    hidden: Option<(usize, (usize, u64))>,
 }
 
 impl<T: Ord + fmt::Debug> fmt::Debug for WeakHeapPeekMut<'_, T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_tuple("WeakHeapPeekMut")
-            .field(&self.heap.data[0])
-            .finish()
+        f.debug_tuple("WeakHeapPeekMut").field(&**self).finish()
+    }
+}
+
+impl<T: Ord> WeakHeapPeekMut<'_, T> {
+    // Gives the hidden elements back to the heap. Returns whether the
+    // greatest item may have been modified.
+    fn restore(&mut self) -> bool {
+        match self.hidden.take() {
+            Some((len, bits)) => {
+                // SAFETY: the first `len` elements were initialized when they
+                // were hidden, and nothing was written to the buffers since.
+                unsafe {
+                    self.heap.data.set_len(len);
+                    self.heap.bit.unhide_tail(bits);
+                }
+                true
+            }
+            None => false,
+        }
     }
 }
 
 impl<T: Ord> Drop for WeakHeapPeekMut<'_, T> {
     fn drop(&mut self) {
-        if self.sift {
-            // SAFETY: PeekMut is only instantiated for non-empty heaps.
-            unsafe { self.heap.sift_down(0) };
-        }
+        WeakHeapPeekMut::refresh(self);
     }
 }
 
 impl<T: Ord> Deref for WeakHeapPeekMut<'_, T> {
     type Target = T;
     fn deref(&self) -> &T {
-        debug_assert!(!self.heap.is_empty());
-        // SAFE: PeekMut is only instantiated for non-empty heaps
-        unsafe { self.heap.data.get_unchecked(0) }
+        // SAFETY: PeekMut is only instantiated for non-empty heaps, and the
+        // greatest item stays initialized while it is hidden.
+        unsafe { &*self.heap.data.as_ptr() }
     }
 }
 
 impl<T: Ord> DerefMut for WeakHeapPeekMut<'_, T> {
     fn deref_mut(&mut self) -> &mut T {
-        debug_assert!(!self.heap.is_empty());
-        self.sift = true;
-        // SAFE: PeekMut is only instantiated for non-empty heaps
-        unsafe { self.heap.data.get_unchecked_mut(0) }
+        if self.hidden.is_none() {
+            // The greatest item may be modified, so it has to be sifted down
+            // when the guard is dropped. Hide the other elements from the heap
+            // until then: if the guard is leaked, the heap is left holding the
+            // greatest item only, which is a valid heap, instead of out of order.
+            let len = self.heap.data.len();
+            let bits = self.heap.bit.hide_tail();
+            // SAFETY: 1 <= len. The elements are not dropped, and are given
+            // back by `restore`.
+            unsafe { self.heap.data.set_len(1) };
+            self.hidden = Some((len, bits));
+        }
+        // SAFETY: PeekMut is only instantiated for non-empty heaps, and the
+        // greatest item stays initialized while it is hidden.
+        unsafe { &mut *self.heap.data.as_mut_ptr() }
     }
 }
 
 impl<'a, T: Ord> WeakHeapPeekMut<'a, T> {
     /// Removes the peeked value from the heap and returns it.
     pub fn pop(mut this: WeakHeapPeekMut<'a, T>) -> T {
-        let value = this.heap.pop().unwrap();
-        this.sift = false;
-        value
+        // The greatest item is replaced, there is no need to sift it.
+        this.restore();
+        // SAFETY: PeekMut is only instantiated for non-empty heaps.
+        unsafe { this.heap.pop().unwrap_unchecked() }
+    }
+
+    /// Restores the heap after the peeked value was modified, and makes the
+    /// guard point to the new greatest item, which may be the same.
+    ///
+    /// This is equivalent to dropping the guard and calling
+    /// [`WeakHeap::peek_mut`] again, but keeps the heap borrowed.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::{WeakHeap, WeakHeapPeekMut};
+    ///
+    /// let mut heap = WeakHeap::from(vec![7, 3, 9, 5]);
+    /// let mut top = heap.peek_mut().unwrap();
+    ///
+    /// // Halve the greatest item until it is below 4.
+    /// while *top >= 4 {
+    ///     *top /= 2;
+    ///     WeakHeapPeekMut::refresh(&mut top);
+    /// }
+    /// assert_eq!(*top, 3);
+    /// drop(top);
+    /// assert_eq!(heap.into_sorted_vec(), [2, 2, 3, 3]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// If the item was modified then the worst case time complexity is
+    /// *O*(log(*n*)), otherwise it's *O*(1).
+    pub fn refresh(this: &mut WeakHeapPeekMut<'a, T>) {
+        if this.restore() {
+            // SAFETY: PeekMut is only instantiated for non-empty heaps.
+            unsafe { this.heap.sift_down(0) };
+            this.heap.debug_check_invariants("peek_mut");
+        }
+    }
+}
+
+/// Structure holding the greatest item of a `WeakHeap` while its removal is pending.
+///
+/// This `struct` is created by the [`begin_pop`] method on [`WeakHeap`]. See
+/// its documentation for more.
+///
+/// [`begin_pop`]: WeakHeap::begin_pop
+pub struct WeakHeapPendingPop<'a, T: 'a + Ord> {
+    // The item stays at the root until the removal is committed, so a
+    // pending pop is a `peek_mut` whose guard may pop the item.
+    peek: WeakHeapPeekMut<'a, T>,
+}
+
+impl<T: Ord + fmt::Debug> fmt::Debug for WeakHeapPendingPop<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WeakHeapPendingPop").field(&**self).finish()
+    }
+}
+
+impl<T: Ord> Deref for WeakHeapPendingPop<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.peek
+    }
+}
+
+impl<T: Ord> DerefMut for WeakHeapPendingPop<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.peek
+    }
+}
+
+impl<'a, T: Ord> WeakHeapPendingPop<'a, T> {
+    /// Finalizes the removal of the item from the heap and returns it.
+    pub fn commit(this: WeakHeapPendingPop<'a, T>) -> T {
+        WeakHeapPeekMut::pop(this.peek)
+    }
+
+    /// Cancels the removal, leaving the item at the top of the heap.
+    ///
+    /// This is equivalent to dropping the `WeakHeapPendingPop`.
+    pub fn abort(this: WeakHeapPendingPop<'a, T>) {
+        drop(this);
     }
 }
 
@@ -231,14 +527,125 @@ impl<T: Ord> Default for WeakHeap<T> {
     }
 }
 
+/// Formats the heap as a list of `(element, reverse bit)` pairs in storage
+/// order.
+///
+/// The alternate form, `{:#?}`, draws the heap as a tree instead, one node per
+/// line, or `[]` if the heap is empty. Every node
+/// shows its index in the storage and its element, the children are marked
+/// `L` and `R` for left and right, and `(reversed)` marks the nodes whose
+/// reverse bit is set, that is whose children are swapped in the storage.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::WeakHeap;
+///
+/// let heap = WeakHeap::from(vec![3, 1, 4, 1, 5, 9]);
+/// assert_eq!(
+///     format!("{:?}", heap),
+///     "[(9, false), (1, false), (5, true), (1, false), (3, true), (4, true)]"
+/// );
+/// assert_eq!(
+///     format!("{:#?}", heap),
+///     "\
+/// [0] 9
+/// └── R [1] 1
+///     ├── L [2] 5 (reversed)
+///     │   ├── L [5] 4 (reversed)
+///     │   └── R [4] 3 (reversed)
+///     └── R [3] 1
+/// "
+/// );
+/// ```
 impl<T: fmt::Debug> fmt::Debug for WeakHeap<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            let root = match self.data.first() {
+                Some(root) => root,
+                None => return f.write_str("[]"),
+            };
+            writeln!(f, "[0] {:?}", root)?;
+            return fmt_subtree(&self.data, &self.bit, 1, "R", true, &mut String::new(), f);
+        }
         f.debug_list()
             .entries(self.data.iter().zip(self.bit.iter()))
             .finish()
     }
 }
 
+// Writes the subtree rooted at `pos` as the `side` child of its parent, one
+// node per line, below the lines drawn for its ancestors.
+fn fmt_subtree<T: fmt::Debug>(
+    data: &[T],
+    bit: &BitVec,
+    pos: usize,
+    side: &str,
+    last: bool,
+    prefix: &mut String,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    if pos >= data.len() {
+        return Ok(());
+    }
+    let reversed = bit.get(pos) == Some(true);
+    write!(
+        f,
+        "{}{} {} [{}] {:?}",
+        prefix,
+        if last { "└──" } else { "├──" },
+        side,
+        pos,
+        data[pos]
+    )?;
+    writeln!(f, "{}", if reversed { " (reversed)" } else { "" })?;
+
+    let len = prefix.len();
+    prefix.push_str(if last { "    " } else { "│   " });
+    let (left, right) = (2 * pos + reversed as usize, 2 * pos + 1 - reversed as usize);
+    fmt_subtree(data, bit, left, "L", right >= data.len(), prefix, f)?;
+    fmt_subtree(data, bit, right, "R", true, prefix, f)?;
+    prefix.truncate(len);
+    Ok(())
+}
+
+/// Compares the contents of two heaps as multisets, regardless of their
+/// internal layout.
+///
+/// Unless the layouts are equal, both heaps are sorted by reference, which
+/// costs *O*(*n*log(*n*)) and allocates.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::WeakHeap;
+///
+/// let a = WeakHeap::from(vec![1, 3, 2, 3]);
+/// let mut b = WeakHeap::new();
+/// b.extend([3, 3, 2, 1]);
+///
+/// assert_eq!(a, b);
+/// b.pop();
+/// assert_ne!(a, b);
+/// ```
+impl<T: Ord> PartialEq for WeakHeap<T> {
+    fn eq(&self, other: &Self) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+        if self.data == other.data {
+            return true;
+        }
+        let mut a: Vec<&T> = self.data.iter().collect();
+        let mut b: Vec<&T> = other.data.iter().collect();
+        a.sort_unstable();
+        b.sort_unstable();
+        a == b
+    }
+}
+
+impl<T: Ord> Eq for WeakHeap<T> {}
+
 impl<T: Ord> WeakHeap<T> {
     /// Creates an empty `WeakHeap` as a max-heap.
     ///
@@ -258,7 +665,7 @@ impl<T: Ord> WeakHeap<T> {
     pub fn new() -> WeakHeap<T> {
         WeakHeap {
             data: vec![],
-            bit: vec![],
+            bit: BitVec::new(),
         }
     }
 
@@ -278,16 +685,25 @@ impl<T: Ord> WeakHeap<T> {
     /// ```
     #[must_use]
     pub fn with_capacity(capacity: usize) -> WeakHeap<T> {
+        let data = Vec::with_capacity(capacity);
         WeakHeap {
-            data: Vec::with_capacity(capacity),
-            bit: Vec::with_capacity(capacity),
+            bit: BitVec::with_capacity(data.capacity()),
+            data,
         }
     }
 
     /// Returns a mutable reference to the greatest item in the weak heap, or
     /// `None` if it is empty.
     ///
-    /// Note: If the `WeakHeapPeekMut` value is leaked, the heap may be in an
+    /// The heap is only restored when the guard is dropped, or by
+    /// [`WeakHeapPeekMut::refresh`], so any number of modifications made
+    /// through the same guard cost a single sift-down. To lower the greatest
+    /// item in several steps, keep the guard instead of calling `peek_mut`
+    /// for every step.
+    ///
+    /// Note: If the `WeakHeapPeekMut` value is leaked after the item was
+    /// modified, the other elements of the heap are leaked along with it and
+    /// the heap is left holding the modified item only, but never in an
     /// inconsistent state.
     ///
     /// # Examples
@@ -307,6 +723,14 @@ impl<T: Ord> WeakHeap<T> {
     ///     *val = 0;
     /// }
     /// assert_eq!(heap.peek(), Some(&2));
+    ///
+    /// // Several modifications, restored once when `top` is dropped.
+    /// let mut top = heap.peek_mut().unwrap();
+    /// for _ in 0..3 {
+    ///     *top -= 1;
+    /// }
+    /// drop(top);
+    /// assert_eq!(heap.into_sorted_vec(), [-1, 0, 1]);
     /// ```
     ///
     /// # Time complexity
@@ -319,11 +743,91 @@ impl<T: Ord> WeakHeap<T> {
         } else {
             Some(WeakHeapPeekMut {
                 heap: self,
-                sift: false,
+                hidden: None,
             })
         }
     }
 
+    /// Returns an iterator visiting all values in the underlying vector, in
+    /// arbitrary order, which allows modifying each value.
+    ///
+    /// The heap is rebuilt when the iterator is dropped, so the values can be
+    /// modified in any way, for example to apply a decay to every priority.
+    ///
+    /// Note: If the `IterMut` value is leaked after yielding an item, all the
+    /// elements of the heap are leaked along with it and the heap is left
+    /// empty, but never in an inconsistent state.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::from(vec![1, 2, 3, 4]);
+    ///
+    /// for x in heap.iter_mut() {
+    ///     *x = 10 - *x;
+    /// }
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [6, 7, 8, 9]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Rebuilding takes *n* - 1 comparisons, and is skipped if no item was
+    /// yielded.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let len = self.data.len();
+        let bit = take(&mut self.bit);
+        let ptr = self.data.as_mut_ptr();
+        // SAFETY: the elements are not dropped, and are given back to the heap
+        // when the iterator is dropped. Until then, the heap is empty, so the
+        // slice is the only way to reach them.
+        let iter = unsafe {
+            self.data.set_len(0);
+            core::slice::from_raw_parts_mut(ptr, len).iter_mut()
+        };
+        IterMut {
+            heap: self,
+            iter,
+            len,
+            bit,
+        }
+    }
+
+    /// Applies `f` to every element in place, then rebuilds the heap.
+    ///
+    /// This is the usual way to adjust all the priorities at once, for
+    /// example to age the entries of a queue. If `f` panics, the heap is
+    /// still rebuilt.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::from(vec![(10, 'a'), (4, 'b'), (7, 'c')]);
+    ///
+    /// // Halve every priority, except the one of 'b'.
+    /// heap.map_in_place(|(priority, name)| {
+    ///     if *name != 'b' {
+    ///         *priority /= 2;
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(heap.pop(), Some((5, 'a')));
+    /// assert_eq!(heap.pop(), Some((4, 'b')));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The heap is restored with a single rebuild of *n* - 1 comparisons.
+    pub fn map_in_place<F: FnMut(&mut T)>(&mut self, f: F) {
+        self.iter_mut().for_each(f);
+    }
+
     /// Removes the greatest item from the weak heap and returns it, or `None` if it
     /// is empty.
     ///
@@ -348,13 +852,175 @@ impl<T: Ord> WeakHeap<T> {
     /// as opposed to *2log(2, n)* for binary heap.
     pub fn pop(&mut self) -> Option<T> {
         self.bit.pop();
-        self.data.pop().map(|mut item| {
+        let item = self.data.pop().map(|mut item| {
             if !self.is_empty() {
-                swap(&mut item, &mut self.data[0]);
                 // SAFETY: !self.is_empty() means that self.len() > 0
-                unsafe { self.sift_down(0) };
+                unsafe {
+                    swap(&mut item, self.data.get_unchecked_mut(0));
+                    self.sift_down(0);
+                }
             }
             item
+        });
+        self.debug_check_invariants("pop");
+        item
+    }
+
+    /// Removes the `k` greatest items from the heap, or all of them if
+    /// `k >= self.len()`, and returns them in descending order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::from(vec![5, 1, 8, 3, 9, 2]);
+    ///
+    /// assert_eq!(heap.pop_batch(3), [9, 8, 5]);
+    /// assert_eq!(heap.pop(), Some(3));
+    /// assert_eq!(heap.pop_batch(10), [2, 1]);
+    /// assert!(heap.is_empty());
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(*k*log(*n*)). Unlike `k` sequential calls of
+    /// `pop()`, the items are sorted in place at the end of the heap and moved
+    /// out at once.
+    pub fn pop_batch(&mut self, k: usize) -> Vec<T> {
+        let start = self.sort_greatest(k);
+        self.bit.truncate(start);
+        let mut batch = self.data.split_off(start);
+        batch.reverse();
+        batch
+    }
+
+    /// Removes the greatest item and all items equal to it from the heap, and
+    /// returns them in arbitrary order. The vector is empty if the heap is.
+    ///
+    /// The items equal to the greatest one are found by following the heap
+    /// order from the root, and every one of them is removed by sifting down
+    /// from its own position rather than from the root.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::from(vec![3, 7, 1, 7, 5, 7]);
+    ///
+    /// assert_eq!(heap.pop_all_max(), [7, 7, 7]);
+    /// assert_eq!(heap.pop_all_max(), [5]);
+    /// assert_eq!(heap.len(), 2);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// For *k* removed items, the worst case cost is *O*(*k*log(*n*)).
+    pub fn pop_all_max(&mut self) -> Vec<T> {
+        let len = self.len();
+        let mut ties = Vec::new();
+        let mut stack = Vec::new();
+        if len > 0 {
+            stack.push(0);
+        }
+        while let Some(pos) = stack.pop() {
+            ties.push(pos);
+            // The elements dominated by `pos` directly: its right child and
+            // the left spine below it (the root has only a right child).
+            // SAFETY: pos < len == self.bit.len().
+            let mut cur = if pos == 0 {
+                1
+            } else {
+                2 * pos + 1 - unsafe { self.bit.get_unchecked(pos) } as usize
+            };
+            while cur < len {
+                if !raw::lt(&self.data[cur], &self.data[0]) {
+                    stack.push(cur);
+                }
+                // SAFETY: cur < len == self.bit.len().
+                cur = 2 * cur + unsafe { self.bit.get_unchecked(cur) } as usize;
+            }
+        }
+
+        // Removing from the back keeps the positions of the remaining ties,
+        // and the d-ancestors of a tie are ties themselves, so the element
+        // moved into its place only needs to go down.
+        ties.sort_unstable();
+        let mut items = Vec::with_capacity(ties.len());
+        for &pos in ties.iter().rev() {
+            self.bit.pop();
+            items.push(self.data.swap_remove(pos));
+            if pos < self.len() {
+                // SAFETY: pos < self.len().
+                unsafe { self.sift_down(pos) };
+            }
+        }
+        items
+    }
+
+    // Moves the `k` greatest items to the end of the vector in ascending
+    // order, leaving a valid heap before them. Returns the start of the
+    // sorted items.
+    fn sort_greatest(&mut self, k: usize) -> usize {
+        let start = self.len() - k.min(self.len());
+        let mut end = self.len();
+        while end > start {
+            end -= 1;
+            if end > 0 {
+                // SAFETY: 0 < end < self.len().
+                unsafe {
+                    let ptr = self.data.as_mut_ptr();
+                    core::ptr::swap(ptr, ptr.add(end));
+                    self.sift_down_range(0, end);
+                }
+            }
+        }
+        start
+    }
+
+    /// Starts removing the greatest item from the weak heap, or returns `None`
+    /// if it is empty.
+    ///
+    /// The removal is finalized with [`WeakHeapPendingPop::commit`]. If the
+    /// returned guard is dropped or [`WeakHeapPendingPop::abort`] is called
+    /// instead, the item stays at the top of the heap without any sifting,
+    /// unless it was modified through the guard.
+    ///
+    /// The item is only removed when the pop is committed, so leaking the
+    /// guard leaves the heap consistent, with the same effect as leaking a
+    /// [`WeakHeapPeekMut`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::{WeakHeap, WeakHeapPendingPop};
+    /// let mut heap = WeakHeap::from(vec![1, 5, 2]);
+    ///
+    /// let pending = heap.begin_pop().unwrap();
+    /// assert_eq!(*pending, 5);
+    /// // Processing failed, keep the item.
+    /// WeakHeapPendingPop::abort(pending);
+    /// assert_eq!(heap.len(), 3);
+    ///
+    /// let pending = heap.begin_pop().unwrap();
+    /// assert_eq!(WeakHeapPendingPop::commit(pending), 5);
+    /// assert_eq!(heap.into_sorted_vec(), vec![1, 2]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Committing costs *O*(log(*n*)) like [`pop`], aborting costs *O*(1)
+    /// if the item was not modified.
+    ///
+    /// [`pop`]: WeakHeap::pop
+    pub fn begin_pop(&mut self) -> Option<WeakHeapPendingPop<'_, T>> {
+        Some(WeakHeapPendingPop {
+            peek: self.peek_mut()?,
         })
     }
 
@@ -392,14 +1058,73 @@ impl<T: Ord> WeakHeap<T> {
     /// has been amortized in the previous figures.
     pub fn push(&mut self, item: T) {
         let old_len = self.len();
+        if old_len == self.data.capacity() {
+            self.reserve(1);
+        }
         self.data.push(item);
         self.bit.push(false);
 
-        if old_len != 0 {
-            // SAFETY: Since we pushed a new item it means that
-            //  old_len = self.len() - 1 < self.len()
-            unsafe { self.sift_up_push(0, old_len) };
-        }
+        // SAFETY: Since we pushed a new item it means that
+        //  old_len = self.len() - 1 < self.len()
+        unsafe { self.sift_up(old_len) };
+        self.debug_check_invariants("push");
+    }
+
+    /// Pushes all the items of `iter` onto the heap.
+    ///
+    /// The items are appended first, then the heap property is restored in a
+    /// single pass, either by sifting up every new item or by rebuilding the
+    /// whole heap, whichever is cheaper.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::from(vec![3, 1]);
+    /// heap.push_batch(vec![4, 1, 5]);
+    ///
+    /// assert_eq!(heap.len(), 5);
+    /// assert_eq!(heap.into_sorted_vec(), [1, 1, 3, 4, 5]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n* + *k*) in the worst case, where *k* is the number of pushed
+    /// items. Pushing few items costs the same as sequential `push` calls.
+    pub fn push_batch<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let guard = RebuildOnDrop {
+            start: self.len(),
+            heap: self,
+        };
+        guard.heap.data.extend(iter);
+    }
+
+    /// Pushes an item onto the weak heap, returning an error instead of
+    /// panicking or aborting if memory cannot be reserved.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows, or the allocator reports a failure, then an
+    /// error is returned and the item is dropped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::new();
+    /// heap.try_push(3).expect("out of memory");
+    /// heap.try_push(5).expect("out of memory");
+    ///
+    /// assert_eq!(heap.peek(), Some(&5));
+    /// ```
+    pub fn try_push(&mut self, item: T) -> Result<(), WeakHeapError> {
+        self.try_reserve(1)?;
+        self.push(item);
+        Ok(())
     }
 
     /// Effective equivalent to a sequential `push()` and `pop()` calls.
@@ -429,24 +1154,25 @@ impl<T: Ord> WeakHeap<T> {
     /// then the time complexity will be *O*(1), otherwise *O*(log(*n*)).
     /// And unlike the sequential call of `push()` and `pop()`, the resizing never happens.
     pub fn pushpop(&mut self, mut item: T) -> T {
-        if self.len() == 0 {
+        if self.is_empty() {
             return item;
         }
 
-        if self.data[0] < item {
-            item
-        } else {
-            swap(&mut item, &mut self.data[0]);
-            // SAFETY: self.len() > 0
-            unsafe {
+        // SAFETY: self.len() > 0
+        unsafe {
+            if raw::lt(self.data.get_unchecked(0), &item) {
+                item
+            } else {
+                swap(&mut item, self.data.get_unchecked_mut(0));
                 self.sift_down(0);
+                item
             }
-            item
         }
     }
 
-    /// Consumes the `WeakHeap` and returns a vector in sorted
-    /// (ascending) order.
+    /// Effective equivalent to a sequential `pop()` and `push()` calls: removes
+    /// the greatest item and inserts `item`, even if `item` is greater.
+    /// Returns the removed item, or `None` if the heap was empty.
     ///
     /// # Examples
     ///
@@ -454,172 +1180,379 @@ impl<T: Ord> WeakHeap<T> {
     ///
     /// ```
     /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::new();
+    /// assert_eq!(heap.replace(5), None);
+    /// assert_eq!(heap.peek(), Some(&5));
     ///
-    /// let mut heap = WeakHeap::from(vec![1, 2, 4, 5, 7]);
-    /// heap.push(6);
-    /// heap.push(3);
+    /// heap.push(10);
+    /// assert_eq!(heap.replace(20), Some(10));
+    /// assert_eq!(heap.peek(), Some(&20));
     ///
-    /// let vec = heap.into_sorted_vec();
-    /// assert_eq!(vec, [1, 2, 3, 4, 5, 6, 7]);
+    /// assert_eq!(heap.replace(1), Some(20));
+    /// assert_eq!(heap.into_sorted_vec(), [1, 5]);
     /// ```
     ///
     /// # Time complexity
     ///
-    /// Operation can be done in *O*(*nlog(n)*) like conventional **heapsort**,
-    /// but sorting by a weak heap produces significantly fewer comparisons.
-    #[must_use = "`self` will be dropped if the result is not used"]
-    pub fn into_sorted_vec(mut self) -> Vec<T> {
-        let mut end = self.len();
-        while end > 1 {
-            end -= 1;
-            // SAFETY: `end` goes from `self.len() - 1` to 1 (both included),
-            //  so it's always a valid index to access.
-            //  It is safe to access index 0 (i.e. `ptr`), because
-            //  1 <= end < self.len(), which means self.len() >= 2.
-            unsafe {
-                let ptr = self.data.as_mut_ptr();
-                std::ptr::swap(ptr, ptr.add(end));
-            }
-            // SAFETY: `end` goes from `self.len() - 1` to 1 (both included) so:
-            //  0 < 1 <= end <= self.len() - 1 < self.len()
-            //  Which means 0 < end and end < self.len().
-            unsafe { self.sift_down_range(0, end) };
+    /// The worst case cost is *O*(log(*n*)). Unlike the sequential call of
+    /// `pop()` and `push()`, only a single sift is performed and the resizing
+    /// never happens.
+    pub fn replace(&mut self, mut item: T) -> Option<T> {
+        if self.is_empty() {
+            self.push(item);
+            return None;
         }
 
-        self.into_vec()
+        // SAFETY: self.len() > 0
+        unsafe {
+            swap(&mut item, self.data.get_unchecked_mut(0));
+            self.sift_down(0);
+        }
+        Some(item)
     }
 
-    /// # Safety
+    /// Pops items in descending order and passes them to `f` until either the
+    /// heap is exhausted or `deadline` is reached. Returns the number of items processed.
     ///
-    /// The caller must guarantee that `pos < self.len() && self.len() > 1`.
-    unsafe fn sift_up(&mut self, start: usize, pos: usize) {
-        let len = self.data.len();
-
-        // Climb up the tree in search of the first
-        // element for which `pos` is in the right subtree.
-        let mut cur = pos;
-        let mut ancestor = cur / 2;
-        while ancestor > start && (cur % 2 == *self.bit.get_unchecked(ancestor) as usize) {
-            cur /= 2;
-            ancestor /= 2;
-        }
-
-        // SAFETY: `0 <= ancestor < pos < self.len()`
-        if self.data.get_unchecked(ancestor) < self.data.get_unchecked(pos) {
-            // The pos element has both children.
-            if 2 * pos - 1 < len {
-                *self.bit.get_unchecked_mut(pos) ^= true;
+    /// The deadline is checked before every pop, so at least one item is processed
+    /// if the heap is not empty and the deadline hasn't passed yet.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut heap = WeakHeap::from(vec![1, 5, 2]);
+    /// let mut processed = Vec::new();
+    ///
+    /// let deadline = Instant::now() + Duration::from_secs(60);
+    /// assert_eq!(heap.pop_until_deadline(deadline, |x| processed.push(x)), 3);
+    /// assert_eq!(processed, [5, 2, 1]);
+    ///
+    /// heap.push(4);
+    /// assert_eq!(heap.pop_until_deadline(Instant::now(), |x| processed.push(x)), 0);
+    /// assert_eq!(heap.len(), 1);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn pop_until_deadline<F>(&mut self, deadline: Instant, mut f: F) -> usize
+    where
+        F: FnMut(T),
+    {
+        let mut count = 0;
+        while !self.is_empty() && Instant::now() < deadline {
+            if let Some(item) = self.pop() {
+                f(item);
+                count += 1;
             }
-            let ptr = self.data.as_mut_ptr();
-            std::ptr::swap_nonoverlapping(ptr.add(ancestor), ptr.add(pos), 1);
         }
+        count
     }
 
-    // `sift_up` works correctly only when you need to build a heap from scratch.
-    // Therefore, to maintain the invariant of the heap after adding one element,
-    // a little "longer" sifting is needed.
-
-    /// # Safety
+    /// Pops at most `budget` items in descending order and passes them to `f`.
+    /// Returns the number of items processed.
     ///
-    /// The caller must guarantee that `pos < self.len() && self.len() > 1`.
-    unsafe fn sift_up_push(&mut self, start: usize, pos: usize) -> usize {
-        let len = self.data.len();
-        let mut hole = Hole::new(&mut self.data, pos);
-
-        // Raise the `pos` element to the start until it is guaranteed
-        // to be less than (or equal to) its ancestor.
-        let mut cur = pos;
-        while cur > start {
-            // Climb up the tree in search of the first
-            // element for which pos is in the right subtree.
-            let mut ancestor = cur / 2;
-            while ancestor > start && (cur % 2 == *self.bit.get_unchecked(ancestor) as usize) {
-                cur /= 2;
-                ancestor /= 2;
-            }
-
-            if hole.get(ancestor) < hole.element() {
-                // The pos element has both children.
-                if 2 * pos - 1 < len {
-                    *self.bit.get_unchecked_mut(pos) ^= true;
-                }
-                hole.move_to(ancestor);
-            } else {
-                break; // Heap property restored.
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut heap = WeakHeap::from(vec![1, 5, 2]);
+    /// let mut processed = Vec::new();
+    ///
+    /// assert_eq!(heap.pop_with_budget(2, |x| processed.push(x)), 2);
+    /// assert_eq!(processed, [5, 2]);
+    /// assert_eq!(heap.pop_with_budget(2, |x| processed.push(x)), 1);
+    /// assert!(heap.is_empty());
+    /// ```
+    pub fn pop_with_budget<F>(&mut self, budget: usize, mut f: F) -> usize
+    where
+        F: FnMut(T),
+    {
+        let mut count = 0;
+        while count < budget {
+            match self.pop() {
+                Some(item) => f(item),
+                None => break,
             }
-
-            cur = ancestor;
+            count += 1;
         }
-
-        hole.pos()
+        count
     }
 
-    // Sifting down in a weak heap can be done in *log(2, n)* comparisons,
-    // as opposed to *2log(2, n)* for binary heap.
+    /// Returns references to the `k` greatest items in descending order, or
+    /// to all of them if `k >= self.len()`, without modifying the heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let heap = WeakHeap::from(vec![31, 7, 95, 42, 68, 15]);
+    ///
+    /// assert_eq!(heap.peek_n(3), [&95, &68, &42]);
+    /// assert_eq!(heap.len(), 6);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Every node is not less than the left spine of its right subtree, so
+    /// the candidates for the next greatest item are kept in an auxiliary
+    /// weak heap, which takes *O*(*k*log(*n*)) expected time.
+    #[must_use]
+    pub fn peek_n(&self, k: usize) -> Vec<&T> {
+        self.sorted_iter().take(k).collect()
+    }
 
-    /// Take an element at `pos` and move it down the heap,
-    /// restoring the heap property.
+    /// Returns an iterator visiting the items of the weak heap in descending
+    /// order, without modifying or cloning the heap.
     ///
-    /// # Safety
+    /// # Examples
     ///
-    /// The caller must guarantee that `start < end <= self.len()`.
-    unsafe fn sift_down_range(&mut self, start: usize, end: usize) {
-        if end == 1 {
-            return;
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let heap = WeakHeap::from(vec![31, 7, 95, 42, 68, 15]);
+    ///
+    /// let sorted: Vec<_> = heap.sorted_iter().copied().collect();
+    /// assert_eq!(sorted, [95, 68, 42, 31, 15, 7]);
+    /// assert_eq!(heap.len(), 6);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The candidates for the next greatest item are kept in an auxiliary
+    /// weak heap of positions, as in [`peek_n`], so each step takes
+    /// *O*(log(*n*)) expected time. Every step removes one candidate and adds
+    /// at most two, so after *k* items the auxiliary heap holds at most
+    /// *k* + 1 candidates and uses *O*(*k*) memory, which is allocated as
+    /// the iterator advances, starting with the root when it is created.
+    ///
+    /// [`peek_n`]: WeakHeap::peek_n
+    pub fn sorted_iter(&self) -> SortedIter<'_, T> {
+        let mut candidates = WeakHeap::new();
+        if let Some(item) = self.data.first() {
+            candidates.push(Candidate { item, pos: 0 });
         }
+        SortedIter {
+            heap: self,
+            candidates,
+            remaining: self.len(),
+        }
+    }
 
-        let mut pos = start.max(1);
+    /// Returns `true` if the weak heap contains an item equal to `value`.
+    ///
+    /// The search skips every subtree whose dominating item is less than
+    /// `value`, since no item in it can be equal to `value`.
+    ///
+    /// `WeakHeap` doesn't offer an optional hash index to answer this in
+    /// *O*(1): keeping one up to date would require `Hash + Eq + Clone`
+    /// elements and extra work in every operation that moves elements in or
+    /// out, including [`peek_mut`], [`iter_mut`] and
+    /// [`from_raw_parts`](WeakHeap::from_raw_parts), whether membership
+    /// tests are needed or not.
+    ///
+    /// [`peek_mut`]: WeakHeap::peek_mut
+    /// [`iter_mut`]: WeakHeap::iter_mut
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let heap = WeakHeap::from(vec![1, 5, 2, 8]);
+    ///
+    /// assert!(heap.contains(&5));
+    /// assert!(!heap.contains(&3));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(*n*), but values greater than most of the
+    /// items are found or ruled out after few comparisons.
+    #[must_use]
+    pub fn contains(&self, value: &T) -> bool {
+        self.position(value).is_some()
+    }
 
-        // We go down the left descendants as low as possible.
-        while pos * 2 + (*self.bit.get_unchecked(pos) as usize) < end {
-            pos = 2 * pos + (*self.bit.get_unchecked(pos) as usize);
+    // Returns the position of an item equal to `value`, skipping the subtrees
+    // dominated by an item less than `value`.
+    fn position(&self, value: &T) -> Option<usize> {
+        let len = self.len();
+        let mut stack = Vec::new();
+        if len > 0 {
+            stack.push(0);
         }
+        while let Some(pos) = stack.pop() {
+            let ordering = self.data[pos].cmp(value);
+            if ordering == Ordering::Equal {
+                return Some(pos);
+            }
+            if pos == 0 {
+                // The root has only a right child.
+                if ordering == Ordering::Greater && len > 1 {
+                    stack.push(1);
+                }
+                continue;
+            }
 
-        while pos > start {
-            if self.data.get_unchecked(start) < self.data.get_unchecked(pos) {
-                *self.bit.get_unchecked_mut(pos) ^= true;
-                let ptr = self.data.as_mut_ptr();
-                std::ptr::swap_nonoverlapping(ptr.add(start), ptr.add(pos), 1);
+            // SAFETY: pos < len == self.bit.len().
+            let bit = unsafe { self.bit.get_unchecked(pos) } as usize;
+            let left = 2 * pos + bit;
+            let right = 2 * pos + 1 - bit;
+            if left < len {
+                stack.push(left);
+            }
+            // The right subtree is dominated by `pos`.
+            if ordering == Ordering::Greater && right < len {
+                stack.push(right);
             }
-            pos /= 2;
         }
+        None
     }
 
-    /// # Safety
+    /// Returns the smallest item in the weak heap, or `None` if it is empty.
     ///
-    /// The caller must guarantee that `pos < self.len()`.
-    unsafe fn sift_down(&mut self, pos: usize) {
-        let len = self.len();
-        // SAFETY: pos < len is guaranteed by the caller and
-        //  obviously len = self.len() <= self.len().
-        self.sift_down_range(pos, len);
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::new();
+    /// assert_eq!(heap.peek_min(), None);
+    ///
+    /// heap.push(1);
+    /// heap.push(5);
+    /// heap.push(2);
+    /// assert_eq!(heap.peek_min(), Some(&1));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Only the nodes without a right subtree can hold the minimum, so
+    /// about *n* / 2 comparisons are made. Cost is *O*(*n*).
+    #[must_use]
+    pub fn peek_min(&self) -> Option<&T> {
+        raw::min_position(&self.data, &self.bit, &mut raw::lt).map(|pos| &self.data[pos])
     }
 
-    // Building a heap. Time complexity: O(self.len()).
-    fn rebuild(&mut self) {
-        for n in (1..self.len()).rev() {
-            // SAFETY: n starts from self.len()-1 and goes down to 1.
-            unsafe {
-                self.sift_up(0, n);
-            }
+    /// Removes the smallest item from the weak heap and returns it, or `None` if it
+    /// is empty.
+    ///
+    /// This makes it possible to keep only the best *N* items in a plain `WeakHeap`
+    /// by removing the smallest one whenever the heap grows too large.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::from(vec![4, 1, 3, 2]);
+    ///
+    /// assert_eq!(heap.remove_min(), Some(1));
+    /// assert_eq!(heap.remove_min(), Some(2));
+    /// assert_eq!(heap.into_sorted_vec(), vec![3, 4]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Finding the minimum costs *O*(*n*), restoring the heap afterwards *O*(log(*n*)).
+    pub fn remove_min(&mut self) -> Option<T> {
+        let pos = raw::min_position(&self.data, &self.bit, &mut raw::lt)?;
+        self.bit.pop();
+        let item = self.data.swap_remove(pos);
+        if pos < self.len() {
+            // SAFETY: pos < self.len() and the bit vector was shrunk along with the data.
+            unsafe { raw::sift(&mut self.data, &mut self.bit, pos, &mut raw::lt) };
         }
+        Some(item)
     }
 
-    /// Rebuild assuming data[0..start] is still a proper heap.
-    fn rebuild_tail(&mut self, start: usize) {
-        if start == self.len() {
-            return;
+    /// Removes an item equal to `value` from the weak heap and returns it, or
+    /// `None` if there is none.
+    ///
+    /// The last item takes the place of the removed one and is moved up or
+    /// down from there, so the rest of the heap is left as it is.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::from(vec![4, 1, 3, 2]);
+    ///
+    /// assert_eq!(heap.remove(&3), Some(3));
+    /// assert_eq!(heap.remove(&3), None);
+    /// assert_eq!(heap.into_sorted_vec(), vec![1, 2, 4]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Finding the item costs what [`contains`] costs, *O*(*n*) in the worst
+    /// case, restoring the heap afterwards *O*(log(*n*)).
+    ///
+    /// [`contains`]: WeakHeap::contains
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        let pos = self.position(value)?;
+        self.bit.pop();
+        let item = self.data.swap_remove(pos);
+        if pos < self.len() {
+            // SAFETY: pos < self.len() and the bit vector was shrunk along with the data.
+            unsafe { raw::sift(&mut self.data, &mut self.bit, pos, &mut raw::lt) };
         }
+        Some(item)
+    }
 
-        for i in start..self.len() {
-            // SAFETY: self.len() > 1 and index `i` is always less than self.len();
-            unsafe {
-                self.sift_up_push(0, i);
-            }
+    /// Consumes the `WeakHeap` and returns a vector in sorted
+    /// (ascending) order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut heap = WeakHeap::from(vec![1, 2, 4, 5, 7]);
+    /// heap.push(6);
+    /// heap.push(3);
+    ///
+    /// let vec = heap.into_sorted_vec();
+    /// assert_eq!(vec, [1, 2, 3, 4, 5, 6, 7]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Operation can be done in *O*(*nlog(n)*) like conventional **heapsort**,
+    /// but sorting by a weak heap produces significantly fewer comparisons.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut end = self.len();
+        while end > 1 {
+            end -= 1;
+            // SAFETY: `end` goes from `self.len() - 1` to 1 (both included) so:
+            //  0 < 1 <= end <= self.len() - 1 < self.len()
+            //  Which means 0 < end and end < self.len().
+            unsafe { raw::extract_to_end(&mut self.data, &mut self.bit, end, &mut raw::lt) };
         }
+
+        self.into_vec()
     }
 
-    /// Moves all the elements of `other` into `self`, leaving `other` empty.
+    /// Consumes the `WeakHeap` and returns its `k` greatest elements in sorted
+    /// (ascending) order, or all of them if `k >= self.len()`. The other
+    /// elements are dropped.
     ///
     /// # Examples
     ///
@@ -627,34 +1560,1049 @@ impl<T: Ord> WeakHeap<T> {
     ///
     /// ```
     /// use weakheap::WeakHeap;
+    /// let heap = WeakHeap::from(vec![5, 1, 8, 3, 9, 2]);
     ///
-    /// let v = vec![-10, 1, 2, 3, 3];
-    /// let mut a = WeakHeap::from(v);
+    /// assert_eq!(heap.into_sorted_vec_limit(3), [5, 8, 9]);
+    /// ```
     ///
-    /// let v = vec![-20, 5, 43];
-    /// let mut b = WeakHeap::from(v);
+    /// # Time complexity
     ///
-    /// a.append(&mut b);
+    /// Only `k` sift-down rounds are made, so the cost is *O*(*k*log(*n*)),
+    /// plus *O*(*n*) to drop the other elements.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec_limit(mut self, k: usize) -> Vec<T> {
+        let start = self.sort_greatest(k);
+        let mut data = self.data;
+        data.drain(..start);
+        data
+    }
+
+    /// Consumes the `WeakHeap` and returns its `k` greatest elements in
+    /// arbitrary order, or all of them if `k >= self.len()`.
     ///
-    /// assert_eq!(a.into_sorted_vec(), [-20, -10, 1, 2, 3, 3, 5, 43]);
-    /// assert!(b.is_empty());
+    /// See [`slice::k_largest_unordered`] for details.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let heap = WeakHeap::from(vec![5, 1, 8, 3, 9, 2]);
+    /// let mut best = heap.into_k_largest_unordered(2);
+    /// best.sort();
+    /// assert_eq!(best, [8, 9]);
     /// ```
     ///
     /// # Time complexity
     ///
-    /// Operation can be done in *O*(*nlog(n)*) in worst case, but
-    /// average time complexity is *O*(*n*), where *n* = self.len() + other.len().
-    pub fn append(&mut self, other: &mut Self) {
-        if self.len() < other.len() {
-            swap(self, other);
-        }
+    /// *O*(*n* + *m*log(*k*)) comparisons, where *m* ≤ *n* is the number of
+    /// elements replacing a smaller one among the *k* greatest seen so far.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_k_largest_unordered(self, k: usize) -> Vec<T> {
+        let mut data = self.data;
+        let k = slice::k_largest_unordered(&mut data, k).len();
+        data.truncate(k);
+        data
+    }
 
-        let start = self.data.len();
+    /// Consumes the `WeakHeap` and returns an iterator which yields elements
+    /// in heap order (greatest first), popping them on demand.
+    ///
+    /// Unlike [`into_sorted_vec`], only the consumed elements are sorted.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let heap = WeakHeap::from(vec![1, 2, 3, 4, 5]);
+    /// assert_eq!(heap.into_iter_sorted().take(2).collect::<Vec<_>>(), [5, 4]);
+    /// ```
+    ///
+    /// [`into_sorted_vec`]: WeakHeap::into_sorted_vec
+    pub fn into_iter_sorted(self) -> IntoIterSorted<T> {
+        IntoIterSorted { inner: self }
+    }
 
-        self.data.append(&mut other.data);
-        self.bit.append(&mut other.bit);
+    /// Clears the weak heap, returning an iterator over the removed elements
+    /// in heap order (greatest first).
+    ///
+    /// The elements are popped on demand. If the iterator is dropped before
+    /// being fully consumed, the remaining elements are dropped and the heap
+    /// is left empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut heap = WeakHeap::from(vec![1, 2, 3, 4, 5]);
+    /// assert_eq!(heap.drain_sorted().take(2).collect::<Vec<_>>(), [5, 4]);
+    /// assert!(heap.is_empty());
+    ///
+    /// heap.push(7);
+    /// assert_eq!(heap.peek(), Some(&7));
+    /// ```
+    pub fn drain_sorted(&mut self) -> DrainSorted<'_, T> {
+        DrainSorted { inner: self }
+    }
 
-        self.rebuild_tail(start);
+    /// Creates an iterator which pops the greatest element as long as `pred`
+    /// returns `true` for it.
+    ///
+    /// The iterator stops at the first greatest element for which `pred`
+    /// returns `false`, and leaves that element and all the others in the
+    /// heap. If the iterator is dropped before, the elements not popped yet
+    /// stay in the heap as well.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::cmp::Reverse;
+    /// use weakheap::WeakHeap;
+    ///
+    /// // Timers ordered by their deadline, the earliest first.
+    /// let mut timers = WeakHeap::from(vec![Reverse(30), Reverse(10), Reverse(50), Reverse(20)]);
+    /// let now = 25;
+    ///
+    /// let expired: Vec<_> = timers.drain_while(|&Reverse(deadline)| deadline <= now).collect();
+    /// assert_eq!(expired, [Reverse(10), Reverse(20)]);
+    /// assert_eq!(timers.peek(), Some(&Reverse(30)));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Yielding *k* elements costs *O*(*k*log(*n*)).
+    pub fn drain_while<F>(&mut self, pred: F) -> DrainWhile<'_, T, F>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        DrainWhile {
+            inner: self,
+            pred,
+            done: false,
+        }
+    }
+
+    /// Creates an iterator which removes and yields the elements for which
+    /// `pred` returns `true`, keeping the others in the heap.
+    ///
+    /// The elements are visited in arbitrary order. If the iterator is dropped
+    /// before being fully consumed, the unvisited elements stay in the heap.
+    /// The heap structure is repaired when the iterator is dropped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut heap = WeakHeap::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    /// let mut evens: Vec<_> = heap.extract_if(|x| x % 2 == 0).collect();
+    /// evens.sort();
+    ///
+    /// assert_eq!(evens, [2, 4, 6, 8]);
+    /// assert_eq!(heap.into_sorted_vec(), [1, 3, 5, 7]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Consuming the whole iterator and dropping it costs *O*(*n*).
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, T, F>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let first_removed = self.len();
+        ExtractIf {
+            inner: self,
+            pred,
+            pos: 0,
+            first_removed,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The caller must guarantee that `pos < self.len()`.
+    unsafe fn sift_up(&mut self, pos: usize) -> usize {
+        raw::sift_up(&mut self.data, &self.bit, pos, &mut raw::lt)
+    }
+
+    /// Take an element at `pos` and move it down the heap,
+    /// restoring the heap property.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that `pos < end <= self.len()`.
+    unsafe fn sift_down_range(&mut self, pos: usize, end: usize) {
+        raw::sift_down_range(&mut self.data, &mut self.bit, pos, end, &mut raw::lt);
+    }
+
+    /// # Safety
+    ///
+    /// The caller must guarantee that `pos < self.len()`.
+    unsafe fn sift_down(&mut self, pos: usize) {
+        let len = self.len();
+        // SAFETY: pos < len is guaranteed by the caller and
+        //  obviously len = self.len() <= self.len().
+        self.sift_down_range(pos, len);
+    }
+
+    /// Restores the heap property from scratch, after the elements have been
+    /// modified through [`as_mut_slice_unchecked`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut heap = WeakHeap::from(vec![1, 2, 3]);
+    /// // SAFETY: the heap is rebuilt right after the modification.
+    /// unsafe { heap.as_mut_slice_unchecked() }
+    ///     .iter_mut()
+    ///     .for_each(|x| *x = 10 - *x);
+    /// heap.rebuild();
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [7, 8, 9]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Rebuilding takes *n* - 1 comparisons.
+    ///
+    /// [`as_mut_slice_unchecked`]: WeakHeap::as_mut_slice_unchecked
+    pub fn rebuild(&mut self) {
+        raw::rebuild(&mut self.data, &mut self.bit, &mut raw::lt);
+    }
+
+    /// Restores the heap property assuming only the elements from position
+    /// `start` onwards have been modified through [`as_mut_slice_unchecked`].
+    ///
+    /// Does nothing if `start >= self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut heap = WeakHeap::from(vec![1, 2, 3, 4]);
+    /// // SAFETY: the tail is rebuilt right after the modification.
+    /// let slice = unsafe { heap.as_mut_slice_unchecked() };
+    /// slice[2..].fill(100);
+    /// heap.rebuild_tail(2);
+    ///
+    /// assert_eq!(heap.peek(), Some(&100));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Each element of the tail costs *O*(log(*n*)) in the worst case, so
+    /// [`rebuild`] is preferable when most of the heap has been modified.
+    ///
+    /// [`as_mut_slice_unchecked`]: WeakHeap::as_mut_slice_unchecked
+    /// [`rebuild`]: WeakHeap::rebuild
+    pub fn rebuild_tail(&mut self, start: usize) {
+        raw::rebuild_tail(&mut self.data, &mut self.bit, start, &mut raw::lt);
+    }
+
+    // Restores the heap property assuming data[..start] is still a proper heap,
+    // choosing whichever of `rebuild` and `rebuild_tail` is cheaper. O(self.len()).
+    fn rebuild_from(&mut self, start: usize) {
+        let len = self.len();
+        let tail = len.saturating_sub(start);
+        let log_len = (usize::BITS - len.leading_zeros()) as usize;
+        if tail.saturating_mul(log_len) <= len {
+            self.rebuild_tail(start);
+        } else {
+            self.rebuild();
+        }
+    }
+
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// In other words, remove all elements `e` for which `f(&e)` returns
+    /// `false`. The elements are visited in arbitrary order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut heap = WeakHeap::from(vec![-10, -5, 1, 2, 4, 13]);
+    /// heap.retain(|x| x % 2 == 0);
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [-10, 2, 4]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(*n*). The part of the heap preceding the first removed
+    /// element is kept as it is.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let len = self.len();
+        // The elements before the first removed one keep their positions,
+        // also when `f` panics, since `Vec::retain` only moves later ones.
+        let mut guard = RebuildOnDrop {
+            start: len,
+            heap: self,
+        };
+        let mut pos = 0;
+        guard.heap.data.retain(|item| {
+            let keep = f(item);
+            if !keep && guard.start == len {
+                guard.start = pos;
+            }
+            pos += 1;
+            keep
+        });
+    }
+
+    /// Retains only the elements specified by the predicate, passing a
+    /// mutable reference to it.
+    ///
+    /// In other words, remove all elements `e` such that `f(&mut e)` returns
+    /// `false`, and keep the modifications made to the other ones. The
+    /// elements are visited in arbitrary order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// // Entries of (time to live, name).
+    /// let mut heap = WeakHeap::from(vec![(3, 'a'), (1, 'b'), (2, 'c')]);
+    /// heap.retain_mut(|(ttl, _)| {
+    ///     *ttl -= 1;
+    ///     *ttl > 0
+    /// });
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [(1, 'c'), (2, 'a')]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(*n*). Since any element may have been modified, the heap is
+    /// always rebuilt, which takes *n* - 1 comparisons.
+    pub fn retain_mut<F>(&mut self, f: F)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        // Any element may have been modified, also when `f` panics.
+        let guard = RebuildOnDrop {
+            start: 0,
+            heap: self,
+        };
+        guard.heap.data.retain_mut(f);
+    }
+
+    /// Removes all elements strictly less than `bound`.
+    ///
+    /// This is [`retain`] with the predicate `|x| x >= bound`, except that the
+    /// heap is cleared right away if its greatest element is less than
+    /// `bound`. The heap keeps its allocation.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut beam = WeakHeap::from(vec![7, 2, 9, 4, 5]);
+    /// beam.prune(&5);
+    ///
+    /// assert_eq!(beam.into_sorted_vec(), [5, 7, 9]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(*n*), or *O*(1) if no element is kept.
+    ///
+    /// [`retain`]: WeakHeap::retain
+    pub fn prune(&mut self, bound: &T) {
+        match self.peek() {
+            Some(top) if top < bound => self.clear(),
+            Some(_) => self.retain(|item| item >= bound),
+            None => {}
+        }
+    }
+
+    /// Keeps only the `k` greatest elements, dropping all the others. Nothing
+    /// happens if `k >= self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut scores = WeakHeap::from(vec![40, 95, 12, 77, 63, 88]);
+    /// scores.truncate_top(3);
+    ///
+    /// assert_eq!(scores.len(), 3);
+    /// assert_eq!(scores.into_sorted_vec(), [77, 88, 95]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(*n* + *k*log(*n*)). The `k` greatest elements
+    /// are sorted in place at the end of the heap, then the rest is dropped.
+    pub fn truncate_top(&mut self, k: usize) {
+        if k >= self.len() {
+            return;
+        }
+        let start = self.sort_greatest(k);
+        self.data.drain(..start);
+        // A vector sorted in descending order with all bits cleared is a
+        // weak heap.
+        self.data.reverse();
+        self.bit.clear();
+        self.bit.resize(k, false);
+    }
+
+    /// Moves all the elements of `other` into `self`, leaving `other` empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let v = vec![-10, 1, 2, 3, 3];
+    /// let mut a = WeakHeap::from(v);
+    ///
+    /// let v = vec![-20, 5, 43];
+    /// let mut b = WeakHeap::from(v);
+    ///
+    /// a.append(&mut b);
+    ///
+    /// assert_eq!(a.into_sorted_vec(), [-20, -10, 1, 2, 3, 3, 5, 43]);
+    /// assert!(b.is_empty());
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*) in the worst case, where *n* = self.len() + other.len(). The
+    /// smaller heap is either sifted up element by element or, if that would
+    /// take more than the *n* - 1 comparisons of a full rebuild, the whole
+    /// heap is rebuilt.
+    pub fn append(&mut self, other: &mut Self) {
+        if self.len() < other.len() {
+            swap(self, other);
+        }
+
+        let start = self.data.len();
+
+        self.reserve(other.len());
+        self.data.append(&mut other.data);
+        self.bit.append(&mut other.bit);
+
+        self.rebuild_from(start);
+        self.debug_check_invariants("append");
+    }
+
+    /// Consumes two heaps and returns a heap holding the elements of both.
+    ///
+    /// Unlike [`append`], no heap needs to be borrowed mutably, and the
+    /// buffer with the larger capacity is reused for the result.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let a = WeakHeap::from(vec![-10, 1, 2, 3, 3]);
+    /// let b = WeakHeap::from(vec![-20, 5, 43]);
+    ///
+    /// let heap = a.meld(b);
+    /// assert_eq!(heap.into_sorted_vec(), [-20, -10, 1, 2, 3, 3, 5, 43]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Same as [`append`].
+    ///
+    /// [`append`]: WeakHeap::append
+    #[must_use = "`self` and `other` will be dropped if the result is not used"]
+    pub fn meld(mut self, mut other: WeakHeap<T>) -> WeakHeap<T> {
+        if self.capacity() < other.capacity() {
+            swap(&mut self, &mut other);
+        }
+
+        let start = self.data.len();
+
+        self.reserve(other.len());
+        self.data.append(&mut other.data);
+        self.bit.append(&mut other.bit);
+
+        self.rebuild_from(start);
+        self
+    }
+
+    /// Merges any number of heaps into one.
+    ///
+    /// The elements of all heaps are moved into the buffer of the largest one
+    /// at once, and the heap property is restored in a single pass, instead
+    /// of once per heap as with repeated [`append`] calls.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let heaps = vec![
+    ///     WeakHeap::from(vec![3, 1]),
+    ///     WeakHeap::from(vec![4, 1, 5]),
+    ///     WeakHeap::from(vec![9, 2, 6]),
+    /// ];
+    ///
+    /// let heap = WeakHeap::merge_many(heaps);
+    /// assert_eq!(heap.into_sorted_vec(), [1, 1, 2, 3, 4, 5, 6, 9]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*) in the worst case, where *n* is the total number of elements.
+    ///
+    /// [`append`]: WeakHeap::append
+    #[must_use]
+    pub fn merge_many(mut heaps: Vec<WeakHeap<T>>) -> WeakHeap<T> {
+        let largest = match (0..heaps.len()).max_by_key(|&i| heaps[i].len()) {
+            Some(largest) => largest,
+            None => return WeakHeap::new(),
+        };
+        let mut merged = heaps.swap_remove(largest);
+        let start = merged.len();
+
+        merged.data.reserve(heaps.iter().map(WeakHeap::len).sum());
+        for mut heap in heaps {
+            merged.data.append(&mut heap.data);
+        }
+        // The bits of the other heaps are of no use in the merged one.
+        merged.bit.resize(merged.data.len(), false);
+
+        merged.rebuild_from(start);
+        merged
+    }
+
+    /// Returns the `k` greatest items of `iter` in descending order, or all
+    /// of them if there are fewer than `k`.
+    ///
+    /// Only `k` candidates are kept in memory while `iter` is consumed.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let scores = [31, 7, 95, 42, 68, 15];
+    /// assert_eq!(WeakHeap::nlargest(3, scores), [95, 68, 42]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*log(*k*)) in the worst case, where *n* is the number of items
+    /// of `iter`.
+    pub fn nlargest<I: IntoIterator<Item = T>>(k: usize, iter: I) -> Vec<T> {
+        WeakHeap::nsmallest(k, iter.into_iter().map(Reverse))
+            .into_iter()
+            .map(|Reverse(item)| item)
+            .collect()
+    }
+
+    /// Returns the `k` smallest items of `iter` in ascending order, or all
+    /// of them if there are fewer than `k`.
+    ///
+    /// Only `k` candidates are kept in memory while `iter` is consumed.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let latencies = [31, 7, 95, 42, 68, 15];
+    /// assert_eq!(WeakHeap::nsmallest(2, latencies), [7, 15]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n*log(*k*)) in the worst case, where *n* is the number of items
+    /// of `iter`.
+    pub fn nsmallest<I: IntoIterator<Item = T>>(k: usize, iter: I) -> Vec<T> {
+        let iter = iter.into_iter();
+        let mut heap = WeakHeap::with_capacity(k.min(iter.size_hint().0));
+        for item in iter {
+            if heap.len() < k {
+                heap.push(item);
+            } else {
+                // Drops the greatest of the candidates and `item`.
+                heap.pushpop(item);
+            }
+        }
+        heap.into_sorted_vec()
+    }
+
+    /// Consumes the `WeakHeap` and splits it into `n` valid heaps whose sizes
+    /// differ by at most one, so that a pool of workers can each take a shard.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let heap = WeakHeap::from(vec![5, 1, 8, 3, 9, 2, 7]);
+    /// let shards = heap.split_to_workers(3);
+    ///
+    /// assert_eq!(shards.len(), 3);
+    /// let sizes: Vec<usize> = shards.iter().map(|h| h.len()).collect();
+    /// assert_eq!(sizes, [3, 2, 2]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Operation can be done in *O*(*n*), where *n* = self.len().
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn split_to_workers(self, n: usize) -> Vec<WeakHeap<T>> {
+        assert!(n > 0, "cannot split a heap into zero parts");
+
+        let mut data = self.data;
+        let (size, rest) = (data.len() / n, data.len() % n);
+
+        // Split from the end, so that every element is moved at most once.
+        let mut shards = Vec::with_capacity(n);
+        for i in (1..n).rev() {
+            let start = i * size + i.min(rest);
+            shards.push(WeakHeap::from(data.split_off(start)));
+        }
+        shards.push(WeakHeap::from(data));
+        shards.reverse();
+        shards
+    }
+
+    /// Splits the heap into two at the given position of the underlying
+    /// vector. `self` keeps the elements `[0, at)` and the returned heap
+    /// gets the elements `[at, len)`.
+    ///
+    /// A prefix of a weak heap is a weak heap on its own, so only the returned
+    /// heap is rebuilt. The greatest element stays in `self` unless `at` is 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > self.len()`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut heap = WeakHeap::from(vec![5, 1, 8, 3, 9, 2]);
+    /// let other = heap.split_off(4);
+    ///
+    /// assert_eq!(heap.len(), 4);
+    /// assert_eq!(other.len(), 2);
+    /// assert_eq!(heap.peek(), Some(&9));
+    ///
+    /// let mut all = heap.into_vec();
+    /// all.extend(other.into_sorted_vec());
+    /// all.sort();
+    /// assert_eq!(all, [1, 2, 3, 5, 8, 9]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n* - `at`), the cost of moving out and rebuilding the tail.
+    #[must_use]
+    pub fn split_off(&mut self, at: usize) -> WeakHeap<T> {
+        let len = self.len();
+        assert!(
+            at <= len,
+            "`at` split index (is {at}) should be <= len (is {len})"
+        );
+
+        self.bit.truncate(at);
+        WeakHeap::from(self.data.split_off(at))
+    }
+
+    /// Appends `additional` elements that were written directly into the spare
+    /// capacity returned by [`spare_capacity_mut`], and restores the heap property.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that `additional <= self.capacity() - self.len()`
+    /// and that the first `additional` elements of the spare capacity have been initialized.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut heap = WeakHeap::from(vec![2, 7]);
+    /// heap.reserve(3);
+    ///
+    /// let spare = heap.spare_capacity_mut();
+    /// spare[0].write(5);
+    /// spare[1].write(9);
+    /// spare[2].write(1);
+    ///
+    /// // SAFETY: three elements of the spare capacity were initialized.
+    /// unsafe { heap.heapify_appended(3) };
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [1, 2, 5, 7, 9]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Operation can be done in *O*(*nlog(n)*) in worst case, but
+    /// average time complexity is *O*(*n*), where *n* = self.len() + additional.
+    ///
+    /// [`spare_capacity_mut`]: WeakHeap::spare_capacity_mut
+    pub unsafe fn heapify_appended(&mut self, additional: usize) {
+        let start = self.len();
+        debug_assert!(additional <= self.data.capacity() - start);
+
+        self.bit.resize(start + additional, false);
+        // SAFETY: the caller guarantees that the elements are initialized
+        //  and fit into the allocated capacity.
+        self.data.set_len(start + additional);
+
+        self.rebuild_tail(start);
+    }
+
+    /// Moves all the elements of `other` into `self`, leaving `other` empty,
+    /// like [`append`], but returning an error instead of panicking or aborting
+    /// if memory cannot be reserved.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows, or the allocator reports a failure, then an
+    /// error is returned and both heaps are left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut a = WeakHeap::from(vec![-10, 1, 2, 3, 3]);
+    /// let mut b = WeakHeap::from(vec![-20, 5, 43]);
+    ///
+    /// a.try_append(&mut b).expect("out of memory");
+    ///
+    /// assert_eq!(a.into_sorted_vec(), [-20, -10, 1, 2, 3, 3, 5, 43]);
+    /// assert!(b.is_empty());
+    /// ```
+    ///
+    /// [`append`]: WeakHeap::append
+    pub fn try_append(&mut self, other: &mut Self) -> Result<(), WeakHeapError> {
+        if self.len() < other.len() {
+            other.try_reserve(self.len())?;
+        } else {
+            self.try_reserve(other.len())?;
+        }
+        self.append(other);
+        Ok(())
+    }
+
+    /// Moves all the elements of vector `other` into `self`, leaving `other` empty,
+    /// like [`append_vec`], but returning an error instead of panicking or aborting
+    /// if memory cannot be reserved.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows, or the allocator reports a failure, then an
+    /// error is returned and both `self` and `other` are left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut a = WeakHeap::from(vec![-10, 1, 2, 3, 3]);
+    /// let mut v = vec![-20, 5, 43];
+    ///
+    /// a.try_append_vec(&mut v).expect("out of memory");
+    ///
+    /// assert_eq!(a.into_sorted_vec(), [-20, -10, 1, 2, 3, 3, 5, 43]);
+    /// assert!(v.is_empty());
+    /// ```
+    ///
+    /// [`append_vec`]: WeakHeap::append_vec
+    pub fn try_append_vec(&mut self, other: &mut Vec<T>) -> Result<(), WeakHeapError> {
+        self.try_reserve(other.len())?;
+        self.append_vec(other);
+        Ok(())
+    }
+
+    /// Converts a `Vec<T>` into a `WeakHeap<T>` like `WeakHeap::from`, returning
+    /// an error instead of panicking or aborting if memory cannot be reserved.
+    ///
+    /// # Errors
+    ///
+    /// If the allocator reports a failure, then an error is returned and the
+    /// vector is dropped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let heap = WeakHeap::try_from_vec(vec![5, 3, 2, 4, 1]).expect("out of memory");
+    /// assert_eq!(heap.into_sorted_vec(), vec![1, 2, 3, 4, 5]);
+    /// ```
+    pub fn try_from_vec(vec: Vec<T>) -> Result<WeakHeap<T>, WeakHeapError> {
+        let mut bit = BitVec::new();
+        bit.try_reserve_exact(vec.capacity())?;
+        bit.resize(vec.len(), false);
+
+        let mut heap = WeakHeap { data: vec, bit };
+        heap.rebuild();
+        Ok(heap)
+    }
+
+    /// Converts a `Vec<T>` sorted in ascending order into a `WeakHeap<T>`
+    /// without comparing any elements.
+    ///
+    /// It is a logic error for `vec` not to be sorted. The behavior resulting
+    /// from such a logic error is not specified, but will not be undefined
+    /// behavior.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::from_sorted_vec(vec![1, 2, 3, 4, 5]);
+    /// assert_eq!(heap.pop(), Some(5));
+    /// assert_eq!(heap.pop(), Some(4));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The elements are reversed in place, which takes *O*(*n*) time.
+    #[must_use]
+    pub fn from_sorted_vec(mut vec: Vec<T>) -> WeakHeap<T> {
+        vec.reverse();
+        WeakHeap::from_sorted_vec_desc(vec)
+    }
+
+    /// Converts a `Vec<T>` sorted in descending order into a `WeakHeap<T>`
+    /// without comparing or moving any elements.
+    ///
+    /// With all reverse bits cleared, the distinguished ancestor of every
+    /// element precedes it in the vector, so a descending vector already is
+    /// a weak heap.
+    ///
+    /// It is a logic error for `vec` not to be sorted. The behavior resulting
+    /// from such a logic error is not specified, but will not be undefined
+    /// behavior.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::from_sorted_vec_desc(vec![5, 4, 3, 2, 1]);
+    /// heap.push(6);
+    /// assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4, 5, 6]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Only the reverse bits are allocated, no element is touched.
+    #[must_use]
+    pub fn from_sorted_vec_desc(vec: Vec<T>) -> WeakHeap<T> {
+        let bit = BitVec::zeros(vec.len());
+        WeakHeap { data: vec, bit }
+    }
+
+    /// Restores a heap from the parts returned by [`WeakHeap::into_raw_parts`]
+    /// without comparing any elements.
+    ///
+    /// The packed bits are taken over as they are, so nothing is copied or
+    /// allocated. With debug assertions enabled, the parts are validated,
+    /// which takes *n* - 1 comparisons.
+    ///
+    /// # Safety
+    ///
+    /// The parts must pass [`WeakHeap::check_integrity`]: `data` and `bits`
+    /// must have the same length, the reverse bit of the root must be clear,
+    /// and every element of `data` must not be greater than its distinguished
+    /// ancestor as defined by `bits`. Parts obtained from
+    /// [`WeakHeap::into_raw_parts`] satisfy all three.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// // The distinguished ancestor of 4 and 3 is 5, the one of 1 is 4.
+    /// let data = vec![5, 4, 1, 3];
+    /// let bits = [false, true, false, false].into_iter().collect();
+    ///
+    /// // SAFETY: the lengths match, the root's bit is clear and the parts
+    /// // form a weak heap.
+    /// let heap = unsafe { WeakHeap::from_raw_parts(data, bits) };
+    /// assert_eq!(heap.into_sorted_vec(), [1, 3, 4, 5]);
+    /// ```
+    pub unsafe fn from_raw_parts(data: Vec<T>, bits: ReverseBits) -> WeakHeap<T> {
+        let heap = WeakHeap {
+            data,
+            bit: bits.bit,
+        };
+        debug_assert!(
+            heap.check_integrity().is_ok(),
+            "the parts do not form a weak heap"
+        );
+        heap
+    }
+
+    /// Restores a heap from the parts returned by [`WeakHeap::into_raw_parts`],
+    /// checking them with [`WeakHeap::check_integrity`] first.
+    ///
+    /// This is the safe counterpart of [`WeakHeap::from_raw_parts`], meant for
+    /// parts of unknown origin, such as a snapshot read from disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first violation of the weak heap invariants found in the
+    /// parts, which are dropped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::{WeakHeap, WeakHeapError};
+    ///
+    /// let bits = [false, true, false, false].into_iter().collect();
+    /// let heap = WeakHeap::try_from_raw_parts(vec![5, 4, 1, 3], bits);
+    /// assert_eq!(heap.unwrap().peek(), Some(&5));
+    ///
+    /// let bits = [false; 4].into_iter().collect();
+    /// let heap = WeakHeap::try_from_raw_parts(vec![5, 1, 4, 3], bits);
+    /// assert_eq!(heap, Err(WeakHeapError::HeapOrder { pos: 3, ancestor: 1 }));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The parts are validated with *n* - 1 comparisons.
+    pub fn try_from_raw_parts(
+        data: Vec<T>,
+        bits: ReverseBits,
+    ) -> Result<WeakHeap<T>, WeakHeapError> {
+        let heap = WeakHeap {
+            data,
+            bit: bits.bit,
+        };
+        heap.check_integrity()?;
+        Ok(heap)
+    }
+
+    /// Verifies the invariants of the heap, returning the first violation
+    /// found:
+    ///
+    /// * there are as many reverse bits as elements;
+    /// * the reverse bit of the root is clear;
+    /// * no element is greater than its distinguished ancestor.
+    ///
+    /// A heap can only break its invariants through a broken `Ord`
+    /// implementation, elements modified while in the heap, or parts passed to
+    /// [`WeakHeap::from_raw_parts`] which do not form a weak heap.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`WeakHeapError::LengthMismatch`], [`WeakHeapError::RootBitSet`]
+    /// or [`WeakHeapError::HeapOrder`] for the first violation found.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::from(vec![1, 5, 2, 4, 3]);
+    /// heap.push(7);
+    /// heap.pop();
+    ///
+    /// assert_eq!(heap.check_integrity(), Ok(()));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *n* - 1 comparisons.
+    pub fn check_integrity(&self) -> Result<(), WeakHeapError> {
+        if self.data.len() != self.bit.len() {
+            return Err(WeakHeapError::LengthMismatch {
+                len: self.data.len(),
+                bits: self.bit.len(),
+            });
+        }
+        if self.bit.get(0) == Some(true) {
+            return Err(WeakHeapError::RootBitSet);
+        }
+        match raw::order_violation(&self.data, &self.bit, &mut raw::lt) {
+            Some((pos, ancestor)) => Err(WeakHeapError::HeapOrder { pos, ancestor }),
+            None => Ok(()),
+        }
+    }
+
+    // With the `debug-invariants` feature in a debug build, panics if the
+    // heap does not pass `check_integrity` after the operation `op`.
+    #[inline]
+    fn debug_check_invariants(&self, op: &str) {
+        if cfg!(all(feature = "debug-invariants", debug_assertions)) {
+            if let Err(err) = self.check_integrity() {
+                panic!("weak heap corrupted after `{}`: {}", op, err);
+            }
+        }
     }
 
     /// Moves all the elements of vector `other` into `self`, leaving `other` empty.
@@ -682,10 +2630,58 @@ impl<T: Ord> WeakHeap<T> {
     pub fn append_vec(&mut self, other: &mut Vec<T>) {
         let start = self.len();
 
-        self.bit.append(&mut vec![false; other.len()]);
+        self.reserve(other.len());
+        self.bit.resize(start + other.len(), false);
         self.data.append(other);
 
         self.rebuild_tail(start);
+        self.debug_check_invariants("append_vec");
+    }
+}
+
+impl<'a, T: Ord> WeakHeap<&'a T> {
+    /// Builds a heap of references to the elements of `slice`, without cloning
+    /// or moving them.
+    ///
+    /// Useful for ranking data owned elsewhere.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let names = vec![String::from("carol"), String::from("alice"), String::from("bob")];
+    /// let mut heap = WeakHeap::from_refs(&names);
+    /// assert_eq!(heap.pop(), Some(&names[0]));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Building takes *n* - 1 comparisons.
+    #[must_use]
+    pub fn from_refs(slice: &'a [T]) -> WeakHeap<&'a T> {
+        WeakHeap::from(slice.iter().collect::<Vec<_>>())
+    }
+
+    /// Consumes the heap and returns the references in sorted (ascending) order
+    /// of the referenced elements.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let data = [3, 1, 2];
+    /// let heap = WeakHeap::from_refs(&data);
+    /// assert_eq!(heap.into_sorted_refs(), [&1, &2, &3]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_refs(self) -> Vec<&'a T> {
+        self.into_sorted_vec()
     }
 }
 
@@ -737,11 +2733,14 @@ impl<T> WeakHeap<T> {
     /// Cost is *O*(1) in the worst case.
     #[must_use]
     pub fn peek(&self) -> Option<&T> {
-        self.data.get(0)
+        self.data.first()
     }
 
     /// Returns the number of elements the weak heap can hold without reallocating.
     ///
+    /// The storage of the reverse bits is always grown together with the
+    /// elements, so it never needs to reallocate before the elements do.
+    ///
     /// # Examples
     ///
     /// Basic usage:
@@ -757,6 +2756,114 @@ impl<T> WeakHeap<T> {
         self.data.capacity()
     }
 
+    /// Returns the number of bytes allocated for the elements and the reverse
+    /// bits of the weak heap.
+    ///
+    /// Memory owned by the elements themselves is not included. See
+    /// [`memory_usage`] for a breakdown.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let heap: WeakHeap<u32> = WeakHeap::with_capacity(64);
+    /// assert!(heap.allocated_bytes() >= 64 * 4 + 8);
+    /// ```
+    ///
+    /// [`memory_usage`]: WeakHeap::memory_usage
+    #[must_use]
+    pub fn allocated_bytes(&self) -> usize {
+        self.memory_usage().total_bytes()
+    }
+
+    /// Returns a breakdown of the memory allocated by the weak heap: the
+    /// length and capacity of the data vector, and the bytes held by it and
+    /// by the bit storage.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let heap = WeakHeap::from(vec![1u64, 2, 3]);
+    /// let usage = heap.memory_usage();
+    ///
+    /// assert_eq!(usage.len, 3);
+    /// assert_eq!(usage.data_bytes, usage.capacity * 8);
+    /// assert_eq!(usage.bit_bytes, 8);
+    /// ```
+    #[must_use]
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            len: self.data.len(),
+            capacity: self.data.capacity(),
+            data_bytes: self.data.capacity() * core::mem::size_of::<T>(),
+            bit_capacity: self.bit.capacity(),
+            bit_bytes: self.bit.allocated_bytes(),
+        }
+    }
+
+    /// Returns the remaining spare capacity of the weak heap as a slice of
+    /// `MaybeUninit<T>`.
+    ///
+    /// The returned slice can be used to fill the heap with data (e.g. by reading
+    /// from a file) before marking the data as initialized using the
+    /// [`heapify_appended`] method.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut heap: WeakHeap<i32> = WeakHeap::with_capacity(10);
+    /// assert!(heap.spare_capacity_mut().len() >= 10);
+    /// ```
+    ///
+    /// [`heapify_appended`]: WeakHeap::heapify_appended
+    #[inline]
+    pub fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        self.data.spare_capacity_mut()
+    }
+
+    /// Returns a mutable slice of all values in the underlying vector, in
+    /// arbitrary order, for bulk modification of the elements in place.
+    ///
+    /// # Safety
+    ///
+    /// The modifications may break the heap property. The caller must call
+    /// [`rebuild`] (or [`rebuild_tail`] if only a tail of the slice changed)
+    /// before any other operation on the heap, otherwise the results of the
+    /// other operations are unspecified.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut heap = WeakHeap::from(vec![(1, 'a'), (5, 'b'), (3, 'c')]);
+    /// // SAFETY: the heap is rebuilt right after the modification.
+    /// for (priority, _) in unsafe { heap.as_mut_slice_unchecked() } {
+    ///     *priority = -*priority;
+    /// }
+    /// heap.rebuild();
+    ///
+    /// assert_eq!(heap.pop(), Some((-1, 'a')));
+    /// ```
+    ///
+    /// [`rebuild`]: WeakHeap::rebuild
+    /// [`rebuild_tail`]: WeakHeap::rebuild_tail
+    #[inline]
+    pub unsafe fn as_mut_slice_unchecked(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
     /// Reserves the minimum capacity for exactly `additional` more elements to be inserted in the
     /// given `WeakHeap`. Does nothing if the capacity is already sufficient.
     ///
@@ -783,7 +2890,7 @@ impl<T> WeakHeap<T> {
     /// [`reserve`]: WeakHeap::reserve
     pub fn reserve_exact(&mut self, additional: usize) {
         self.data.reserve_exact(additional);
-        self.bit.reserve_exact(additional);
+        self.match_bit_capacity();
     }
 
     /// Reserves capacity for at least `additional` more elements to be inserted in the
@@ -806,7 +2913,57 @@ impl<T> WeakHeap<T> {
     /// ```
     pub fn reserve(&mut self, additional: usize) {
         self.data.reserve(additional);
-        self.bit.reserve(additional);
+        self.match_bit_capacity();
+    }
+
+    /// Tries to reserve the minimum capacity for exactly `additional` more elements
+    /// to be inserted in the given `WeakHeap`. Does nothing if the capacity is
+    /// already sufficient.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows, or the allocator reports a failure, then an error
+    /// is returned.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap: WeakHeap<i32> = WeakHeap::new();
+    /// heap.try_reserve_exact(100).expect("out of memory");
+    /// assert!(heap.capacity() >= 100);
+    /// ```
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), WeakHeapError> {
+        self.data.try_reserve_exact(additional)?;
+        self.try_match_bit_capacity()
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements to be inserted
+    /// in the given `WeakHeap`. The collection may reserve more space to avoid
+    /// frequent reallocations.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows, or the allocator reports a failure, then an error
+    /// is returned.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap: WeakHeap<i32> = WeakHeap::new();
+    /// heap.try_reserve(100).expect("out of memory");
+    /// assert!(heap.capacity() >= 100);
+    ///
+    /// assert!(heap.try_reserve(usize::MAX).is_err());
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), WeakHeapError> {
+        self.data.try_reserve(additional)?;
+        self.try_match_bit_capacity()
     }
 
     /// Discards as much additional capacity as possible.
@@ -825,7 +2982,7 @@ impl<T> WeakHeap<T> {
     /// ```
     pub fn shrink_to_fit(&mut self) {
         self.data.shrink_to_fit();
-        self.bit.shrink_to_fit();
+        self.bit.shrink_to(self.data.capacity());
     }
 
     /// Discards capacity with a lower bound.
@@ -848,7 +3005,21 @@ impl<T> WeakHeap<T> {
     #[inline]
     pub fn shrink_to(&mut self, min_capacity: usize) {
         self.data.shrink_to(min_capacity);
-        self.bit.shrink_to(min_capacity);
+        self.bit.shrink_to(self.data.capacity());
+    }
+
+    // Grows the bit storage to hold a bit for every element the data vector
+    // can hold, so that the elements decide when both reallocate.
+    fn match_bit_capacity(&mut self) {
+        self.bit
+            .reserve_exact(self.data.capacity() - self.bit.len());
+    }
+
+    // Fallible version of `match_bit_capacity`.
+    fn try_match_bit_capacity(&mut self) -> Result<(), WeakHeapError> {
+        self.bit
+            .try_reserve_exact(self.data.capacity() - self.bit.len())?;
+        Ok(())
     }
 
     /// Consumes the `WeakHeap<T>` and returns the underlying vector Vec<T>
@@ -875,6 +3046,63 @@ impl<T> WeakHeap<T> {
         self.data
     }
 
+    /// Consumes the heap and returns a heap of the results of `f` applied to
+    /// every element.
+    ///
+    /// The result can hold at least as many elements as `self` without
+    /// reallocating, and its reverse bits reuse the allocation of `self`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let heap = WeakHeap::from(vec![3, -7, 5]);
+    ///
+    /// let heap = heap.map(|x: i32| x.abs());
+    /// assert_eq!(heap.into_sorted_vec(), [3, 5, 7]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The new heap is built with a single rebuild of *n* - 1 comparisons.
+    pub fn map<U: Ord, F: FnMut(T) -> U>(self, f: F) -> WeakHeap<U> {
+        let mut data = Vec::with_capacity(self.data.capacity());
+        data.extend(self.data.into_iter().map(f));
+        let mut heap = WeakHeap {
+            data,
+            bit: self.bit,
+        };
+        heap.rebuild();
+        heap
+    }
+
+    /// Consumes the heap and returns its elements, in heap order, together
+    /// with its packed reverse bits. The heap can be restored from them with
+    /// [`WeakHeap::from_raw_parts`] without comparing any elements.
+    ///
+    /// Neither the elements nor the bits are copied.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let heap = WeakHeap::from(vec![1, 5, 2]);
+    /// let (data, bits) = heap.into_raw_parts();
+    /// assert_eq!(data.len(), bits.len());
+    ///
+    /// // SAFETY: the parts come from `into_raw_parts`.
+    /// let heap = unsafe { WeakHeap::from_raw_parts(data, bits) };
+    /// assert_eq!(heap.peek(), Some(&5));
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_raw_parts(self) -> (Vec<T>, ReverseBits) {
+        (self.data, ReverseBits { bit: self.bit })
+    }
+
     /// Returns the length of the weak heap.
     ///
     /// # Examples
@@ -964,79 +3192,6 @@ impl<T> WeakHeap<T> {
     }
 }
 
-/// Hole represents a hole in a slice i.e., an index without valid value
-/// (because it was moved from or duplicated).
-/// In drop, `Hole` will restore the slice by filling the hole
-/// position with the value that was originally removed.
-struct Hole<'a, T: 'a> {
-    data: &'a mut [T],
-    elt: ManuallyDrop<T>,
-    pos: usize,
-}
-
-impl<'a, T> Hole<'a, T> {
-    /// Create a new `Hole` at index `pos`.
-    ///
-    /// Unsafe because pos must be within the data slice.
-    #[inline]
-    unsafe fn new(data: &'a mut [T], pos: usize) -> Self {
-        debug_assert!(pos < data.len());
-        // SAFE: pos should be inside the slice
-        let elt = ptr::read(data.get_unchecked(pos));
-        Hole {
-            data,
-            elt: ManuallyDrop::new(elt),
-            pos,
-        }
-    }
-
-    #[inline]
-    fn pos(&self) -> usize {
-        self.pos
-    }
-
-    /// Returns a reference to the element removed.
-    #[inline]
-    fn element(&self) -> &T {
-        &self.elt
-    }
-
-    /// Returns a reference to the element at `index`.
-    ///
-    /// Unsafe because index must be within the data slice and not equal to pos.
-    #[inline]
-    unsafe fn get(&self, index: usize) -> &T {
-        debug_assert!(index != self.pos);
-        debug_assert!(index < self.data.len());
-        self.data.get_unchecked(index)
-    }
-
-    /// Move hole to new location
-    ///
-    /// Unsafe because index must be within the data slice and not equal to pos.
-    #[inline]
-    unsafe fn move_to(&mut self, index: usize) {
-        debug_assert!(index != self.pos);
-        debug_assert!(index < self.data.len());
-        let ptr = self.data.as_mut_ptr();
-        let index_ptr: *const _ = ptr.add(index);
-        let hole_ptr = ptr.add(self.pos);
-        ptr::copy_nonoverlapping(index_ptr, hole_ptr, 1);
-        self.pos = index;
-    }
-}
-
-impl<T> Drop for Hole<'_, T> {
-    #[inline]
-    fn drop(&mut self) {
-        // fill the hole again
-        unsafe {
-            let pos = self.pos;
-            ptr::copy_nonoverlapping(&*self.elt, self.data.get_unchecked_mut(pos), 1);
-        }
-    }
-}
-
 impl<T: Ord> From<Vec<T>> for WeakHeap<T> {
     /// Converts a `Vec<T>` into a `WeakHeap<T>`.
     ///
@@ -1052,11 +3207,9 @@ impl<T: Ord> From<Vec<T>> for WeakHeap<T> {
     /// assert_eq!(heap.into_sorted_vec(), vec![1, 2, 3, 4, 5]);
     /// ```
     fn from(vec: Vec<T>) -> WeakHeap<T> {
-        let n = vec.len();
-        let mut heap = WeakHeap {
-            data: vec,
-            bit: vec![false; n],
-        };
+        let mut bit = BitVec::with_capacity(vec.capacity());
+        bit.resize(vec.len(), false);
+        let mut heap = WeakHeap { data: vec, bit };
         heap.rebuild();
         heap
     }
@@ -1085,6 +3238,78 @@ impl<T: Ord, const N: usize> From<[T; N]> for WeakHeap<T> {
     }
 }
 
+impl<T: Ord> From<BinaryHeap<T>> for WeakHeap<T> {
+    /// Converts a `BinaryHeap<T>` into a `WeakHeap<T>`.
+    ///
+    /// This conversion reuses the vector of the binary heap and rebuilds it
+    /// in-place, and has *O*(*n*) time complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::BinaryHeap;
+    /// use weakheap::WeakHeap;
+    ///
+    /// let binary = BinaryHeap::from(vec![5, 3, 2, 4, 1]);
+    /// let heap = WeakHeap::from(binary);
+    /// assert_eq!(heap.into_sorted_vec(), vec![1, 2, 3, 4, 5]);
+    /// ```
+    fn from(heap: BinaryHeap<T>) -> WeakHeap<T> {
+        WeakHeap::from(heap.into_vec())
+    }
+}
+
+impl<T: Ord> From<BTreeSet<T>> for WeakHeap<T> {
+    /// Converts a `BTreeSet<T>` into a `WeakHeap<T>`.
+    ///
+    /// The set is consumed in descending order, which is a weak heap as it
+    /// is, so no elements are compared.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// use weakheap::WeakHeap;
+    ///
+    /// let set = BTreeSet::from([5, 3, 2, 4, 1]);
+    /// let heap = WeakHeap::from(set);
+    /// assert_eq!(heap.peek(), Some(&5));
+    /// assert_eq!(heap.into_sorted_vec(), vec![1, 2, 3, 4, 5]);
+    /// ```
+    fn from(set: BTreeSet<T>) -> WeakHeap<T> {
+        WeakHeap::from_sorted_vec_desc(set.into_iter().rev().collect())
+    }
+}
+
+impl<K: Ord, V: Ord> From<BTreeMap<K, V>> for WeakHeap<(K, V)> {
+    /// Converts a `BTreeMap<K, V>` into a `WeakHeap<(K, V)>` of its entries.
+    ///
+    /// Since the keys are distinct, the entries in descending key order are
+    /// in descending order as well, which is a weak heap as it is, so no
+    /// elements are compared.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use weakheap::WeakHeap;
+    ///
+    /// let map = BTreeMap::from([(2, "b"), (3, "c"), (1, "a")]);
+    /// let mut heap = WeakHeap::from(map);
+    /// assert_eq!(heap.pop(), Some((3, "c")));
+    /// assert_eq!(heap.pop(), Some((2, "b")));
+    /// ```
+    fn from(map: BTreeMap<K, V>) -> WeakHeap<(K, V)> {
+        WeakHeap::from_sorted_vec_desc(map.into_iter().rev().collect())
+    }
+}
+
 impl<T> From<WeakHeap<T>> for Vec<T> {
     /// Converts a `WeakHeap<T>` into a `Vec<T>`.
     ///
@@ -1107,6 +3332,29 @@ impl<T> From<WeakHeap<T>> for Vec<T> {
     }
 }
 
+impl<T: Ord> From<WeakHeap<T>> for BinaryHeap<T> {
+    /// Converts a `WeakHeap<T>` into a `BinaryHeap<T>`.
+    ///
+    /// This conversion reuses the vector of the weak heap and rebuilds it
+    /// in-place, and has *O*(*n*) time complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::collections::BinaryHeap;
+    /// use weakheap::WeakHeap;
+    ///
+    /// let heap = WeakHeap::from(vec![5, 3, 2, 4, 1]);
+    /// let binary = BinaryHeap::from(heap);
+    /// assert_eq!(binary.into_sorted_vec(), vec![1, 2, 3, 4, 5]);
+    /// ```
+    fn from(heap: WeakHeap<T>) -> BinaryHeap<T> {
+        BinaryHeap::from(heap.data)
+    }
+}
+
 impl<T: Ord> FromIterator<T> for WeakHeap<T> {
     /// Building WeakHeap from iterator.
     ///
@@ -1125,8 +3373,29 @@ impl<T: Ord> FromIterator<T> for WeakHeap<T> {
     ///     assert_eq!(a, b);
     /// }
     /// ```
-    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> WeakHeap<T> {
-        WeakHeap::from(iter.into_iter().collect::<Vec<_>>())
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> WeakHeap<T> {
+        WeakHeap::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<T: Ord> FromIterator<WeakHeap<T>> for WeakHeap<T> {
+    /// Merges the heaps of the iterator with [`WeakHeap::merge_many`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let heap: WeakHeap<i32> = [vec![3, 1], vec![4, 1, 5]]
+    ///     .into_iter()
+    ///     .map(WeakHeap::from)
+    ///     .collect();
+    /// assert_eq!(heap.into_sorted_vec(), [1, 1, 3, 4, 5]);
+    /// ```
+    fn from_iter<I: IntoIterator<Item = WeakHeap<T>>>(iter: I) -> WeakHeap<T> {
+        WeakHeap::merge_many(iter.into_iter().collect())
     }
 }
 
@@ -1144,10 +3413,14 @@ impl<T: Ord> Extend<T> for WeakHeap<T> {
     /// heap.extend(vec![7, 1, 0, 4, 5, 3]);
     /// assert_eq!(heap.into_sorted_vec(), [0, 1, 3, 4, 5, 7]);
     /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Same as [`WeakHeap::push_batch`]: the elements are appended first, so
+    /// extending with an iterator comparable in size to the heap costs a
+    /// single *O*(*n*) rebuild instead of a sift per element.
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
-        for x in iter {
-            self.push(x);
-        }
+        self.push_batch(iter);
     }
 }
 
@@ -1214,6 +3487,34 @@ impl<'a, T> IntoIterator for &'a WeakHeap<T> {
     }
 }
 
+impl<'a, T: Ord> IntoIterator for &'a mut WeakHeap<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    /// Returns an iterator visiting all values in the underlying vector, in
+    /// arbitrary order, which allows modifying each value. The heap is
+    /// rebuilt when the iterator is dropped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    /// let mut heap = WeakHeap::from(vec![1, 2, 3, 4]);
+    ///
+    /// for x in &mut heap {
+    ///     // x has type &mut i32
+    ///     *x *= -1;
+    /// }
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), vec![-4, -3, -2, -1]);
+    /// ```
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
 /// An iterator over the elements of a `WeakHeap`.
 ///
 /// This `struct` is created by [`WeakHeap::iter()`]. See its
@@ -1222,7 +3523,7 @@ impl<'a, T> IntoIterator for &'a WeakHeap<T> {
 /// [`iter`]: WeakHeap::iter
 #[derive(Clone)]
 pub struct Iter<'a, T: 'a> {
-    iter: std::slice::Iter<'a, T>,
+    iter: core::slice::Iter<'a, T>,
 }
 
 impl<T: fmt::Debug> fmt::Debug for Iter<'_, T> {
@@ -1244,6 +3545,16 @@ impl<'a, T> Iterator for Iter<'a, T> {
         self.iter.size_hint()
     }
 
+    #[inline]
+    fn count(self) -> usize {
+        self.iter.len()
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<&'a T> {
+        self.iter.nth(n)
+    }
+
     #[inline]
     fn last(self) -> Option<&'a T> {
         self.iter.last()
@@ -1255,10 +3566,84 @@ impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
     fn next_back(&mut self) -> Option<&'a T> {
         self.iter.next_back()
     }
+
+    #[inline]
+    fn nth_back(&mut self, n: usize) -> Option<&'a T> {
+        self.iter.nth_back(n)
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
 }
 
 impl<T> FusedIterator for Iter<'_, T> {}
 
+/// A mutable iterator over the elements of a `WeakHeap`, which rebuilds the
+/// heap when dropped.
+///
+/// This `struct` is created by [`WeakHeap::iter_mut()`]. See its
+/// documentation for more.
+///
+/// [`iter_mut`]: WeakHeap::iter_mut
+pub struct IterMut<'a, T: 'a + Ord> {
+    heap: &'a mut WeakHeap<T>,
+    iter: core::slice::IterMut<'a, T>,
+    // The length and the reverse bits of the heap, which are hidden from it
+    // while iterating.
+    len: usize,
+    bit: BitVec,
+}
+
+impl<T: Ord + fmt::Debug> fmt::Debug for IterMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IterMut")
+            .field(&self.iter.as_slice())
+            .finish()
+    }
+}
+
+impl<T: Ord> Drop for IterMut<'_, T> {
+    fn drop(&mut self) {
+        let modified = self.iter.len() < self.len;
+        // SAFETY: the first `len` elements were initialized when they were
+        // hidden, and nothing was written to the buffer since.
+        unsafe { self.heap.data.set_len(self.len) };
+        self.heap.bit = take(&mut self.bit);
+        if modified {
+            self.heap.rebuild();
+        }
+    }
+}
+
+impl<'a, T: Ord> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.iter.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T: Ord> DoubleEndedIterator for IterMut<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        self.iter.next_back()
+    }
+}
+
+impl<T: Ord> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T: Ord> FusedIterator for IterMut<'_, T> {}
+
 /// An owning iterator over the elements of a `WeakHeap`.
 ///
 /// This `struct` is created by [`WeakHeap::into_iter()`]
@@ -1269,7 +3654,7 @@ impl<T> FusedIterator for Iter<'_, T> {}
 
 #[derive(Clone)]
 pub struct IntoIter<T> {
-    iter: std::vec::IntoIter<T>,
+    iter: alloc::vec::IntoIter<T>,
 }
 
 impl<T: fmt::Debug> fmt::Debug for IntoIter<T> {
@@ -1287,6 +3672,26 @@ impl<T> Iterator for IntoIter<T> {
     fn next(&mut self) -> Option<T> {
         self.iter.next()
     }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.iter.len()
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<T> {
+        self.iter.nth(n)
+    }
+
+    #[inline]
+    fn last(self) -> Option<T> {
+        self.iter.last()
+    }
 }
 
 impl<T> DoubleEndedIterator for IntoIter<T> {
@@ -1294,27 +3699,328 @@ impl<T> DoubleEndedIterator for IntoIter<T> {
     fn next_back(&mut self) -> Option<T> {
         self.iter.next_back()
     }
+
+    #[inline]
+    fn nth_back(&mut self, n: usize) -> Option<T> {
+        self.iter.nth_back(n)
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
 }
 
 impl<T> FusedIterator for IntoIter<T> {}
 
-/// A draining iterator over the elements of a `WeakHeap`.
+/// An owning iterator over the elements of a `WeakHeap`, in heap order.
 ///
-/// This `struct` is created by [`WeakHeap::drain()`]. See its
+/// This `struct` is created by [`WeakHeap::into_iter_sorted()`]. See its
 /// documentation for more.
+#[derive(Clone, Debug)]
+pub struct IntoIterSorted<T> {
+    inner: WeakHeap<T>,
+}
+
+impl<T: Ord> Iterator for IntoIterSorted<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.inner.pop()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let exact = self.inner.len();
+        (exact, Some(exact))
+    }
+}
+
+impl<T: Ord> ExactSizeIterator for IntoIterSorted<T> {}
+
+impl<T: Ord> FusedIterator for IntoIterSorted<T> {}
+
+/// An iterator over references to the elements of a `WeakHeap`, in heap
+/// order.
 ///
-/// [`drain`]: WeakHeap::drain
+/// This `struct` is created by [`WeakHeap::sorted_iter()`]. See its
+/// documentation for more.
+pub struct SortedIter<'a, T: 'a> {
+    heap: &'a WeakHeap<T>,
+    // The items which may be the next greatest: the left spines of the right
+    // subtrees of the items returned so far.
+    candidates: WeakHeap<Candidate<'a, T>>,
+    remaining: usize,
+}
+
+impl<T> Clone for SortedIter<'_, T> {
+    fn clone(&self) -> Self {
+        SortedIter {
+            heap: self.heap,
+            candidates: self.candidates.clone(),
+            remaining: self.remaining,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SortedIter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SortedIter")
+            .field("remaining", &self.remaining)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, T: Ord> Iterator for SortedIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let Candidate { item, pos } = self.candidates.pop()?;
+        self.remaining -= 1;
+
+        let heap = self.heap;
+        // The right child of `pos` (the root has only a right child).
+        // SAFETY: pos < heap.len() == heap.bit.len().
+        let mut cur = if pos == 0 {
+            1
+        } else {
+            2 * pos + 1 - unsafe { heap.bit.get_unchecked(pos) } as usize
+        };
+        while cur < heap.len() {
+            self.candidates.push(Candidate {
+                item: &heap.data[cur],
+                pos: cur,
+            });
+            // SAFETY: cur < heap.len() == heap.bit.len().
+            cur = 2 * cur + unsafe { heap.bit.get_unchecked(cur) } as usize;
+        }
+        Some(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: Ord> ExactSizeIterator for SortedIter<'_, T> {}
+
+impl<T: Ord> FusedIterator for SortedIter<'_, T> {}
+
+/// An iterator which removes the elements of a `WeakHeap` matching a predicate.
+///
+/// This `struct` is created by [`WeakHeap::extract_if()`]. See its
+/// documentation for more.
+pub struct ExtractIf<'a, T: Ord, F: FnMut(&T) -> bool> {
+    inner: &'a mut WeakHeap<T>,
+    pred: F,
+    // Next position to visit.
+    pos: usize,
+    // The heap is unchanged before this position.
+    first_removed: usize,
+}
+
+impl<T: Ord + fmt::Debug, F: FnMut(&T) -> bool> fmt::Debug for ExtractIf<'_, T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtractIf")
+            .field("inner", &self.inner)
+            .field("pos", &self.pos)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Ord, F: FnMut(&T) -> bool> Iterator for ExtractIf<'_, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while self.pos < self.inner.len() {
+            if (self.pred)(&self.inner.data[self.pos]) {
+                self.first_removed = self.first_removed.min(self.pos);
+                // The last element takes the place of the removed one and is
+                // visited next. It is sifted up later, whatever its reverse
+                // bit, so the bit at `pos` stays, which keeps the root's clear.
+                self.inner.bit.pop();
+                return Some(self.inner.data.swap_remove(self.pos));
+            }
+            self.pos += 1;
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.inner.len() - self.pos))
+    }
+}
+
+impl<T: Ord, F: FnMut(&T) -> bool> Drop for ExtractIf<'_, T, F> {
+    /// Restores the heap property.
+    fn drop(&mut self) {
+        if self.first_removed < self.inner.len() {
+            self.inner.rebuild_from(self.first_removed);
+        }
+    }
+}
+
+/// An iterator which pops the greatest elements of a `WeakHeap` as long as
+/// they match a predicate.
+///
+/// This `struct` is created by [`WeakHeap::drain_while()`]. See its
+/// documentation for more.
+pub struct DrainWhile<'a, T: Ord, F: FnMut(&T) -> bool> {
+    inner: &'a mut WeakHeap<T>,
+    pred: F,
+    // Set once the greatest element did not match.
+    done: bool,
+}
+
+impl<T: Ord + fmt::Debug, F: FnMut(&T) -> bool> fmt::Debug for DrainWhile<'_, T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DrainWhile")
+            .field("inner", &self.inner)
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Ord, F: FnMut(&T) -> bool> Iterator for DrainWhile<'_, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.done {
+            return None;
+        }
+        match self.inner.peek() {
+            Some(top) if (self.pred)(top) => self.inner.pop(),
+            _ => {
+                self.done = true;
+                None
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            (0, Some(0))
+        } else {
+            (0, Some(self.inner.len()))
+        }
+    }
+}
+
+impl<T: Ord, F: FnMut(&T) -> bool> FusedIterator for DrainWhile<'_, T, F> {}
+
+// An element of a heap, ordered by the element only, together with its position.
+struct Candidate<'a, T> {
+    item: &'a T,
+    pos: usize,
+}
+
+impl<T> Clone for Candidate<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Candidate<'_, T> {}
+
+impl<T: Ord> PartialEq for Candidate<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.item == other.item
+    }
+}
+
+impl<T: Ord> Eq for Candidate<'_, T> {}
+
+impl<T: Ord> PartialOrd for Candidate<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for Candidate<'_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.item.cmp(other.item)
+    }
+}
+
+// Restores the heap property of the elements placed at or after `start`, even
+// if placing them panics.
+struct RebuildOnDrop<'a, T: Ord> {
+    heap: &'a mut WeakHeap<T>,
+    start: usize,
+}
+
+impl<T: Ord> Drop for RebuildOnDrop<'_, T> {
+    fn drop(&mut self) {
+        self.heap.match_bit_capacity();
+        self.heap.bit.resize(self.heap.data.len(), false);
+        self.heap.rebuild_from(self.start);
+    }
+}
+
+// Takes the guard out of the result of locking a mutex of this crate, even if
+// the mutex is poisoned. A panic while one is held, be it from `Ord`, `Drop`
+// or a failed allocation, unwinds through operations which leave the data
+// memory safe and every remaining element in place. At worst a panicking
+// `Ord` leaves the heap order unspecified and a panicking `Drop` leaks
+// elements, exactly as for a heap used without a lock, so there is nothing a
+// caller could do about the poisoning.
+#[cfg(feature = "std")]
+fn ignore_poison<G>(result: std::sync::LockResult<G>) -> G {
+    result.unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// A draining iterator over the elements of a `WeakHeap`, in heap order.
+///
+/// This `struct` is created by [`WeakHeap::drain_sorted()`]. See its
+/// documentation for more.
 #[derive(Debug)]
-pub struct Drain<'a, T: 'a> {
-    iter: std::vec::Drain<'a, T>,
+pub struct DrainSorted<'a, T: Ord> {
+    inner: &'a mut WeakHeap<T>,
+}
+
+impl<T: Ord> Drop for DrainSorted<'_, T> {
+    /// Drops the remaining elements and leaves the heap empty.
+    fn drop(&mut self) {
+        self.inner.clear();
+    }
+}
+
+impl<T: Ord> Iterator for DrainSorted<'_, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.inner.pop()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let exact = self.inner.len();
+        (exact, Some(exact))
+    }
 }
 
+impl<T: Ord> ExactSizeIterator for DrainSorted<'_, T> {}
+
+impl<T: Ord> FusedIterator for DrainSorted<'_, T> {}
+
 /// A draining iterator over the elements of a `WeakHeap`.
 ///
 /// This `struct` is created by [`WeakHeap::drain()`]. See its
 /// documentation for more.
 ///
 /// [`drain`]: WeakHeap::drain
+#[derive(Debug)]
+pub struct Drain<'a, T: 'a> {
+    iter: alloc::vec::Drain<'a, T>,
+}
+
 impl<T> Iterator for Drain<'_, T> {
     type Item = T;
 
@@ -1327,6 +4033,21 @@ impl<T> Iterator for Drain<'_, T> {
     fn size_hint(&self) -> (usize, Option<usize>) {
         self.iter.size_hint()
     }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.iter.len()
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<T> {
+        self.iter.nth(n)
+    }
+
+    #[inline]
+    fn last(self) -> Option<T> {
+        self.iter.last()
+    }
 }
 
 impl<T> DoubleEndedIterator for Drain<'_, T> {
@@ -1336,6 +4057,13 @@ impl<T> DoubleEndedIterator for Drain<'_, T> {
     }
 }
 
+impl<T> ExactSizeIterator for Drain<'_, T> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
 impl<T> FusedIterator for Drain<'_, T> {}
 
 #[cfg(test)]
@@ -0,0 +1,40 @@
+//! Construction macros.
+
+/// Creates a [`WeakHeap`] containing the arguments.
+///
+/// Like `vec!`, `weak_heap!` takes a comma-separated list of elements. The
+/// heap is built from them with a single *O*(*n*) rebuild and has exactly
+/// the needed capacity. With a leading `min:`, the elements are wrapped in
+/// [`Reverse`], which makes a min-heap.
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Reverse;
+/// use weakheap::{weak_heap, WeakHeap};
+///
+/// let mut heap = weak_heap![3, 1, 4, 1, 5];
+/// assert_eq!(heap.len(), 5);
+/// assert_eq!(heap.pop(), Some(5));
+///
+/// let mut heap = weak_heap![min: 3, 1, 4];
+/// assert_eq!(heap.pop(), Some(Reverse(1)));
+///
+/// let heap: WeakHeap<i32> = weak_heap![];
+/// assert!(heap.is_empty());
+/// ```
+///
+/// [`WeakHeap`]: crate::WeakHeap
+/// [`Reverse`]: core::cmp::Reverse
+#[macro_export]
+macro_rules! weak_heap {
+    () => {
+        $crate::WeakHeap::new()
+    };
+    (min: $($x:expr),* $(,)?) => {
+        $crate::WeakHeap::from([$(::core::cmp::Reverse($x)),*])
+    };
+    ($($x:expr),+ $(,)?) => {
+        $crate::WeakHeap::from([$($x),+])
+    };
+}
@@ -0,0 +1,328 @@
+//! A priority queue with one entry per key.
+
+use crate::raw;
+use crate::tracked::{Slab, Tracked};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+use core::iter::FromIterator;
+use core::mem;
+use std::collections::HashMap;
+
+/// A priority map implemented with a weak heap: every key appears at most
+/// once, and the entries are ordered by their priority.
+///
+/// A hash index maps every key to the position of its entry, so the priority
+/// of a key can be looked up in *O*(1) and changed or removed in
+/// *O*(log(*n*)). Only the priorities are compared.
+///
+/// This will be a max-heap with respect to the priorities.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::WeakHeapMap;
+///
+/// let mut map = WeakHeapMap::new();
+/// map.push("a", 3);
+/// map.push("b", 7);
+/// map.push("c", 5);
+///
+/// // Pushing an existing key changes its priority.
+/// assert_eq!(map.push("a", 9), Some(3));
+/// assert_eq!(map.get_priority("c"), Some(&5));
+///
+/// assert_eq!(map.pop(), Some(("a", 9)));
+/// assert_eq!(map.remove("b"), Some(7));
+/// assert_eq!(map.pop(), Some(("c", 5)));
+/// assert!(map.is_empty());
+/// ```
+pub struct WeakHeapMap<K, P> {
+    entries: Slab<(K, P)>,
+    heap: Tracked,
+    index: HashMap<K, usize>,
+}
+
+impl<K: Clone, P: Clone> Clone for WeakHeapMap<K, P> {
+    fn clone(&self) -> Self {
+        WeakHeapMap {
+            entries: self.entries.clone(),
+            heap: self.heap.clone(),
+            index: self.index.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, P: fmt::Debug> fmt::Debug for WeakHeapMap<K, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Clone + Hash + Eq, P: Ord> Default for WeakHeapMap<K, P> {
+    /// Creates an empty `WeakHeapMap`.
+    #[inline]
+    fn default() -> WeakHeapMap<K, P> {
+        WeakHeapMap::new()
+    }
+}
+
+// Compares the priorities of the entries of two handles.
+#[inline]
+fn less<K, P: Ord>(entries: &Slab<(K, P)>) -> impl FnMut(usize, usize) -> bool + '_ {
+    move |a, b| raw::lt(&entries.at(a).1, &entries.at(b).1)
+}
+
+impl<K: Clone + Hash + Eq, P: Ord> WeakHeapMap<K, P> {
+    /// Creates an empty `WeakHeapMap`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapMap;
+    /// let mut map = WeakHeapMap::new();
+    /// map.push("a", 4);
+    /// ```
+    #[must_use]
+    pub fn new() -> WeakHeapMap<K, P> {
+        WeakHeapMap {
+            entries: Slab::default(),
+            heap: Tracked::default(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Creates an empty `WeakHeapMap` with a specific capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapMap;
+    /// let mut map = WeakHeapMap::with_capacity(10);
+    /// map.push("a", 4);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> WeakHeapMap<K, P> {
+        WeakHeapMap {
+            entries: Slab::with_capacity(capacity),
+            heap: Tracked::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// Inserts `key` with `priority`. If the key is already present, its
+    /// priority is replaced and the old priority is returned.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapMap;
+    /// let mut map = WeakHeapMap::new();
+    ///
+    /// assert_eq!(map.push("a", 3), None);
+    /// assert_eq!(map.push("a", 1), Some(3));
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost of inserting a new key is *O*(1), the worst case
+    /// and the cost of changing a priority is *O*(log(*n*)).
+    pub fn push(&mut self, key: K, priority: P) -> Option<P> {
+        if let Some(&handle) = self.index.get(&key) {
+            let entry = self.entries.get_mut(handle).expect("vacant slot");
+            let old = mem::replace(&mut entry.1, priority);
+            self.heap.update(handle, &mut less(&self.entries));
+            return Some(old);
+        }
+        let handle = self.entries.insert((key.clone(), priority));
+        self.index.insert(key, handle);
+        self.heap.push(handle, &mut less(&self.entries));
+        None
+    }
+
+    /// Removes the entry with the greatest priority and returns it, or `None`
+    /// if the map is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapMap;
+    /// let mut map: WeakHeapMap<_, _> = vec![("a", 1), ("b", 3)].into_iter().collect();
+    ///
+    /// assert_eq!(map.pop(), Some(("b", 3)));
+    /// assert_eq!(map.pop(), Some(("a", 1)));
+    /// assert_eq!(map.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<(K, P)> {
+        let handle = self.heap.top()?;
+        Some(self.remove_handle(handle))
+    }
+
+    /// Removes `key` from the map and returns its priority, or `None` if the
+    /// key is not present.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapMap;
+    /// let mut map: WeakHeapMap<_, _> = vec![("a", 1), ("b", 3)].into_iter().collect();
+    ///
+    /// assert_eq!(map.remove("b"), Some(3));
+    /// assert_eq!(map.remove("b"), None);
+    /// assert_eq!(map.peek(), Some((&"a", &1)));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<P>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, priority)| priority)
+    }
+
+    /// Removes `key` from the map and returns the stored key together with
+    /// its priority, or `None` if the key is not present.
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, P)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let handle = *self.index.get(key)?;
+        Some(self.remove_handle(handle))
+    }
+
+    /// Consumes the map and returns its entries sorted in ascending order of
+    /// the priorities.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapMap;
+    /// let map: WeakHeapMap<_, _> = vec![("a", 2), ("b", 1), ("c", 3)].into_iter().collect();
+    ///
+    /// assert_eq!(map.into_sorted_vec(), [("b", 1), ("a", 2), ("c", 3)]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<(K, P)> {
+        let mut vec = Vec::with_capacity(self.len());
+        while let Some(entry) = self.pop() {
+            vec.push(entry);
+        }
+        vec.reverse();
+        vec
+    }
+
+    fn remove_handle(&mut self, handle: usize) -> (K, P) {
+        self.heap.remove(handle, &mut less(&self.entries));
+        let (key, priority) = self.entries.remove(handle);
+        self.index.remove(&key);
+        (key, priority)
+    }
+}
+
+impl<K: Hash + Eq, P> WeakHeapMap<K, P> {
+    /// Returns the priority of `key`, or `None` if the key is not present.
+    ///
+    /// # Time complexity
+    ///
+    /// Expected cost is *O*(1).
+    #[must_use]
+    pub fn get_priority<Q>(&self, key: &Q) -> Option<&P>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let &handle = self.index.get(key)?;
+        Some(&self.entries.at(handle).1)
+    }
+
+    /// Checks if `key` is present in the map.
+    #[must_use]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.contains_key(key)
+    }
+}
+
+impl<K, P> WeakHeapMap<K, P> {
+    /// Returns the entry with the greatest priority, or `None` if the map is
+    /// empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<(&K, &P)> {
+        self.heap.top().map(|handle| {
+            let (key, priority) = self.entries.at(handle);
+            (key, priority)
+        })
+    }
+
+    /// Returns an iterator visiting all keys and priorities in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &P)> + '_ {
+        self.heap.handles().iter().map(move |&handle| {
+            let (key, priority) = self.entries.at(handle);
+            (key, priority)
+        })
+    }
+
+    /// Returns the number of entries in the map.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the map is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all entries from the map.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.heap.clear();
+        self.index.clear();
+    }
+}
+
+impl<K: Clone + Hash + Eq, P: Ord> FromIterator<(K, P)> for WeakHeapMap<K, P> {
+    fn from_iter<I: IntoIterator<Item = (K, P)>>(iter: I) -> WeakHeapMap<K, P> {
+        let mut map = WeakHeapMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Clone + Hash + Eq, P: Ord> Extend<(K, P)> for WeakHeapMap<K, P> {
+    fn extend<I: IntoIterator<Item = (K, P)>>(&mut self, iter: I) {
+        for (key, priority) in iter {
+            self.push(key, priority);
+        }
+    }
+}
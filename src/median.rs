@@ -0,0 +1,201 @@
+//! A running median of a stream of elements.
+
+use crate::WeakHeap;
+use core::cmp::Reverse;
+use core::fmt;
+use core::iter::FromIterator;
+
+/// A collection keeping track of the median of its elements, implemented
+/// with a pair of weak heaps.
+///
+/// The lower half of the elements is kept in a max-heap and the upper half
+/// in a min-heap, so the median is always at the top of the lower half.
+/// Pushing an element costs a comparison with the top of one half plus the
+/// sifting in both halves, which makes the weak heap's low comparison count
+/// pay off for expensive-to-compare elements.
+///
+/// With an even number of elements, the lower of the two middle elements is
+/// the median.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::MedianHeap;
+///
+/// let mut heap = MedianHeap::new();
+/// heap.push(5);
+/// heap.push(1);
+/// heap.push(9);
+/// assert_eq!(heap.median(), Some(&5));
+///
+/// heap.push(7);
+/// assert_eq!(heap.median(), Some(&5));
+///
+/// assert_eq!(heap.pop_median(), Some(5));
+/// assert_eq!(heap.median(), Some(&7));
+/// ```
+pub struct MedianHeap<T> {
+    // Holds as many elements as `upper`, or one more.
+    lower: WeakHeap<T>,
+    upper: WeakHeap<Reverse<T>>,
+}
+
+impl<T: Clone> Clone for MedianHeap<T> {
+    fn clone(&self) -> Self {
+        MedianHeap {
+            lower: self.lower.clone(),
+            upper: self.upper.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MedianHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MedianHeap")
+            .field("lower", &self.lower)
+            .field("upper", &self.upper)
+            .finish()
+    }
+}
+
+impl<T: Ord> Default for MedianHeap<T> {
+    /// Creates an empty `MedianHeap`.
+    #[inline]
+    fn default() -> MedianHeap<T> {
+        MedianHeap::new()
+    }
+}
+
+impl<T: Ord> MedianHeap<T> {
+    /// Creates an empty `MedianHeap`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MedianHeap;
+    /// let mut heap = MedianHeap::new();
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn new() -> MedianHeap<T> {
+        MedianHeap {
+            lower: WeakHeap::new(),
+            upper: WeakHeap::new(),
+        }
+    }
+
+    /// Pushes an item into the collection.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MedianHeap;
+    /// let mut heap = MedianHeap::new();
+    /// heap.push(3);
+    /// heap.push(5);
+    /// heap.push(1);
+    ///
+    /// assert_eq!(heap.len(), 3);
+    /// assert_eq!(heap.median(), Some(&3));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn push(&mut self, item: T) {
+        if self.lower.len() == self.upper.len() {
+            // The lower half grows by the smaller of `item` and the upper half.
+            let Reverse(item) = self.upper.pushpop(Reverse(item));
+            self.lower.push(item);
+        } else {
+            // The upper half grows by the greater of `item` and the lower half.
+            let item = self.lower.pushpop(item);
+            self.upper.push(Reverse(item));
+        }
+    }
+
+    /// Removes the median and returns it, or `None` if the collection is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MedianHeap;
+    /// let mut heap: MedianHeap<_> = [1, 2, 3, 4].into_iter().collect();
+    ///
+    /// assert_eq!(heap.pop_median(), Some(2));
+    /// assert_eq!(heap.pop_median(), Some(3));
+    /// assert_eq!(heap.len(), 2);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn pop_median(&mut self) -> Option<T> {
+        let median = self.lower.pop()?;
+        if self.upper.len() > self.lower.len() {
+            if let Some(Reverse(item)) = self.upper.pop() {
+                self.lower.push(item);
+            }
+        }
+        Some(median)
+    }
+}
+
+impl<T> MedianHeap<T> {
+    /// Returns the median, or `None` if the collection is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn median(&self) -> Option<&T> {
+        self.lower.peek()
+    }
+
+    /// Returns an iterator visiting all values in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.lower
+            .iter()
+            .chain(self.upper.iter().map(|Reverse(item)| item))
+    }
+
+    /// Returns the number of elements in the collection.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lower.len() + self.upper.len()
+    }
+
+    /// Checks if the collection is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lower.is_empty()
+    }
+
+    /// Drops all items from the collection.
+    pub fn clear(&mut self) {
+        self.lower.clear();
+        self.upper.clear();
+    }
+}
+
+impl<T: Ord> FromIterator<T> for MedianHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> MedianHeap<T> {
+        let mut heap = MedianHeap::new();
+        heap.extend(iter);
+        heap
+    }
+}
+
+impl<T: Ord> Extend<T> for MedianHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Memory introspection.
+
+/// A breakdown of the memory held by a [`WeakHeap`](crate::WeakHeap),
+/// returned by [`WeakHeap::memory_usage`](crate::WeakHeap::memory_usage).
+///
+/// The byte counts cover the heap buffers only: the memory owned by the
+/// elements themselves (e.g. the contents of a `String`) is not included.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::WeakHeap;
+///
+/// let heap: WeakHeap<u64> = WeakHeap::with_capacity(100);
+/// let usage = heap.memory_usage();
+///
+/// assert_eq!(usage.len, 0);
+/// assert!(usage.capacity >= 100);
+/// assert_eq!(usage.data_bytes, usage.capacity * 8);
+/// assert!(usage.bit_capacity >= usage.capacity);
+/// assert_eq!(usage.total_bytes(), heap.allocated_bytes());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct MemoryUsage {
+    /// Number of elements in the heap.
+    pub len: usize,
+    /// Number of elements the data vector can hold without reallocating.
+    pub capacity: usize,
+    /// Bytes allocated for the data vector, that is `capacity` times the
+    /// size of an element.
+    pub data_bytes: usize,
+    /// Number of reverse bits the bit storage can hold without reallocating.
+    pub bit_capacity: usize,
+    /// Bytes allocated for the bit storage. The bits are packed into 64-bit
+    /// words, so this is about one eighth of a byte per element.
+    pub bit_bytes: usize,
+}
+
+impl MemoryUsage {
+    /// Returns the bytes allocated for the data vector and the bit storage
+    /// together.
+    #[must_use]
+    pub const fn total_bytes(&self) -> usize {
+        self.data_bytes + self.bit_bytes
+    }
+
+    /// Returns the bytes allocated per element in the heap, including the
+    /// unused capacity, or `None` if the heap is empty.
+    #[must_use]
+    pub fn bytes_per_element(&self) -> Option<f64> {
+        (self.len != 0).then(|| self.total_bytes() as f64 / self.len as f64)
+    }
+}
@@ -0,0 +1,238 @@
+//! A min-heap without `Reverse` wrappers.
+
+use crate::WeakHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::fmt;
+use core::iter::FromIterator;
+
+/// A priority queue implemented with a weak heap, which pops the smallest
+/// element first.
+///
+/// This is a `WeakHeap<Reverse<T>>` which takes and returns plain `T`
+/// values, so the `Reverse` wrappers do not leak into the calling code.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::MinWeakHeap;
+///
+/// let mut heap = MinWeakHeap::new();
+/// heap.push(5);
+/// heap.push(1);
+/// heap.push(3);
+///
+/// assert_eq!(heap.peek(), Some(&1));
+/// assert_eq!(heap.pop(), Some(1));
+/// assert_eq!(heap.pop(), Some(3));
+/// assert_eq!(heap.pop(), Some(5));
+/// assert_eq!(heap.pop(), None);
+/// ```
+pub struct MinWeakHeap<T> {
+    heap: WeakHeap<Reverse<T>>,
+}
+
+impl<T: Clone> Clone for MinWeakHeap<T> {
+    fn clone(&self) -> Self {
+        MinWeakHeap {
+            heap: self.heap.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MinWeakHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> Default for MinWeakHeap<T> {
+    /// Creates an empty `MinWeakHeap`.
+    #[inline]
+    fn default() -> MinWeakHeap<T> {
+        MinWeakHeap::new()
+    }
+}
+
+impl<T: Ord> MinWeakHeap<T> {
+    /// Creates an empty `MinWeakHeap`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MinWeakHeap;
+    /// let mut heap = MinWeakHeap::new();
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn new() -> MinWeakHeap<T> {
+        MinWeakHeap {
+            heap: WeakHeap::new(),
+        }
+    }
+
+    /// Creates an empty `MinWeakHeap` with a specific capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MinWeakHeap;
+    /// let mut heap = MinWeakHeap::with_capacity(10);
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> MinWeakHeap<T> {
+        MinWeakHeap {
+            heap: WeakHeap::with_capacity(capacity),
+        }
+    }
+
+    /// Pushes an item onto the heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MinWeakHeap;
+    /// let mut heap = MinWeakHeap::new();
+    /// heap.push(3);
+    /// heap.push(5);
+    /// heap.push(1);
+    ///
+    /// assert_eq!(heap.len(), 3);
+    /// assert_eq!(heap.peek(), Some(&1));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost of `push` is *O*(1), the worst case is *O*(log(*n*)).
+    pub fn push(&mut self, item: T) {
+        self.heap.push(Reverse(item));
+    }
+
+    /// Removes the smallest item from the heap and returns it, or `None` if it
+    /// is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MinWeakHeap;
+    /// let mut heap = MinWeakHeap::from(vec![1, 3]);
+    ///
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.pop(), Some(3));
+    /// assert_eq!(heap.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost of `pop` on a heap containing *n* elements is *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        self.heap.pop().map(|Reverse(item)| item)
+    }
+
+    /// Consumes the heap and returns a vector in sorted (ascending) order,
+    /// which is the order of popping.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MinWeakHeap;
+    /// let heap = MinWeakHeap::from(vec![4, 1, 3, 2]);
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let mut vec: Vec<T> = self
+            .heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(item)| item)
+            .collect();
+        vec.reverse();
+        vec
+    }
+}
+
+impl<T> MinWeakHeap<T> {
+    /// Returns the smallest item in the heap, or `None` if it is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.peek().map(|Reverse(item)| item)
+    }
+
+    /// Returns an iterator visiting all values in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.heap.iter().map(|Reverse(item)| item)
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns the number of elements the heap can hold without reallocating.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.heap.capacity()
+    }
+
+    /// Drops all items from the heap.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+
+    /// Consumes the heap and returns its elements in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        self.heap
+            .into_vec()
+            .into_iter()
+            .map(|Reverse(item)| item)
+            .collect()
+    }
+}
+
+impl<T: Ord> From<Vec<T>> for MinWeakHeap<T> {
+    /// Converts a `Vec<T>` into a `MinWeakHeap<T>`.
+    ///
+    /// This conversion has *O*(*n*) time complexity.
+    fn from(vec: Vec<T>) -> MinWeakHeap<T> {
+        vec.into_iter().collect()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for MinWeakHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> MinWeakHeap<T> {
+        MinWeakHeap {
+            heap: iter.into_iter().map(Reverse).collect(),
+        }
+    }
+}
+
+impl<T: Ord> Extend<T> for MinWeakHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.heap.extend(iter.into_iter().map(Reverse));
+    }
+}
@@ -0,0 +1,287 @@
+//! A double-ended priority queue.
+
+use crate::raw;
+use crate::tracked::{Slab, Tracked};
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FromIterator;
+
+/// A double-ended priority queue implemented with a pair of weak heaps.
+///
+/// Every element is stored once and indexed by a max weak heap and a min
+/// weak heap, which track each other's positions. Both the greatest and the
+/// smallest element can be inspected in *O*(1) and removed in *O*(log(*n*)).
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::MinMaxWeakHeap;
+///
+/// let mut heap = MinMaxWeakHeap::new();
+/// heap.push(3);
+/// heap.push(1);
+/// heap.push(4);
+/// heap.push(1);
+/// heap.push(5);
+///
+/// assert_eq!(heap.peek_max(), Some(&5));
+/// assert_eq!(heap.peek_min(), Some(&1));
+///
+/// assert_eq!(heap.pop_max(), Some(5));
+/// assert_eq!(heap.pop_min(), Some(1));
+/// assert_eq!(heap.pop_min(), Some(1));
+/// assert_eq!(heap.len(), 2);
+/// ```
+pub struct MinMaxWeakHeap<T> {
+    items: Slab<T>,
+    max: Tracked,
+    min: Tracked,
+}
+
+impl<T: Clone> Clone for MinMaxWeakHeap<T> {
+    fn clone(&self) -> Self {
+        MinMaxWeakHeap {
+            items: self.items.clone(),
+            max: self.max.clone(),
+            min: self.min.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MinMaxWeakHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> Default for MinMaxWeakHeap<T> {
+    /// Creates an empty `MinMaxWeakHeap`.
+    #[inline]
+    fn default() -> MinMaxWeakHeap<T> {
+        MinMaxWeakHeap::new()
+    }
+}
+
+impl<T: Ord> MinMaxWeakHeap<T> {
+    /// Creates an empty `MinMaxWeakHeap`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MinMaxWeakHeap;
+    /// let mut heap = MinMaxWeakHeap::new();
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn new() -> MinMaxWeakHeap<T> {
+        MinMaxWeakHeap {
+            items: Slab::default(),
+            max: Tracked::default(),
+            min: Tracked::default(),
+        }
+    }
+
+    /// Creates an empty `MinMaxWeakHeap` with a specific capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MinMaxWeakHeap;
+    /// let mut heap = MinMaxWeakHeap::with_capacity(10);
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> MinMaxWeakHeap<T> {
+        MinMaxWeakHeap {
+            items: Slab::with_capacity(capacity),
+            max: Tracked::with_capacity(capacity),
+            min: Tracked::with_capacity(capacity),
+        }
+    }
+
+    /// Pushes an item onto the heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MinMaxWeakHeap;
+    /// let mut heap = MinMaxWeakHeap::new();
+    /// heap.push(3);
+    /// heap.push(5);
+    /// heap.push(1);
+    ///
+    /// assert_eq!(heap.peek_max(), Some(&5));
+    /// assert_eq!(heap.peek_min(), Some(&1));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost of `push` is *O*(1), the worst case is *O*(log(*n*)).
+    pub fn push(&mut self, item: T) {
+        let handle = self.items.insert(item);
+        let items = &self.items;
+        self.max
+            .push(handle, &mut |a, b| raw::lt(items.at(a), items.at(b)));
+        self.min
+            .push(handle, &mut |a, b| raw::lt(items.at(b), items.at(a)));
+    }
+
+    /// Removes the greatest item from the heap and returns it, or `None` if it
+    /// is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MinMaxWeakHeap;
+    /// let mut heap: MinMaxWeakHeap<_> = vec![1, 3].into_iter().collect();
+    ///
+    /// assert_eq!(heap.pop_max(), Some(3));
+    /// assert_eq!(heap.pop_max(), Some(1));
+    /// assert_eq!(heap.pop_max(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn pop_max(&mut self) -> Option<T> {
+        let handle = self.max.top()?;
+        Some(self.remove(handle))
+    }
+
+    /// Removes the smallest item from the heap and returns it, or `None` if it
+    /// is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MinMaxWeakHeap;
+    /// let mut heap: MinMaxWeakHeap<_> = vec![1, 3].into_iter().collect();
+    ///
+    /// assert_eq!(heap.pop_min(), Some(1));
+    /// assert_eq!(heap.pop_min(), Some(3));
+    /// assert_eq!(heap.pop_min(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn pop_min(&mut self) -> Option<T> {
+        let handle = self.min.top()?;
+        Some(self.remove(handle))
+    }
+
+    /// Consumes the heap and returns a vector in sorted (ascending) order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::MinMaxWeakHeap;
+    /// let heap: MinMaxWeakHeap<_> = vec![4, 1, 3, 2].into_iter().collect();
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut vec = Vec::with_capacity(self.len());
+        while let Some(item) = self.pop_min() {
+            vec.push(item);
+        }
+        vec
+    }
+
+    fn remove(&mut self, handle: usize) -> T {
+        let items = &self.items;
+        self.max
+            .remove(handle, &mut |a, b| raw::lt(items.at(a), items.at(b)));
+        self.min
+            .remove(handle, &mut |a, b| raw::lt(items.at(b), items.at(a)));
+        self.items.remove(handle)
+    }
+}
+
+impl<T> MinMaxWeakHeap<T> {
+    /// Returns the greatest item in the heap, or `None` if it is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek_max(&self) -> Option<&T> {
+        self.max.top().map(|handle| self.items.at(handle))
+    }
+
+    /// Returns the smallest item in the heap, or `None` if it is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek_min(&self) -> Option<&T> {
+        self.min.top().map(|handle| self.items.at(handle))
+    }
+
+    /// Returns an iterator visiting all values in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.max
+            .handles()
+            .iter()
+            .map(move |&handle| self.items.at(handle))
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.max.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all items from the heap.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.max.clear();
+        self.min.clear();
+    }
+
+    /// Consumes the heap and returns its elements in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(mut self) -> Vec<T> {
+        let handles = core::mem::take(&mut self.max).handles().to_vec();
+        handles
+            .into_iter()
+            .map(|handle| self.items.remove(handle))
+            .collect()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for MinMaxWeakHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> MinMaxWeakHeap<T> {
+        let mut heap = MinMaxWeakHeap::new();
+        heap.extend(iter);
+        heap
+    }
+}
+
+impl<T: Ord> Extend<T> for MinMaxWeakHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
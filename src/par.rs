@@ -0,0 +1,59 @@
+//! Parallel sorting of a heap, with `rayon`.
+
+use crate::{kmerge, sort, WeakHeap};
+use alloc::vec::Vec;
+use rayon::prelude::*;
+
+/// Heaps with fewer elements are sorted sequentially.
+pub(crate) const PAR_THRESHOLD: usize = 1 << 15;
+
+impl<T: Ord + Send> WeakHeap<T> {
+    /// Consumes the heap and returns a vector in sorted (ascending) order,
+    /// sorting it on the `rayon` thread pool.
+    ///
+    /// The elements are split into one chunk per thread, the chunks are
+    /// weak-heapsorted in parallel, and the sorted chunks are merged with
+    /// [`kmerge`], which spends about log2(*k*) comparisons per element for
+    /// *k* chunks. Heaps of fewer than 32768 elements, or a pool with a single
+    /// thread, fall back to [`WeakHeap::into_sorted_vec`].
+    ///
+    /// Available with the `rayon` feature.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeap;
+    ///
+    /// let heap: WeakHeap<u32> = (0..100_000).map(|x| x * 7919 % 100_000).collect();
+    /// let sorted = heap.par_into_sorted_vec();
+    /// assert!(sorted.iter().copied().eq(0..100_000));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// *O*(*n* * log(*n*) / *k* + *n* * log(*k*)) with *k* threads.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn par_into_sorted_vec(self) -> Vec<T> {
+        let threads = rayon::current_num_threads();
+        if self.len() < PAR_THRESHOLD || threads < 2 {
+            return self.into_sorted_vec();
+        }
+
+        let mut data = self.into_vec();
+        let chunk = data.len().div_ceil(threads);
+        let mut chunks = Vec::with_capacity(threads);
+        while !data.is_empty() {
+            let start = (data.len() - 1) / chunk * chunk;
+            chunks.push(data.split_off(start));
+        }
+        chunks
+            .par_iter_mut()
+            .for_each(|chunk| sort::weak_heapsort(chunk));
+
+        let mut sorted = data;
+        sorted.extend(kmerge(chunks));
+        sorted
+    }
+}
@@ -0,0 +1,201 @@
+//! Bulk operations reporting their progress.
+
+use crate::bits::BitVec;
+use crate::{raw, WeakHeap};
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+
+/// The progress of a long bulk operation, passed to the callbacks of
+/// [`WeakHeap::from_vec_with_progress`], [`WeakHeap::into_sorted_vec_with_progress`]
+/// and [`WeakHeap::append_vec_with_progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of elements processed so far.
+    pub done: usize,
+    /// Total number of elements to process.
+    pub total: usize,
+}
+
+impl<T: Ord> WeakHeap<T> {
+    /// Converts a `Vec<T>` into a `WeakHeap<T>` like `WeakHeap::from`, invoking
+    /// `callback` every `every` processed elements.
+    ///
+    /// If the callback returns [`ControlFlow::Break`], the operation is aborted
+    /// and the vector is returned with its elements in arbitrary order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::ops::ControlFlow;
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut calls = 0;
+    /// let heap = WeakHeap::from_vec_with_progress((0..100).collect(), 10, |p| {
+    ///     assert_eq!(p.total, 100);
+    ///     calls += 1;
+    ///     ControlFlow::Continue(())
+    /// });
+    /// assert_eq!(heap.unwrap().peek(), Some(&99));
+    /// assert!(calls >= 9);
+    ///
+    /// let aborted = WeakHeap::from_vec_with_progress(vec![1, 2, 3], 1, |_| ControlFlow::Break(()));
+    /// assert_eq!(aborted.unwrap_err().len(), 3);
+    /// ```
+    pub fn from_vec_with_progress<F>(
+        vec: Vec<T>,
+        every: usize,
+        mut callback: F,
+    ) -> Result<WeakHeap<T>, Vec<T>>
+    where
+        F: FnMut(Progress) -> ControlFlow<()>,
+    {
+        assert!(every > 0, "progress interval must be positive");
+
+        let total = vec.len();
+        let mut heap = WeakHeap {
+            data: vec,
+            bit: BitVec::zeros(total),
+        };
+        for pos in (1..total).rev() {
+            // SAFETY: pos goes from total - 1 down to 1.
+            unsafe { raw::join(&mut heap.data, &mut heap.bit, pos, &mut raw::lt) };
+
+            let done = total - pos;
+            if done.is_multiple_of(every) && callback(Progress { done, total }).is_break() {
+                return Err(heap.data);
+            }
+        }
+        Ok(heap)
+    }
+
+    /// Consumes the `WeakHeap` and returns a vector in sorted (ascending) order
+    /// like [`into_sorted_vec`], invoking `callback` every `every` processed elements.
+    ///
+    /// If the callback returns [`ControlFlow::Break`], the operation is aborted
+    /// and the heap is returned with all of its elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::ops::ControlFlow;
+    /// use weakheap::WeakHeap;
+    ///
+    /// let heap = WeakHeap::from(vec![3, 1, 2, 5, 4]);
+    /// let aborted = heap.into_sorted_vec_with_progress(1, |p| {
+    ///     if p.done < 2 {
+    ///         ControlFlow::Continue(())
+    ///     } else {
+    ///         ControlFlow::Break(())
+    ///     }
+    /// });
+    ///
+    /// let heap = aborted.unwrap_err();
+    /// let sorted = heap.into_sorted_vec_with_progress(2, |_| ControlFlow::Continue(()));
+    /// assert_eq!(sorted.unwrap(), vec![1, 2, 3, 4, 5]);
+    /// ```
+    ///
+    /// [`into_sorted_vec`]: WeakHeap::into_sorted_vec
+    pub fn into_sorted_vec_with_progress<F>(
+        mut self,
+        every: usize,
+        mut callback: F,
+    ) -> Result<Vec<T>, WeakHeap<T>>
+    where
+        F: FnMut(Progress) -> ControlFlow<()>,
+    {
+        assert!(every > 0, "progress interval must be positive");
+
+        let total = self.len();
+        let mut end = total;
+        while end > 1 {
+            end -= 1;
+            self.data.swap(0, end);
+            // SAFETY: 0 < end < self.len().
+            unsafe { self.sift_down_range(0, end) };
+
+            let done = total - end;
+            if done.is_multiple_of(every) && callback(Progress { done, total }).is_break() {
+                // The sorted tail holds the greatest elements, so putting them
+                // back into the heap is cheap.
+                self.rebuild_tail(end);
+                return Err(self);
+            }
+        }
+        Ok(self.into_vec())
+    }
+
+    /// Moves all the elements of vector `other` into `self` like [`append_vec`],
+    /// invoking `callback` every `every` inserted elements.
+    ///
+    /// If the callback returns [`ControlFlow::Break`], the operation is aborted:
+    /// the heap keeps the elements inserted so far and the rest is left in `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `every` is zero.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use std::ops::ControlFlow;
+    /// use weakheap::WeakHeap;
+    ///
+    /// let mut heap = WeakHeap::from(vec![1, 2]);
+    /// let mut v = vec![3, 4, 5, 6];
+    ///
+    /// let flow = heap.append_vec_with_progress(&mut v, 3, |_| ControlFlow::Break(()));
+    /// assert!(flow.is_break());
+    /// assert_eq!(heap.len(), 5);
+    /// assert_eq!(v, [6]);
+    ///
+    /// let flow = heap.append_vec_with_progress(&mut v, 3, |_| ControlFlow::Continue(()));
+    /// assert!(flow.is_continue());
+    /// assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4, 5, 6]);
+    /// assert!(v.is_empty());
+    /// ```
+    ///
+    /// [`append_vec`]: WeakHeap::append_vec
+    pub fn append_vec_with_progress<F>(
+        &mut self,
+        other: &mut Vec<T>,
+        every: usize,
+        mut callback: F,
+    ) -> ControlFlow<()>
+    where
+        F: FnMut(Progress) -> ControlFlow<()>,
+    {
+        assert!(every > 0, "progress interval must be positive");
+
+        let start = self.len();
+        let total = other.len();
+        self.bit.resize(start + total, false);
+        self.data.append(other);
+
+        for pos in start..self.len() {
+            // SAFETY: pos < self.len().
+            unsafe { self.sift_up(pos) };
+
+            let done = pos + 1 - start;
+            if done.is_multiple_of(every) && callback(Progress { done, total }).is_break() {
+                other.extend(self.data.drain(pos + 1..));
+                self.bit.truncate(pos + 1);
+                return ControlFlow::Break(());
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
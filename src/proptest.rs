@@ -0,0 +1,131 @@
+//! [`proptest`](::proptest) strategies generating weak heaps.
+//!
+//! Every strategy generates valid heaps, built or pushed through the public
+//! API, so shrinking a failing case (which removes and simplifies elements)
+//! yields valid heaps as well. `WeakHeap<T>` also implements
+//! [`Arbitrary`], so `any::<WeakHeap<T>>()` generates heaps of up to 100
+//! arbitrary elements.
+//!
+//! Available with the `proptest` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use proptest::test_runner::TestRunner;
+//! use weakheap::proptest::duplicate_heavy;
+//!
+//! let mut runner = TestRunner::default();
+//! runner
+//!     .run(&duplicate_heavy(any::<u8>(), 3, 0..50), |heap| {
+//!         let sorted = heap.clone().into_sorted_vec();
+//!         prop_assert_eq!(heap.peek(), sorted.last());
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ```
+
+use crate::WeakHeap;
+use ::proptest::arbitrary::Arbitrary;
+use ::proptest::collection::{vec, SizeRange};
+use ::proptest::sample::select;
+use ::proptest::strategy::{BoxedStrategy, Strategy};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Generates heaps of `size` elements generated by `element`, in arbitrary
+/// order.
+///
+/// # Examples
+///
+/// ```
+/// use proptest::prelude::*;
+/// use proptest::test_runner::TestRunner;
+/// use weakheap::proptest::weak_heap;
+///
+/// let mut runner = TestRunner::default();
+/// runner
+///     .run(&weak_heap(any::<i32>(), 0..100), |heap| {
+///         prop_assert_eq!(heap.peek(), heap.iter().max());
+///         Ok(())
+///     })
+///     .unwrap();
+/// ```
+pub fn weak_heap<S>(
+    element: S,
+    size: impl Into<SizeRange>,
+) -> impl Strategy<Value = WeakHeap<S::Value>>
+where
+    S: Strategy,
+    S::Value: Ord,
+{
+    vec(element, size).prop_map(WeakHeap::from)
+}
+
+/// Generates heaps of `size` elements, drawn from a pool of at most
+/// `distinct` values generated by `element`, so that most elements have
+/// duplicates.
+///
+/// # Panics
+///
+/// Panics if `distinct` is zero.
+pub fn duplicate_heavy<S>(
+    element: S,
+    distinct: usize,
+    size: impl Into<SizeRange>,
+) -> impl Strategy<Value = WeakHeap<S::Value>>
+where
+    S: Strategy,
+    S::Value: Ord + Clone + fmt::Debug + 'static,
+{
+    assert!(distinct > 0, "the pool needs at least one value");
+    let size = size.into();
+    vec(element, 1..=distinct)
+        .prop_flat_map(move |pool| vec(select(pool), size.clone()))
+        .prop_map(WeakHeap::from)
+}
+
+/// Generates heaps of `size` elements generated by `element`, pushed one by
+/// one in an order which is hard on a heap: ascending (every push sifts up to
+/// the root), descending, organ pipe (ascending, then descending) or random.
+///
+/// The resulting layouts differ from the ones built by
+/// [`WeakHeap::from`], which rebuilds the heap from scratch.
+pub fn adversarial<S>(
+    element: S,
+    size: impl Into<SizeRange>,
+) -> impl Strategy<Value = WeakHeap<S::Value>>
+where
+    S: Strategy,
+    S::Value: Ord,
+{
+    (vec(element, size), 0..4u8).prop_map(|(mut elements, order)| {
+        match order {
+            0 => elements.sort(),
+            1 => elements.sort_by(|a, b| b.cmp(a)),
+            2 => {
+                elements.sort();
+                let back: Vec<_> = elements.drain(elements.len() / 2..).rev().collect();
+                elements.extend(back);
+            }
+            _ => {}
+        }
+        let mut heap = WeakHeap::with_capacity(elements.len());
+        for item in elements {
+            heap.push(item);
+        }
+        heap
+    })
+}
+
+impl<T> Arbitrary for WeakHeap<T>
+where
+    T: Arbitrary + Ord + 'static,
+{
+    type Parameters = T::Parameters;
+    type Strategy = BoxedStrategy<WeakHeap<T>>;
+
+    fn arbitrary_with(args: T::Parameters) -> Self::Strategy {
+        weak_heap(T::arbitrary_with(args), 0..100).boxed()
+    }
+}
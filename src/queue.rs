@@ -0,0 +1,600 @@
+//! A priority queue made of a forest of perfect weak heaps.
+
+use crate::raw;
+use crate::tracked::Slab;
+use crate::IntoIter;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FromIterator;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+
+// Link to a missing node.
+const NONE: usize = usize::MAX;
+
+#[derive(Clone)]
+struct Node<T> {
+    item: T,
+    // The subtree dominated by this node.
+    right: usize,
+    // The subtree dominated by the distinguished ancestor of this node.
+    left: usize,
+}
+
+/// A priority queue implemented with a weak queue: a forest of perfect weak
+/// heaps, at most one of every size 2<sup>*r*</sup>, like the digits of a
+/// binary number.
+///
+/// A perfect weak heap of 2<sup>*r*+1</sup> elements is linked from two of
+/// 2<sup>*r*</sup> elements with a single comparison, by making the loser's
+/// root the right child of the winner's. Pushed elements wait in an
+/// insertion buffer, so `push` costs one comparison, with the current
+/// maximum, in the worst case. `pop` empties the buffer into the forest,
+/// removes the root holding the maximum and links the perfect weak heaps of
+/// its right spine back in.
+///
+/// Besides the sifting operations specific to [`WeakHeap`], it offers the
+/// same API: conversions from vectors and iterators, [`peek_mut`],
+/// [`append`], [`drain`] and consuming iteration.
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::WeakQueue;
+///
+/// let mut queue = WeakQueue::new();
+/// queue.push(1);
+/// queue.push(5);
+/// queue.push(2);
+///
+/// assert_eq!(queue.peek(), Some(&5));
+/// assert_eq!(queue.len(), 3);
+///
+/// assert_eq!(queue.pop(), Some(5));
+/// assert_eq!(queue.pop(), Some(2));
+/// assert_eq!(queue.pop(), Some(1));
+/// assert_eq!(queue.pop(), None);
+/// ```
+///
+/// [`WeakHeap`]: crate::WeakHeap
+/// [`peek_mut`]: WeakQueue::peek_mut
+/// [`append`]: WeakQueue::append
+/// [`drain`]: WeakQueue::drain
+pub struct WeakQueue<T> {
+    nodes: Slab<Node<T>>,
+    // The root of the perfect weak heap of 2^r elements at index r.
+    trees: Vec<usize>,
+    // Pushed nodes not linked into the forest yet.
+    buffer: Vec<usize>,
+    // The node holding the greatest element, a root or in the buffer.
+    top: usize,
+    // Set while a `WeakQueuePeekMut` may have modified the element of `top`,
+    // which then waits in the buffer. Stays set if the guard is leaked, and
+    // the next `pop` searches for the greatest element again.
+    stale: bool,
+    len: usize,
+}
+
+/// Structure wrapping a mutable reference to the greatest item on a
+/// `WeakQueue`.
+///
+/// This `struct` is created by the [`peek_mut`] method on [`WeakQueue`]. See
+/// its documentation for more.
+///
+/// [`peek_mut`]: WeakQueue::peek_mut
+pub struct WeakQueuePeekMut<'a, T: 'a + Ord> {
+    queue: &'a mut WeakQueue<T>,
+}
+
+impl<T: Ord + fmt::Debug> fmt::Debug for WeakQueuePeekMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WeakQueuePeekMut").field(&**self).finish()
+    }
+}
+
+impl<T: Ord> Drop for WeakQueuePeekMut<'_, T> {
+    fn drop(&mut self) {
+        if self.queue.stale {
+            self.queue.flush();
+            self.queue.update_top();
+        }
+    }
+}
+
+impl<T: Ord> Deref for WeakQueuePeekMut<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.queue.nodes.at(self.queue.top).item
+    }
+}
+
+impl<T: Ord> DerefMut for WeakQueuePeekMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        let queue = &mut *self.queue;
+        if !queue.stale {
+            // The greatest item may be modified, so take it out of its tree
+            // into the buffer, where it can hold any value.
+            queue.flush();
+            let top = queue.detach_top();
+            queue.buffer.push(top);
+            queue.stale = true;
+        }
+        &mut queue.nodes.at_mut(queue.top).item
+    }
+}
+
+impl<T: Ord> WeakQueuePeekMut<'_, T> {
+    /// Removes the peeked value from the queue and returns it.
+    pub fn pop(this: WeakQueuePeekMut<'_, T>) -> T {
+        let queue = &mut *this.queue;
+        if !queue.stale {
+            return queue
+                .pop()
+                .expect("PeekMut is only created for non-empty queues");
+        }
+        // The possibly modified item is the last one in the buffer.
+        let top = queue.buffer.pop();
+        debug_assert_eq!(top, Some(queue.top));
+        let node = queue.nodes.remove(queue.top);
+        queue.len -= 1;
+        queue.flush();
+        queue.update_top();
+        node.item
+    }
+}
+
+impl<T: Clone> Clone for WeakQueue<T> {
+    fn clone(&self) -> Self {
+        WeakQueue {
+            nodes: self.nodes.clone(),
+            trees: self.trees.clone(),
+            buffer: self.buffer.clone(),
+            top: self.top,
+            stale: self.stale,
+            len: self.len,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for WeakQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> Default for WeakQueue<T> {
+    /// Creates an empty `WeakQueue`.
+    #[inline]
+    fn default() -> WeakQueue<T> {
+        WeakQueue::new()
+    }
+}
+
+impl<T: Ord> WeakQueue<T> {
+    /// Creates an empty `WeakQueue` as a max-heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakQueue;
+    /// let mut queue = WeakQueue::new();
+    /// queue.push(4);
+    /// ```
+    #[must_use]
+    pub fn new() -> WeakQueue<T> {
+        WeakQueue {
+            nodes: Slab::default(),
+            trees: Vec::new(),
+            buffer: Vec::new(),
+            top: NONE,
+            stale: false,
+            len: 0,
+        }
+    }
+
+    /// Creates an empty `WeakQueue` with a specific capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakQueue;
+    /// let mut queue = WeakQueue::with_capacity(10);
+    /// queue.push(4);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> WeakQueue<T> {
+        WeakQueue {
+            nodes: Slab::with_capacity(capacity),
+            trees: Vec::new(),
+            buffer: Vec::with_capacity(capacity),
+            top: NONE,
+            stale: false,
+            len: 0,
+        }
+    }
+
+    /// Pushes an item onto the queue.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakQueue;
+    /// let mut queue = WeakQueue::new();
+    /// queue.push(3);
+    /// queue.push(5);
+    /// queue.push(1);
+    ///
+    /// assert_eq!(queue.len(), 3);
+    /// assert_eq!(queue.peek(), Some(&5));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    pub fn push(&mut self, item: T) {
+        let node = self.nodes.insert(Node {
+            item,
+            right: NONE,
+            left: NONE,
+        });
+        self.buffer.push(node);
+        self.len += 1;
+        if self.top == NONE || self.less(self.top, node) {
+            self.top = node;
+        }
+    }
+
+    /// Removes the greatest item from the queue and returns it, or `None` if
+    /// it is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakQueue;
+    /// let mut queue: WeakQueue<_> = vec![1, 3].into_iter().collect();
+    ///
+    /// assert_eq!(queue.pop(), Some(3));
+    /// assert_eq!(queue.pop(), Some(1));
+    /// assert_eq!(queue.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The amortized cost of `pop` on a queue containing *n* elements is
+    /// *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        if self.top == NONE {
+            return None;
+        }
+        self.flush();
+        if self.stale {
+            self.update_top();
+        }
+        let top = self.detach_top();
+        let node = self.nodes.remove(top);
+        self.len -= 1;
+        self.update_top();
+        Some(node.item)
+    }
+
+    /// Returns a mutable reference to the greatest item in the queue, or
+    /// `None` if it is empty.
+    ///
+    /// Note: If the `WeakQueuePeekMut` value is leaked after the item was
+    /// modified, the queue keeps all its elements and the next `pop` returns
+    /// the greatest one, but `peek` may return the modified item until then.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakQueue;
+    /// let mut queue: WeakQueue<_> = vec![1, 5, 2].into();
+    /// {
+    ///     let mut val = queue.peek_mut().unwrap();
+    ///     *val = 0;
+    /// }
+    /// assert_eq!(queue.peek(), Some(&2));
+    /// assert_eq!(queue.into_sorted_vec(), [0, 1, 2]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// If the item is modified then the amortized time complexity is
+    /// *O*(log(*n*)), otherwise it's *O*(1).
+    pub fn peek_mut(&mut self) -> Option<WeakQueuePeekMut<'_, T>> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(WeakQueuePeekMut { queue: self })
+        }
+    }
+
+    /// Moves all the elements of `other` into `self`, leaving `other` empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakQueue;
+    ///
+    /// let mut a = WeakQueue::from(vec![-10, 1, 2, 3, 3]);
+    /// let mut b = WeakQueue::from(vec![-20, 5, 43]);
+    ///
+    /// a.append(&mut b);
+    ///
+    /// assert_eq!(a.into_sorted_vec(), [-20, -10, 1, 2, 3, 3, 5, 43]);
+    /// assert!(b.is_empty());
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(*m*), where *m* is the length of `other`, since every
+    /// element is pushed into the insertion buffer.
+    pub fn append(&mut self, other: &mut Self) {
+        self.extend(other.drain());
+    }
+
+    /// Consumes the queue and returns a vector in sorted (ascending) order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakQueue;
+    /// let queue: WeakQueue<_> = vec![4, 1, 3, 2].into_iter().collect();
+    ///
+    /// assert_eq!(queue.into_sorted_vec(), [1, 2, 3, 4]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut vec = Vec::with_capacity(self.len());
+        while let Some(item) = self.pop() {
+            vec.push(item);
+        }
+        vec.reverse();
+        vec
+    }
+
+    fn less(&self, a: usize, b: usize) -> bool {
+        raw::lt(&self.nodes.at(a).item, &self.nodes.at(b).item)
+    }
+
+    // Links the buffered nodes into the forest.
+    fn flush(&mut self) {
+        for node in mem::take(&mut self.buffer) {
+            self.add_tree(node, 0);
+        }
+    }
+
+    // Takes the root `top` out of the forest, linking the perfect weak heaps
+    // of its right spine back in, and returns it as a single node. The buffer
+    // must be empty.
+    fn detach_top(&mut self) -> usize {
+        let top = self.top;
+        let rank = self.trees.iter().position(|&root| root == top);
+        let rank = rank.expect("the greatest element is a root");
+        self.trees[rank] = NONE;
+
+        // The right spine holds the roots of the perfect weak heaps of
+        // 2^(rank - 1), ..., 2, 1 elements.
+        let mut child = mem::replace(&mut self.nodes.at_mut(top).right, NONE);
+        for rank in (0..rank).rev() {
+            let next = mem::replace(&mut self.nodes.at_mut(child).left, NONE);
+            self.add_tree(child, rank);
+            child = next;
+        }
+
+        while self.trees.last() == Some(&NONE) {
+            self.trees.pop();
+        }
+        top
+    }
+
+    // Finds the greatest element among the roots. The buffer must be empty.
+    fn update_top(&mut self) {
+        self.top = NONE;
+        self.stale = false;
+        for rank in 0..self.trees.len() {
+            let root = self.trees[rank];
+            if root != NONE && (self.top == NONE || self.less(self.top, root)) {
+                self.top = root;
+            }
+        }
+    }
+
+    // Adds the perfect weak heap of 2^rank elements rooted at `root`, linking
+    // it with the trees of the same size like a binary carry.
+    fn add_tree(&mut self, mut root: usize, mut rank: usize) {
+        loop {
+            if self.trees.len() <= rank {
+                self.trees.resize(rank + 1, NONE);
+            }
+            match mem::replace(&mut self.trees[rank], NONE) {
+                NONE => {
+                    self.trees[rank] = root;
+                    return;
+                }
+                other => {
+                    root = self.link(other, root);
+                    rank += 1;
+                }
+            }
+        }
+    }
+
+    // Links two perfect weak heaps of the same size and returns the root of
+    // the result.
+    fn link(&mut self, a: usize, b: usize) -> usize {
+        let (winner, loser) = if self.less(a, b) { (b, a) } else { (a, b) };
+        let below = mem::replace(&mut self.nodes.at_mut(winner).right, loser);
+        self.nodes.at_mut(loser).left = below;
+        if self.top == loser {
+            // An element equal to the greatest one won.
+            self.top = winner;
+        }
+        winner
+    }
+}
+
+impl<T> WeakQueue<T> {
+    /// Returns the greatest item in the queue, or `None` if it is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        if self.top == NONE {
+            return None;
+        }
+        Some(&self.nodes.at(self.top).item)
+    }
+
+    /// Returns an iterator visiting all values in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.nodes.iter().map(|(_, node)| &node.item)
+    }
+
+    /// Returns the number of elements in the queue.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Clears the queue, returning an iterator over the removed elements.
+    ///
+    /// The elements are removed in arbitrary order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakQueue;
+    /// let mut queue = WeakQueue::from(vec![1, 3]);
+    ///
+    /// let mut drained: Vec<_> = queue.drain().collect();
+    /// drained.sort();
+    ///
+    /// assert_eq!(drained, [1, 3]);
+    /// assert!(queue.is_empty());
+    /// ```
+    pub fn drain(&mut self) -> IntoIter<T> {
+        let items: Vec<T> = self.nodes.drain().map(|node| node.item).collect();
+        self.clear();
+        IntoIter {
+            iter: items.into_iter(),
+        }
+    }
+
+    /// Drops all items from the queue.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.trees.clear();
+        self.buffer.clear();
+        self.top = NONE;
+        self.stale = false;
+        self.len = 0;
+    }
+
+    /// Consumes the queue and returns its elements in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(mut self) -> Vec<T> {
+        self.nodes.drain().map(|node| node.item).collect()
+    }
+}
+
+impl<T: Ord> From<Vec<T>> for WeakQueue<T> {
+    /// Converts a `Vec<T>` into a `WeakQueue<T>`.
+    ///
+    /// This conversion has *O*(*n*) time complexity, since every element is
+    /// pushed into the insertion buffer.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakQueue;
+    /// let queue = WeakQueue::from(vec![5, 3, 2, 4, 1]);
+    /// assert_eq!(queue.into_sorted_vec(), [1, 2, 3, 4, 5]);
+    /// ```
+    fn from(vec: Vec<T>) -> WeakQueue<T> {
+        let mut queue = WeakQueue::with_capacity(vec.len());
+        queue.extend(vec);
+        queue
+    }
+}
+
+impl<T: Ord, const N: usize> From<[T; N]> for WeakQueue<T> {
+    /// Converts a `[T, N]` into a `WeakQueue<T>`.
+    ///
+    /// This conversion has *O*(*n*) time complexity.
+    fn from(arr: [T; N]) -> Self {
+        arr.into_iter().collect()
+    }
+}
+
+impl<T> IntoIterator for WeakQueue<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    /// Creates a consuming iterator, that is, one that moves each value out of
+    /// the queue in arbitrary order. The queue cannot be used after calling
+    /// this.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakQueue;
+    /// let queue = WeakQueue::from(vec![1, 2, 3, 4]);
+    ///
+    /// // Print 1, 2, 3, 4 in arbitrary order
+    /// for x in queue {
+    ///     println!("{}", x);
+    /// }
+    /// ```
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            iter: self.into_vec().into_iter(),
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for WeakQueue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> WeakQueue<T> {
+        let mut queue = WeakQueue::new();
+        queue.extend(iter);
+        queue
+    }
+}
+
+impl<T: Ord> Extend<T> for WeakQueue<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
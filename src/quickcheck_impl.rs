@@ -0,0 +1,20 @@
+//! `quickcheck` support.
+
+use crate::WeakHeap;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use quickcheck::{Arbitrary, Gen};
+
+/// Generates a vector of arbitrary elements and builds the heap from it.
+///
+/// Shrinking shrinks that vector, by removing elements or shrinking them, and
+/// rebuilds a heap from each smaller vector, so every shrunk heap is valid.
+impl<T: Arbitrary + Ord> Arbitrary for WeakHeap<T> {
+    fn arbitrary(g: &mut Gen) -> WeakHeap<T> {
+        WeakHeap::from(Vec::arbitrary(g))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = WeakHeap<T>>> {
+        Box::new(self.data.shrink().map(WeakHeap::from))
+    }
+}
@@ -0,0 +1,406 @@
+//! Sifting routines shared by every container in this crate.
+//!
+//! All functions operate on an element slice `data` and reverse bits `bit` of
+//! the same length, either a `bool` slice or a packed `BitVec`, and compare
+//! elements through `is_less`, so the same code serves max-heaps, min-heaps
+//! and heaps with custom comparators.
+
+use crate::bits::Bits;
+use core::mem::ManuallyDrop;
+use core::ptr;
+
+// Updates the counters of the `stats` feature, compiles to nothing without it.
+macro_rules! record {
+    ($($field:ident),+) => {
+        #[cfg(feature = "stats")]
+        crate::stats::record(|stats| {
+            $(stats.$field += 1;)+
+        });
+    };
+}
+
+/// Compares two elements of an `Ord` type, `a < b`.
+///
+/// In debug builds, and with the `paranoid` feature, a comparison finding
+/// `a < b` is cross-checked against `b < a`, and a panic reports the
+/// inconsistency, instead of letting a broken `Ord` implementation silently
+/// corrupt the heap. Only comparisons which are true are checked, and those
+/// are the ones followed by a swap or a move, so the check costs at most one
+/// extra comparison per element moved.
+#[inline]
+pub(crate) fn lt<T: Ord>(a: &T, b: &T) -> bool {
+    let less = a < b;
+    #[cfg(any(debug_assertions, feature = "paranoid"))]
+    if less && b < a {
+        inconsistent::<T>();
+    }
+    less
+}
+
+#[cfg(any(debug_assertions, feature = "paranoid"))]
+#[cold]
+#[inline(never)]
+fn inconsistent<T>() -> ! {
+    panic!(
+        "inconsistent `Ord` implementation for `{}`: found a < b and b < a",
+        core::any::type_name::<T>(),
+    );
+}
+
+/// Returns the distinguished ancestor of `pos`, that is the parent of the
+/// first node on the way to the root for which `pos` lies in the right subtree.
+///
+/// # Safety
+///
+/// The caller must guarantee that `0 < pos < bit.len()`.
+#[inline]
+pub(crate) unsafe fn d_ancestor<B: Bits + ?Sized>(bit: &B, pos: usize) -> usize {
+    let mut cur = pos;
+    let mut ancestor = cur / 2;
+    while ancestor > 0 && (cur % 2 == bit.get_unchecked(ancestor) as usize) {
+        cur /= 2;
+        ancestor /= 2;
+    }
+    ancestor
+}
+
+/// Joins the weak heap rooted at `pos` with its distinguished ancestor,
+/// swapping them and flipping the reverse bit of `pos` if needed.
+///
+/// # Safety
+///
+/// The caller must guarantee that `0 < pos < data.len()` and `bit.len() == data.len()`.
+#[inline]
+pub(crate) unsafe fn join<T, B, F>(data: &mut [T], bit: &mut B, pos: usize, is_less: &mut F)
+where
+    B: Bits + ?Sized,
+    F: FnMut(&T, &T) -> bool,
+{
+    let ancestor = d_ancestor(bit, pos);
+    record!(joins);
+
+    // SAFETY: `0 <= ancestor < pos < data.len()`
+    if is_less(data.get_unchecked(ancestor), data.get_unchecked(pos)) {
+        record!(swaps, bit_flips);
+        bit.flip_unchecked(pos);
+        let ptr = data.as_mut_ptr();
+        ptr::swap_nonoverlapping(ptr.add(ancestor), ptr.add(pos), 1);
+    }
+}
+
+/// Raises the element at `pos` towards the root until it is not greater than
+/// its distinguished ancestor. Returns the final position of the element.
+///
+/// Only the elements on the path are moved, the reverse bits stay untouched:
+/// every element that moves down is greater than or equal to the one it replaces.
+///
+/// # Safety
+///
+/// The caller must guarantee that `pos < data.len()` and `bit.len() == data.len()`.
+pub(crate) unsafe fn sift_up<T, B, F>(data: &mut [T], bit: &B, pos: usize, is_less: &mut F) -> usize
+where
+    B: Bits + ?Sized,
+    F: FnMut(&T, &T) -> bool,
+{
+    let mut hole = Hole::new(data, pos);
+    record!(sift_ups);
+
+    while hole.pos() > 0 {
+        let ancestor = d_ancestor(bit, hole.pos());
+        if is_less(hole.get(ancestor), hole.element()) {
+            record!(sift_up_moves);
+            hole.move_to(ancestor);
+        } else {
+            break; // Heap property restored.
+        }
+    }
+
+    hole.pos()
+}
+
+/// Takes the element at `pos` and moves it down the heap formed by
+/// `data[..end]`, restoring the heap property.
+///
+/// Sifting down in a weak heap can be done in *log(2, n)* comparisons,
+/// as opposed to *2log(2, n)* for binary heap. Every element on the path is
+/// moved at most once, see [`sift_hole_down`].
+///
+/// # Safety
+///
+/// The caller must guarantee that `pos < end <= data.len()` and `bit.len() == data.len()`.
+pub(crate) unsafe fn sift_down_range<T, B, F>(
+    data: &mut [T],
+    bit: &mut B,
+    pos: usize,
+    end: usize,
+    is_less: &mut F,
+) where
+    B: Bits + ?Sized,
+    F: FnMut(&T, &T) -> bool,
+{
+    let mut hole = Hole::new(data, pos);
+    sift_hole_down(&mut hole, bit, end, is_less);
+}
+
+/// Moves the greatest element of the heap formed by `data[..=end]` to `end`,
+/// and restores the heap property of `data[..end]`, as one step of
+/// weak-heapsort.
+///
+/// Unlike swapping the root with `data[end]` and sifting the swapped element
+/// down, every element on the sift path is moved at most once.
+///
+/// # Safety
+///
+/// The caller must guarantee that `0 < end < data.len()` and `bit.len() == data.len()`.
+pub(crate) unsafe fn extract_to_end<T, B, F>(
+    data: &mut [T],
+    bit: &mut B,
+    end: usize,
+    is_less: &mut F,
+) where
+    B: Bits + ?Sized,
+    F: FnMut(&T, &T) -> bool,
+{
+    let mut hole = Hole::new(data, end);
+    hole.move_to(0);
+    sift_hole_down(&mut hole, bit, end, is_less);
+}
+
+/// Moves the element of `hole` down the heap formed by `data[..end]`,
+/// starting from the position of the hole.
+///
+/// The comparisons are made first, against the element which would be at the
+/// position of the hole after the swaps so far, and the elements which would
+/// be swapped are then rotated along the path, one move each.
+///
+/// # Safety
+///
+/// The caller must guarantee that `hole.pos() < end <= data.len()` and
+/// `bit.len() == data.len()`.
+unsafe fn sift_hole_down<T, B, F>(hole: &mut Hole<'_, T>, bit: &mut B, end: usize, is_less: &mut F)
+where
+    B: Bits + ?Sized,
+    F: FnMut(&T, &T) -> bool,
+{
+    let pos = hole.pos();
+    // The right child of `pos` (the root has only a right child).
+    let mut bottom = if pos == 0 {
+        1
+    } else {
+        2 * pos + 1 - bit.get_unchecked(pos) as usize
+    };
+    if bottom >= end {
+        return;
+    }
+    record!(sift_downs);
+
+    // We go down the left descendants as low as possible.
+    while bottom * 2 + (bit.get_unchecked(bottom) as usize) < end {
+        bottom = 2 * bottom + (bit.get_unchecked(bottom) as usize);
+    }
+
+    // Bit `level` of `swaps` is set if the element at `bottom >> level` is
+    // swapped with the root of the sift. The path is shorter than
+    // `usize::BITS`, since its nodes are distinct positive `usize` values.
+    let mut swaps: usize = 0;
+    // The element which is at the root of the sift after the swaps so far.
+    let mut top: *const T = hole.element();
+    let mut level = 0;
+    while bottom >> level > pos {
+        record!(sift_down_depth);
+        let cur = hole.get(bottom >> level);
+        // SAFETY: `top` points either into the hole or at an element of the
+        // path, neither of which is moved before the rotation below.
+        if is_less(&*top, cur) {
+            swaps |= 1 << level;
+            top = cur;
+        }
+        level += 1;
+    }
+
+    // Each swapped element takes the place of the one swapped before it, and
+    // the last one takes the place of `pos`, where the hole is.
+    while swaps != 0 {
+        record!(swaps, bit_flips);
+        let level = (usize::BITS - 1 - swaps.leading_zeros()) as usize;
+        swaps &= !(1 << level);
+        let cur = bottom >> level;
+        bit.flip_unchecked(cur);
+        hole.move_to(cur);
+    }
+}
+
+/// Restores the heap property after the element at `pos` has been replaced
+/// by an arbitrary value, moving it up or down as needed.
+///
+/// # Safety
+///
+/// The caller must guarantee that `pos < data.len()` and `bit.len() == data.len()`.
+pub(crate) unsafe fn sift<T, B, F>(data: &mut [T], bit: &mut B, pos: usize, is_less: &mut F)
+where
+    B: Bits + ?Sized,
+    F: FnMut(&T, &T) -> bool,
+{
+    if pos > 0
+        && is_less(
+            data.get_unchecked(d_ancestor(bit, pos)),
+            data.get_unchecked(pos),
+        )
+    {
+        sift_up(data, bit, pos, is_less);
+    } else {
+        sift_down_range(data, bit, pos, data.len(), is_less);
+    }
+}
+
+/// Returns the position of the smallest element, or `None` if `data` is empty.
+///
+/// Only the nodes without a right subtree are inspected, since every other
+/// node is not less than the elements of its right subtree.
+pub(crate) fn min_position<T, B, F>(data: &[T], bit: &B, is_less: &mut F) -> Option<usize>
+where
+    B: Bits + ?Sized,
+    F: FnMut(&T, &T) -> bool,
+{
+    debug_assert_eq!(data.len(), bit.len());
+    let len = data.len();
+    let has_right_child = |pos: usize| {
+        if pos == 0 {
+            len > 1
+        } else {
+            // SAFETY: pos < len == bit.len().
+            2 * pos + 1 - (unsafe { bit.get_unchecked(pos) } as usize) < len
+        }
+    };
+
+    let mut candidates = (len.saturating_sub(1) / 2..len).filter(|&pos| !has_right_child(pos));
+    let first = candidates.next()?;
+    Some(candidates.fold(first, |min, pos| {
+        if is_less(&data[pos], &data[min]) {
+            pos
+        } else {
+            min
+        }
+    }))
+}
+
+/// Returns the first position, together with its distinguished ancestor,
+/// whose element is greater than the ancestor's.
+///
+/// The caller must guarantee that `bit.len() >= data.len()`.
+pub(crate) fn order_violation<T, B, F>(
+    data: &[T],
+    bit: &B,
+    is_less: &mut F,
+) -> Option<(usize, usize)>
+where
+    B: Bits + ?Sized,
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(bit.len() >= data.len());
+    (1..data.len()).find_map(|pos| {
+        // SAFETY: 0 < pos < data.len() <= bit.len().
+        let ancestor = unsafe { d_ancestor(bit, pos) };
+        is_less(&data[ancestor], &data[pos]).then_some((pos, ancestor))
+    })
+}
+
+/// Builds a weak heap from scratch. Time complexity: *O*(*n*).
+pub(crate) fn rebuild<T, B, F>(data: &mut [T], bit: &mut B, is_less: &mut F)
+where
+    B: Bits + ?Sized,
+    F: FnMut(&T, &T) -> bool,
+{
+    debug_assert_eq!(data.len(), bit.len());
+    bit.reset();
+    for pos in (1..data.len()).rev() {
+        // SAFETY: `pos` starts from data.len() - 1 and goes down to 1.
+        unsafe { join(data, bit, pos, is_less) };
+    }
+}
+
+/// Rebuilds the heap assuming `data[..start]` is still a proper weak heap.
+pub(crate) fn rebuild_tail<T, B, F>(data: &mut [T], bit: &mut B, start: usize, is_less: &mut F)
+where
+    B: Bits + ?Sized,
+    F: FnMut(&T, &T) -> bool,
+{
+    debug_assert_eq!(data.len(), bit.len());
+    for pos in start..data.len() {
+        // SAFETY: index `pos` is always less than data.len().
+        unsafe { sift_up(data, bit, pos, is_less) };
+    }
+}
+
+/// Hole represents a hole in a slice i.e., an index without valid value
+/// (because it was moved from or duplicated).
+/// In drop, `Hole` will restore the slice by filling the hole
+/// position with the value that was originally removed.
+pub(crate) struct Hole<'a, T: 'a> {
+    data: &'a mut [T],
+    elt: ManuallyDrop<T>,
+    pos: usize,
+}
+
+impl<'a, T> Hole<'a, T> {
+    /// Create a new `Hole` at index `pos`.
+    ///
+    /// Unsafe because pos must be within the data slice.
+    #[inline]
+    pub(crate) unsafe fn new(data: &'a mut [T], pos: usize) -> Self {
+        debug_assert!(pos < data.len());
+        // SAFE: pos should be inside the slice
+        let elt = ptr::read(data.get_unchecked(pos));
+        Hole {
+            data,
+            elt: ManuallyDrop::new(elt),
+            pos,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns a reference to the element removed.
+    #[inline]
+    pub(crate) fn element(&self) -> &T {
+        &self.elt
+    }
+
+    /// Returns a reference to the element at `index`.
+    ///
+    /// Unsafe because index must be within the data slice and not equal to pos.
+    #[inline]
+    pub(crate) unsafe fn get(&self, index: usize) -> &T {
+        debug_assert!(index != self.pos);
+        debug_assert!(index < self.data.len());
+        self.data.get_unchecked(index)
+    }
+
+    /// Move hole to new location
+    ///
+    /// Unsafe because index must be within the data slice and not equal to pos.
+    #[inline]
+    pub(crate) unsafe fn move_to(&mut self, index: usize) {
+        debug_assert!(index != self.pos);
+        debug_assert!(index < self.data.len());
+        let ptr = self.data.as_mut_ptr();
+        let index_ptr: *const _ = ptr.add(index);
+        let hole_ptr = ptr.add(self.pos);
+        ptr::copy_nonoverlapping(index_ptr, hole_ptr, 1);
+        self.pos = index;
+    }
+}
+
+impl<T> Drop for Hole<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // fill the hole again
+        unsafe {
+            let pos = self.pos;
+            ptr::copy_nonoverlapping(&*self.elt, self.data.get_unchecked_mut(pos), 1);
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! A priority queue with bounded per-operation latency.
+
+use crate::raw;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A priority queue implemented with a weak heap, whose operations have a
+/// bounded worst-case cost, for hard real-time loops such as audio callbacks
+/// or controllers.
+///
+/// The capacity is fixed on construction, so no operation ever allocates or
+/// reallocates. Elements can be queued with [`push_deferred`] in *O*(1)
+/// without any comparison. They are integrated into the heap by subsequent
+/// operations, at most `work` of them per operation, and take part in the
+/// ordering only once integrated. [`step`] integrates them explicitly, for
+/// instance while the loop is idle.
+///
+/// With *c* = ⌊log2(*capacity*)⌋ + 1, the worst-case number of comparisons is:
+///
+/// | Operation          | Comparisons       |
+/// |--------------------|-------------------|
+/// | [`push`]           | (1 + `work`) · *c* |
+/// | [`pop`]            | (1 + `work`) · *c* |
+/// | [`push_deferred`]  | 0                 |
+/// | [`peek`]           | 0                 |
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::RealtimeWeakHeap;
+///
+/// let mut heap = RealtimeWeakHeap::with_capacity(1024, 2);
+/// heap.push(5).unwrap();
+/// for x in [9, 1, 7] {
+///     heap.push_deferred(x).unwrap();
+/// }
+/// assert_eq!(heap.len(), 4);
+/// assert_eq!(heap.pending_len(), 3);
+///
+/// // `pop` integrates two deferred elements before popping.
+/// assert_eq!(heap.pop(), Some(9));
+/// assert_eq!(heap.pending_len(), 1);
+///
+/// heap.step(usize::MAX);
+/// assert_eq!(heap.pop(), Some(7));
+/// ```
+///
+/// [`push`]: RealtimeWeakHeap::push
+/// [`pop`]: RealtimeWeakHeap::pop
+/// [`peek`]: RealtimeWeakHeap::peek
+/// [`push_deferred`]: RealtimeWeakHeap::push_deferred
+/// [`step`]: RealtimeWeakHeap::step
+pub struct RealtimeWeakHeap<T> {
+    // The heap occupies `data[..integrated]`, the deferred elements follow it.
+    data: Vec<T>,
+    bit: Vec<bool>,
+    integrated: usize,
+    capacity: usize,
+    work: usize,
+}
+
+impl<T: Clone> Clone for RealtimeWeakHeap<T> {
+    fn clone(&self) -> Self {
+        let mut data = Vec::with_capacity(self.capacity);
+        data.extend_from_slice(&self.data);
+        let mut bit = Vec::with_capacity(self.capacity);
+        bit.extend_from_slice(&self.bit);
+        RealtimeWeakHeap {
+            data,
+            bit,
+            integrated: self.integrated,
+            capacity: self.capacity,
+            work: self.work,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RealtimeWeakHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RealtimeWeakHeap")
+            .field("heap", &&self.data[..self.integrated])
+            .field("pending", &&self.data[self.integrated..])
+            .finish()
+    }
+}
+
+impl<T: Ord> RealtimeWeakHeap<T> {
+    /// Creates an empty `RealtimeWeakHeap` holding up to `capacity` elements,
+    /// where every operation integrates up to `work` deferred elements.
+    ///
+    /// All memory is allocated here.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::RealtimeWeakHeap;
+    /// let mut heap = RealtimeWeakHeap::with_capacity(16, 1);
+    /// heap.push(4).unwrap();
+    /// assert_eq!(heap.capacity(), 16);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize, work: usize) -> RealtimeWeakHeap<T> {
+        RealtimeWeakHeap {
+            data: Vec::with_capacity(capacity),
+            bit: Vec::with_capacity(capacity),
+            integrated: 0,
+            capacity,
+            work,
+        }
+    }
+
+    /// Pushes an item onto the heap. The item is returned back if the heap is full.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::RealtimeWeakHeap;
+    /// let mut heap = RealtimeWeakHeap::with_capacity(2, 0);
+    /// assert_eq!(heap.push(3), Ok(()));
+    /// assert_eq!(heap.push(5), Ok(()));
+    /// assert_eq!(heap.push(1), Err(1));
+    ///
+    /// assert_eq!(heap.peek(), Some(&5));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// At most (1 + `work`) · (⌊log2(*capacity*)⌋ + 1) comparisons.
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        self.push_deferred(item)?;
+        let last = self.data.len() - 1;
+        self.data.swap(self.integrated, last);
+        self.integrate_one();
+        self.step(self.work);
+        Ok(())
+    }
+
+    /// Queues an item to be integrated into the heap by later operations.
+    /// The item is returned back if the heap is full.
+    ///
+    /// The item is not visible to [`peek`] and [`pop`] until integrated.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::RealtimeWeakHeap;
+    /// let mut heap = RealtimeWeakHeap::with_capacity(8, 1);
+    /// heap.push_deferred(3).unwrap();
+    /// assert_eq!(heap.peek(), None);
+    ///
+    /// heap.step(1);
+    /// assert_eq!(heap.peek(), Some(&3));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1), no comparison is made.
+    ///
+    /// [`peek`]: RealtimeWeakHeap::peek
+    /// [`pop`]: RealtimeWeakHeap::pop
+    pub fn push_deferred(&mut self, item: T) -> Result<(), T> {
+        if self.data.len() == self.capacity {
+            return Err(item);
+        }
+        self.data.push(item);
+        self.bit.push(false);
+        Ok(())
+    }
+
+    /// Integrates up to `work` deferred elements into the heap, then removes
+    /// the greatest item from the heap and returns it, or `None` if it is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::RealtimeWeakHeap;
+    /// let mut heap = RealtimeWeakHeap::with_capacity(8, 0);
+    /// heap.push(1).unwrap();
+    /// heap.push(3).unwrap();
+    ///
+    /// assert_eq!(heap.pop(), Some(3));
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// At most (1 + `work`) · (⌊log2(*capacity*)⌋ + 1) comparisons.
+    pub fn pop(&mut self) -> Option<T> {
+        self.step(self.work);
+        if self.integrated == 0 {
+            return None;
+        }
+
+        let last = self.integrated - 1;
+        self.data.swap(0, last);
+        // The last deferred element, if any, takes the place of `last`.
+        let item = self.data.swap_remove(last);
+        self.bit.swap_remove(last);
+        self.integrated = last;
+
+        if last > 1 {
+            // SAFETY: 0 < last <= self.data.len().
+            unsafe { raw::sift_down_range(&mut self.data, &mut self.bit, 0, last, &mut raw::lt) };
+        }
+        Some(item)
+    }
+
+    /// Integrates up to `max` deferred elements into the heap and returns the
+    /// number of deferred elements left.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::RealtimeWeakHeap;
+    /// let mut heap = RealtimeWeakHeap::with_capacity(8, 0);
+    /// heap.push_deferred(1).unwrap();
+    /// heap.push_deferred(2).unwrap();
+    ///
+    /// assert_eq!(heap.step(1), 1);
+    /// assert_eq!(heap.step(1), 0);
+    /// assert_eq!(heap.peek(), Some(&2));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Each integrated element costs at most ⌊log2(*capacity*)⌋ + 1 comparisons.
+    pub fn step(&mut self, max: usize) -> usize {
+        for _ in 0..max.min(self.pending_len()) {
+            self.integrate_one();
+        }
+        self.pending_len()
+    }
+
+    fn integrate_one(&mut self) {
+        let pos = self.integrated;
+        // SAFETY: pos < self.data.len() since an element is pending.
+        unsafe { raw::sift_up(&mut self.data[..=pos], &self.bit[..=pos], pos, &mut raw::lt) };
+        self.integrated += 1;
+    }
+}
+
+impl<T> RealtimeWeakHeap<T> {
+    /// Returns the greatest integrated item, or `None` if there is none.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.data[..self.integrated].first()
+    }
+
+    /// Returns the number of elements in the heap, including the deferred ones.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns the number of deferred elements not integrated yet.
+    #[must_use]
+    pub fn pending_len(&self) -> usize {
+        self.data.len() - self.integrated
+    }
+
+    /// Checks if the heap is empty, including the deferred elements.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the fixed number of elements the heap can hold.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Drops all items from the heap, keeping the allocated memory.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.bit.clear();
+        self.integrated = 0;
+    }
+}
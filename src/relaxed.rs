@@ -0,0 +1,520 @@
+//! An addressable priority queue with constant-time key increases.
+
+use crate::raw;
+use crate::tracked::Slab;
+use crate::Handle;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+
+// Link to a missing node.
+const NONE: usize = usize::MAX;
+
+#[derive(Clone)]
+struct Node<T> {
+    item: T,
+    rank: usize,
+    parent: usize,
+    // The subtree dominated by this node.
+    right: usize,
+    // The subtree dominated by the distinguished ancestor of this node.
+    left: usize,
+}
+
+/// An addressable priority queue implemented with a forest of weak heaps,
+/// whose elements can be increased in amortized constant time.
+///
+/// Every tree is weak-heap ordered: a node is not less than the elements of
+/// its right subtree, and a root has no left subtree. Unlike in an
+/// [`IndexedWeakHeap`], the trees are linked by pointers and need not be
+/// perfect. Increasing an element cuts it out together with its right
+/// subtree as a new tree, and `pop` links the trees pairwise by rank as in a
+/// weak queue. The rank rules are the ones of rank-pairing heaps, which
+/// relax the shape of the trees just enough to make [`increase_key`] cost
+/// *O*(1) amortized, the bound the run-relaxed weak queues of Elmasry, Jensen
+/// and Katajainen guarantee in the worst case with much more bookkeeping.
+///
+/// This will be a max-heap, so increasing an element is the counterpart of
+/// the decrease-key operation of the min-heaps used by Dijkstra's or Prim's
+/// algorithm. Wrap the elements in [`Reverse`] to get a min-heap.
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Reverse;
+/// use weakheap::RelaxedWeakQueue;
+///
+/// // Tentative distances, the shortest first.
+/// let mut queue = RelaxedWeakQueue::new();
+/// let a = queue.push(Reverse(7));
+/// let b = queue.push(Reverse(4));
+/// let c = queue.push(Reverse(9));
+///
+/// // A shorter path to `c` was found.
+/// queue.increase_key(c, Reverse(2));
+///
+/// assert_eq!(queue.pop_with_handle(), Some((c, Reverse(2))));
+/// assert_eq!(queue.pop_with_handle(), Some((b, Reverse(4))));
+/// assert_eq!(queue.get(a), Some(&Reverse(7)));
+/// ```
+///
+/// [`IndexedWeakHeap`]: crate::IndexedWeakHeap
+/// [`increase_key`]: RelaxedWeakQueue::increase_key
+/// [`Reverse`]: core::cmp::Reverse
+pub struct RelaxedWeakQueue<T> {
+    nodes: Slab<Node<T>>,
+    roots: Vec<usize>,
+    // The root holding the greatest element.
+    top: usize,
+    len: usize,
+}
+
+impl<T: Clone> Clone for RelaxedWeakQueue<T> {
+    fn clone(&self) -> Self {
+        RelaxedWeakQueue {
+            nodes: self.nodes.clone(),
+            roots: self.roots.clone(),
+            top: self.top,
+            len: self.len,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RelaxedWeakQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> Default for RelaxedWeakQueue<T> {
+    /// Creates an empty `RelaxedWeakQueue`.
+    #[inline]
+    fn default() -> RelaxedWeakQueue<T> {
+        RelaxedWeakQueue::new()
+    }
+}
+
+impl<T: Ord> RelaxedWeakQueue<T> {
+    /// Creates an empty `RelaxedWeakQueue` as a max-heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::RelaxedWeakQueue;
+    /// let mut queue = RelaxedWeakQueue::new();
+    /// queue.push(4);
+    /// ```
+    #[must_use]
+    pub fn new() -> RelaxedWeakQueue<T> {
+        RelaxedWeakQueue {
+            nodes: Slab::default(),
+            roots: Vec::new(),
+            top: NONE,
+            len: 0,
+        }
+    }
+
+    /// Creates an empty `RelaxedWeakQueue` with a specific capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::RelaxedWeakQueue;
+    /// let mut queue = RelaxedWeakQueue::with_capacity(10);
+    /// queue.push(4);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> RelaxedWeakQueue<T> {
+        RelaxedWeakQueue {
+            nodes: Slab::with_capacity(capacity),
+            roots: Vec::new(),
+            top: NONE,
+            len: 0,
+        }
+    }
+
+    /// Pushes an item onto the queue and returns a handle to it.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::RelaxedWeakQueue;
+    /// let mut queue = RelaxedWeakQueue::new();
+    /// let handle = queue.push(3);
+    /// queue.push(5);
+    ///
+    /// assert_eq!(queue.get(handle), Some(&3));
+    /// assert_eq!(queue.peek(), Some(&5));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    pub fn push(&mut self, item: T) -> Handle {
+        let handle = self.nodes.insert(Node {
+            item,
+            rank: 0,
+            parent: NONE,
+            right: NONE,
+            left: NONE,
+        });
+        self.roots.push(handle);
+        self.len += 1;
+        if self.top == NONE || self.less(self.top, handle) {
+            self.top = handle;
+        }
+        Handle(handle)
+    }
+
+    /// Removes the greatest item from the queue and returns it, or `None` if
+    /// it is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::RelaxedWeakQueue;
+    /// let mut queue = RelaxedWeakQueue::new();
+    /// queue.push(1);
+    /// queue.push(3);
+    ///
+    /// assert_eq!(queue.pop(), Some(3));
+    /// assert_eq!(queue.pop(), Some(1));
+    /// assert_eq!(queue.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The amortized cost of `pop` on a queue containing *n* elements is
+    /// *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        self.pop_with_handle().map(|(_, item)| item)
+    }
+
+    /// Removes the greatest item from the queue and returns it together with
+    /// its handle, or `None` if the queue is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::RelaxedWeakQueue;
+    /// let mut queue = RelaxedWeakQueue::new();
+    /// let handle = queue.push(7);
+    ///
+    /// assert_eq!(queue.pop_with_handle(), Some((handle, 7)));
+    /// ```
+    pub fn pop_with_handle(&mut self) -> Option<(Handle, T)> {
+        let top = self.top;
+        if top == NONE {
+            return None;
+        }
+        Some((Handle(top), self.remove_root(top)))
+    }
+
+    /// Removes the element of `handle` from the queue and returns it, or
+    /// `None` if the handle does not refer to an element of the queue.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::RelaxedWeakQueue;
+    /// let mut queue = RelaxedWeakQueue::new();
+    /// let handle = queue.push(3);
+    /// queue.push(5);
+    ///
+    /// assert_eq!(queue.remove(handle), Some(3));
+    /// assert_eq!(queue.remove(handle), None);
+    /// assert_eq!(queue.len(), 1);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The amortized cost is *O*(log(*n*)).
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        let node = self.nodes.get(handle.0)?;
+        if node.parent != NONE {
+            self.cut(handle.0);
+        }
+        Some(self.remove_root(handle.0))
+    }
+
+    /// Replaces the element of `handle` with the not smaller `item` and
+    /// returns the old element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handle does not refer to an element of the queue, or if
+    /// `item` is less than the current element.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::RelaxedWeakQueue;
+    /// let mut queue = RelaxedWeakQueue::new();
+    /// let handle = queue.push(3);
+    /// queue.push(5);
+    ///
+    /// assert_eq!(queue.increase_key(handle, 8), 3);
+    /// assert_eq!(queue.peek(), Some(&8));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The amortized cost is *O*(1).
+    pub fn increase_key(&mut self, handle: Handle, item: T) -> T {
+        let node = self.nodes.get_mut(handle.0).expect("invalid handle");
+        assert!(
+            !raw::lt(&item, &node.item),
+            "new element is less than the current one"
+        );
+        let old = mem::replace(&mut node.item, item);
+        if node.parent != NONE {
+            self.cut(handle.0);
+        }
+        if self.less(self.top, handle.0) {
+            self.top = handle.0;
+        }
+        old
+    }
+
+    /// Consumes the queue and returns a vector in sorted (ascending) order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::RelaxedWeakQueue;
+    /// let mut queue = RelaxedWeakQueue::new();
+    /// queue.extend(vec![4, 1, 3, 2]);
+    ///
+    /// assert_eq!(queue.into_sorted_vec(), [1, 2, 3, 4]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut vec = Vec::with_capacity(self.len());
+        while let Some(item) = self.pop() {
+            vec.push(item);
+        }
+        vec.reverse();
+        vec
+    }
+
+    fn less(&self, a: usize, b: usize) -> bool {
+        raw::lt(&self.nodes.at(a).item, &self.nodes.at(b).item)
+    }
+
+    // Makes the non-root `x` with its right subtree a new tree, moving its
+    // left subtree into its place, and lowers the ranks above as needed.
+    fn cut(&mut self, x: usize) {
+        let rank = self.root_rank(x);
+        let node = self.nodes.at_mut(x);
+        let (parent, left) = (node.parent, node.left);
+        node.parent = NONE;
+        node.left = NONE;
+        node.rank = rank;
+        self.roots.push(x);
+
+        let node = self.nodes.at_mut(parent);
+        if node.right == x {
+            node.right = left;
+        } else {
+            node.left = left;
+        }
+        if left != NONE {
+            self.nodes.at_mut(left).parent = parent;
+        }
+
+        let mut cur = parent;
+        loop {
+            let node = self.nodes.at(cur);
+            if node.parent == NONE {
+                self.nodes.at_mut(cur).rank = self.root_rank(cur);
+                break;
+            }
+            // Both children one rank lower, or one child of the same rank.
+            let rank = match (self.rank(node.left), self.rank(node.right)) {
+                (Some(a), Some(b)) if a == b => a + 1,
+                (Some(a), Some(b)) => a.max(b),
+                (Some(a), None) | (None, Some(a)) => a,
+                (None, None) => 0,
+            };
+            if rank >= node.rank {
+                break;
+            }
+            let parent = node.parent;
+            self.nodes.at_mut(cur).rank = rank;
+            cur = parent;
+        }
+    }
+
+    // Removes the root `x`, turning the right spine of its right subtree into
+    // new trees, and links the trees by rank.
+    fn remove_root(&mut self, x: usize) -> T {
+        let pos = self.roots.iter().position(|&root| root == x);
+        self.roots.swap_remove(pos.expect("not a root"));
+        let mut trees = mem::take(&mut self.roots);
+        let node = self.nodes.remove(x);
+        self.len -= 1;
+
+        let mut cur = node.right;
+        while cur != NONE {
+            let rank = self.root_rank(cur);
+            let child = self.nodes.at_mut(cur);
+            let next = child.left;
+            child.parent = NONE;
+            child.left = NONE;
+            child.rank = rank;
+            trees.push(cur);
+            cur = next;
+        }
+
+        // Link every tree with the first one of the same rank not linked yet.
+        let mut unpaired: Vec<usize> = Vec::new();
+        for tree in trees {
+            let rank = self.nodes.at(tree).rank;
+            if unpaired.len() <= rank {
+                unpaired.resize(rank + 1, NONE);
+            }
+            match mem::replace(&mut unpaired[rank], NONE) {
+                NONE => unpaired[rank] = tree,
+                other => {
+                    let root = self.link(other, tree);
+                    self.roots.push(root);
+                }
+            }
+        }
+        self.roots
+            .extend(unpaired.into_iter().filter(|&tree| tree != NONE));
+        self.top = self.roots.iter().copied().fold(NONE, |top, root| {
+            if top == NONE || self.less(top, root) {
+                root
+            } else {
+                top
+            }
+        });
+        node.item
+    }
+
+    // Links two trees of the same rank and returns the root of the result.
+    fn link(&mut self, a: usize, b: usize) -> usize {
+        let (winner, loser) = if self.less(a, b) { (b, a) } else { (a, b) };
+        let node = self.nodes.at_mut(winner);
+        let below = mem::replace(&mut node.right, loser);
+        node.rank += 1;
+        let node = self.nodes.at_mut(loser);
+        node.parent = winner;
+        node.left = below;
+        if below != NONE {
+            self.nodes.at_mut(below).parent = loser;
+        }
+        winner
+    }
+}
+
+impl<T> RelaxedWeakQueue<T> {
+    /// Returns the greatest item in the queue, or `None` if it is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.peek_with_handle().map(|(_, item)| item)
+    }
+
+    /// Returns the greatest item in the queue together with its handle, or
+    /// `None` if the queue is empty.
+    #[must_use]
+    pub fn peek_with_handle(&self) -> Option<(Handle, &T)> {
+        if self.top == NONE {
+            return None;
+        }
+        Some((Handle(self.top), &self.nodes.at(self.top).item))
+    }
+
+    /// Returns the element of `handle`, or `None` if the handle does not
+    /// refer to an element of the queue.
+    #[must_use]
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.nodes.get(handle.0).map(|node| &node.item)
+    }
+
+    /// Checks if `handle` refers to an element of the queue.
+    #[must_use]
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.nodes.get(handle.0).is_some()
+    }
+
+    /// Returns an iterator visiting all handles and values in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (Handle, &T)> + '_ {
+        self.nodes
+            .iter()
+            .map(|(handle, node)| (Handle(handle), &node.item))
+    }
+
+    /// Returns the number of elements in the queue.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Drops all items from the queue, invalidating all handles.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.roots.clear();
+        self.top = NONE;
+        self.len = 0;
+    }
+
+    /// Consumes the queue and returns its elements in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(mut self) -> Vec<T> {
+        let handles: Vec<_> = self.nodes.iter().map(|(handle, _)| handle).collect();
+        handles
+            .into_iter()
+            .map(|handle| self.nodes.remove(handle).item)
+            .collect()
+    }
+
+    // The rank of a tree rooted at `x`, one more than the rank of its only
+    // child.
+    fn root_rank(&self, x: usize) -> usize {
+        self.rank(self.nodes.at(x).right).map_or(0, |rank| rank + 1)
+    }
+
+    fn rank(&self, x: usize) -> Option<usize> {
+        if x == NONE {
+            None
+        } else {
+            Some(self.nodes.at(x).rank)
+        }
+    }
+}
+
+impl<T: Ord> Extend<T> for RelaxedWeakQueue<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
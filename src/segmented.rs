@@ -0,0 +1,437 @@
+//! A weak heap stored in fixed-size segments.
+
+use crate::raw;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FromIterator;
+
+/// Number of elements per segment. A power of two, so that indexing compiles
+/// to a shift and a mask.
+const SEGMENT_LEN: usize = 4096;
+
+struct Segment<T> {
+    data: Vec<T>,
+    bit: Vec<bool>,
+}
+
+impl<T> Segment<T> {
+    fn new() -> Segment<T> {
+        Segment {
+            data: Vec::with_capacity(SEGMENT_LEN),
+            bit: Vec::with_capacity(SEGMENT_LEN),
+        }
+    }
+}
+
+impl<T: Clone> Clone for Segment<T> {
+    fn clone(&self) -> Self {
+        let mut segment = Segment::new();
+        segment.data.extend_from_slice(&self.data);
+        segment.bit.extend_from_slice(&self.bit);
+        segment
+    }
+}
+
+/// A priority queue implemented with a weak heap, whose elements are stored
+/// in fixed-size segments instead of one contiguous vector.
+///
+/// Growing a [`WeakHeap`] occasionally reallocates and copies the whole
+/// underlying vector, which causes long pauses for very large heaps. Here a
+/// new segment is allocated whenever the last one is full and existing
+/// elements are never moved to grow the heap, at the price of a small
+/// indexing overhead on every access. One emptied segment is kept as a spare,
+/// so that pushing and popping around a segment boundary doesn't allocate
+/// and free a segment on every operation.
+///
+/// This is a type of its own rather than a storage option of [`WeakHeap`]:
+/// `WeakHeap` hands out its elements as one slice, for example through
+/// [`as_slice`], [`into_vec`] and [`into_raw_parts`], and its sifting code
+/// works on slices. Segments could only be plugged into it by threading a
+/// storage parameter through all of its API, which the crate has no
+/// mechanism for. Both types build a heap from many elements in *O*(*n*),
+/// through `FromIterator`, `Extend` and `From<Vec<T>>`.
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::SegmentedWeakHeap;
+///
+/// let mut heap = SegmentedWeakHeap::new();
+/// heap.extend(0..10_000);
+///
+/// assert_eq!(heap.len(), 10_000);
+/// assert_eq!(heap.pop(), Some(9_999));
+/// assert_eq!(heap.peek(), Some(&9_998));
+/// ```
+///
+/// [`WeakHeap`]: crate::WeakHeap
+/// [`as_slice`]: crate::WeakHeap::as_slice
+/// [`into_vec`]: crate::WeakHeap::into_vec
+/// [`into_raw_parts`]: crate::WeakHeap::into_raw_parts
+pub struct SegmentedWeakHeap<T> {
+    // The segments holding the elements, possibly followed by one empty spare.
+    segments: Vec<Segment<T>>,
+    len: usize,
+}
+
+impl<T: Clone> Clone for SegmentedWeakHeap<T> {
+    fn clone(&self) -> Self {
+        SegmentedWeakHeap {
+            // The spare segment, if any, is not cloned.
+            segments: self
+                .segments
+                .iter()
+                .filter(|segment| !segment.data.is_empty())
+                .cloned()
+                .collect(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SegmentedWeakHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> Default for SegmentedWeakHeap<T> {
+    /// Creates an empty `SegmentedWeakHeap`.
+    #[inline]
+    fn default() -> SegmentedWeakHeap<T> {
+        SegmentedWeakHeap::new()
+    }
+}
+
+impl<T: Ord> SegmentedWeakHeap<T> {
+    /// Creates an empty `SegmentedWeakHeap` as a max-heap.
+    ///
+    /// No memory is allocated until the first element is pushed.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::SegmentedWeakHeap;
+    /// let mut heap = SegmentedWeakHeap::new();
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn new() -> SegmentedWeakHeap<T> {
+        SegmentedWeakHeap {
+            segments: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Pushes an item onto the heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::SegmentedWeakHeap;
+    /// let mut heap = SegmentedWeakHeap::new();
+    /// heap.push(3);
+    /// heap.push(5);
+    /// heap.push(1);
+    ///
+    /// assert_eq!(heap.len(), 3);
+    /// assert_eq!(heap.peek(), Some(&5));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost of `push` is *O*(1), the worst case is *O*(log(*n*)).
+    /// No element is ever moved to make room for the new one.
+    pub fn push(&mut self, item: T) {
+        self.push_unsifted(item);
+        self.sift_up(self.len - 1);
+    }
+
+    /// Removes the greatest item from the heap and returns it, or `None` if it
+    /// is empty.
+    ///
+    /// An emptied segment is kept as a spare, and freed once the segment
+    /// before it becomes empty too.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::SegmentedWeakHeap;
+    /// let mut heap: SegmentedWeakHeap<_> = vec![1, 3].into_iter().collect();
+    ///
+    /// assert_eq!(heap.pop(), Some(3));
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost of `pop` on a heap containing *n* elements is *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        let index = self.len.checked_sub(1)? / SEGMENT_LEN;
+        let segment = &mut self.segments[index];
+        let mut item = segment.data.pop()?;
+        segment.bit.pop();
+        if segment.data.is_empty() {
+            // Keep this segment as the spare, in place of the previous one.
+            self.segments.truncate(index + 1);
+        }
+        self.len -= 1;
+
+        if self.len > 0 {
+            core::mem::swap(&mut item, &mut self.segments[0].data[0]);
+            self.sift_down();
+        }
+        Some(item)
+    }
+
+    /// Consumes the heap and returns a vector in sorted (ascending) order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::SegmentedWeakHeap;
+    /// let heap: SegmentedWeakHeap<_> = vec![4, 1, 3, 2].into_iter().collect();
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut vec = Vec::with_capacity(self.len);
+        while let Some(item) = self.pop() {
+            vec.push(item);
+        }
+        vec.reverse();
+        vec
+    }
+
+    // Appends an item without restoring the heap property.
+    fn push_unsifted(&mut self, item: T) {
+        let index = self.len / SEGMENT_LEN;
+        if index == self.segments.len() {
+            self.segments.push(Segment::new());
+        }
+        let segment = &mut self.segments[index];
+        segment.data.push(item);
+        segment.bit.push(false);
+        self.len += 1;
+    }
+
+    // Restores the heap property of the elements appended after `start`, by
+    // sifting them up if they are few, or by rebuilding the whole heap with
+    // *n* - 1 joins otherwise.
+    fn rebuild_from(&mut self, start: usize) {
+        let len = self.len;
+        let tail = len.saturating_sub(start);
+        let log_len = (usize::BITS - len.leading_zeros()) as usize;
+        if tail.saturating_mul(log_len) <= len {
+            for pos in start..len {
+                self.sift_up(pos);
+            }
+        } else {
+            for segment in &mut self.segments {
+                segment.bit.fill(false);
+            }
+            for pos in (1..len).rev() {
+                let ancestor = self.d_ancestor(pos);
+                if raw::lt(self.get(ancestor), self.get(pos)) {
+                    self.flip(pos);
+                    self.swap(ancestor, pos);
+                }
+            }
+        }
+    }
+
+    fn get(&self, pos: usize) -> &T {
+        &self.segments[pos / SEGMENT_LEN].data[pos % SEGMENT_LEN]
+    }
+
+    fn bit(&self, pos: usize) -> bool {
+        self.segments[pos / SEGMENT_LEN].bit[pos % SEGMENT_LEN]
+    }
+
+    fn flip(&mut self, pos: usize) {
+        self.segments[pos / SEGMENT_LEN].bit[pos % SEGMENT_LEN] ^= true;
+    }
+
+    // Swaps the elements at `a < b`.
+    fn swap(&mut self, a: usize, b: usize) {
+        let (sa, sb) = (a / SEGMENT_LEN, b / SEGMENT_LEN);
+        if sa == sb {
+            self.segments[sa]
+                .data
+                .swap(a % SEGMENT_LEN, b % SEGMENT_LEN);
+        } else {
+            let (head, tail) = self.segments.split_at_mut(sb);
+            core::mem::swap(
+                &mut head[sa].data[a % SEGMENT_LEN],
+                &mut tail[0].data[b % SEGMENT_LEN],
+            );
+        }
+    }
+
+    fn d_ancestor(&self, pos: usize) -> usize {
+        let mut cur = pos;
+        let mut ancestor = cur / 2;
+        while ancestor > 0 && (cur % 2 == self.bit(ancestor) as usize) {
+            cur /= 2;
+            ancestor /= 2;
+        }
+        ancestor
+    }
+
+    fn sift_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let ancestor = self.d_ancestor(pos);
+            if raw::lt(self.get(ancestor), self.get(pos)) {
+                self.swap(ancestor, pos);
+                pos = ancestor;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn sift_down(&mut self) {
+        let end = self.len;
+        if end <= 1 {
+            return;
+        }
+
+        let mut cur = 1;
+        while cur * 2 + (self.bit(cur) as usize) < end {
+            cur = 2 * cur + (self.bit(cur) as usize);
+        }
+
+        while cur > 0 {
+            if raw::lt(self.get(0), self.get(cur)) {
+                self.flip(cur);
+                self.swap(0, cur);
+            }
+            cur /= 2;
+        }
+    }
+}
+
+impl<T> SegmentedWeakHeap<T> {
+    /// Returns the greatest item in the heap, or `None` if it is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.segments
+            .first()
+            .and_then(|segment| segment.data.first())
+    }
+
+    /// Returns an iterator visiting all values in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.segments.iter().flat_map(|segment| segment.data.iter())
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of elements the heap can hold without allocating a
+    /// new segment.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::SegmentedWeakHeap;
+    /// let mut heap = SegmentedWeakHeap::new();
+    /// assert_eq!(heap.capacity(), 0);
+    ///
+    /// heap.push(1);
+    /// assert!(heap.capacity() >= 1);
+    /// ```
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.segments.len() * SEGMENT_LEN
+    }
+
+    /// Drops all items from the heap and frees all segments.
+    pub fn clear(&mut self) {
+        self.segments.clear();
+        self.len = 0;
+    }
+}
+
+impl<T: Ord> From<Vec<T>> for SegmentedWeakHeap<T> {
+    /// Converts a `Vec<T>` into a `SegmentedWeakHeap<T>`.
+    ///
+    /// The elements are moved into segments, and the heap is built with
+    /// *n* - 1 comparisons, in *O*(*n*) time.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::SegmentedWeakHeap;
+    /// let heap = SegmentedWeakHeap::from(vec![5, 3, 2, 4, 1]);
+    /// assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4, 5]);
+    /// ```
+    fn from(vec: Vec<T>) -> SegmentedWeakHeap<T> {
+        vec.into_iter().collect()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SegmentedWeakHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> SegmentedWeakHeap<T> {
+        let mut heap = SegmentedWeakHeap::new();
+        heap.extend(iter);
+        heap
+    }
+}
+
+impl<T: Ord> Extend<T> for SegmentedWeakHeap<T> {
+    /// Appends the items and restores the heap property once, either by
+    /// sifting up the new items or by rebuilding the heap in *O*(*n*),
+    /// whichever takes fewer comparisons.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let guard = RebuildOnDrop {
+            start: self.len,
+            heap: self,
+        };
+        for item in iter {
+            guard.heap.push_unsifted(item);
+        }
+    }
+}
+
+// Restores the heap property of the elements appended after `start`, even if
+// appending them panics.
+struct RebuildOnDrop<'a, T: Ord> {
+    heap: &'a mut SegmentedWeakHeap<T>,
+    start: usize,
+}
+
+impl<T: Ord> Drop for RebuildOnDrop<'_, T> {
+    fn drop(&mut self) {
+        self.heap.rebuild_from(self.start);
+    }
+}
@@ -0,0 +1,101 @@
+//! Order statistics of borrowed slices.
+//!
+//! Finding the *n*-th smallest element only needs a weak heap of the
+//! min(*n* + 1, *len* - *n*) elements on the closer side of it. When that
+//! heap is small, every other element is compared with its root once and
+//! only few elements cost more, which makes fewer comparisons than a
+//! quickselect. Otherwise, the selection falls back to
+//! [`slice::select_nth_unstable`].
+//!
+//! # Examples
+//!
+//! ```
+//! use weakheap::select::nth;
+//!
+//! let mut data = [7, 2, 9, 4, 1, 8];
+//! let (smaller, second, larger) = nth(&mut data, 1);
+//!
+//! assert_eq!(*second, 2);
+//! assert_eq!(smaller, [1]);
+//! assert!(larger.iter().all(|&x| x >= 2));
+//! ```
+//!
+//! [`slice::select_nth_unstable`]: slice::select_nth_unstable
+
+use crate::bits::BitVec;
+use crate::raw;
+
+/// Reorders `data` such that the element at `n` is the one that would be
+/// there if `data` was sorted, with the elements before it not greater and
+/// the elements after it not less. Returns the elements before `n`, the
+/// element at `n` and the elements after it.
+///
+/// # Panics
+///
+/// Panics if `n >= data.len()`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use weakheap::select::nth;
+///
+/// let mut scores = [31, 7, 95, 42, 68, 15, 54];
+/// let (_, median, _) = nth(&mut scores, 3);
+/// assert_eq!(*median, 42);
+/// ```
+///
+/// # Time complexity
+///
+/// With *m* = min(*n* + 1, *len* - *n*), the heap selection is used while
+/// *m*log(*len*) <= *len*, costing *O*(*len*log(*m*)) in the worst case and
+/// about *len* comparisons for random input. Otherwise the cost is the one of
+/// `select_nth_unstable`, *O*(*len*) on average.
+pub fn nth<T: Ord>(data: &mut [T], n: usize) -> (&mut [T], &mut T, &mut [T]) {
+    let len = data.len();
+    assert!(n < len, "index {n} out of range for slice of length {len}");
+
+    let front = n + 1;
+    let back = len - n;
+    let log_len = (usize::BITS - len.leading_zeros()) as usize;
+    if front.min(back).saturating_mul(log_len) > len {
+        return data.select_nth_unstable(n);
+    }
+
+    if front <= back {
+        // The `n + 1` smallest elements in a max-heap at the front, the
+        // greatest of them is the answer.
+        let (heap, rest) = data.split_at_mut(front);
+        select_heap(heap, rest, &mut raw::lt);
+        data.swap(0, n);
+    } else {
+        // The `len - n` greatest elements in a min-heap at the back, the
+        // smallest of them is the answer and already at `n`.
+        let (rest, heap) = data.split_at_mut(n);
+        select_heap(heap, rest, &mut |a: &T, b: &T| raw::lt(b, a));
+    }
+
+    let (smaller, rest) = data.split_at_mut(n);
+    let (nth, larger) = rest.split_first_mut().expect("n < len");
+    (smaller, nth, larger)
+}
+
+// Arranges `heap` into a weak heap with respect to `is_less`, then swaps in
+// every element of `rest` less than its root, so that `heap` ends up with the
+// `heap.len()` smallest elements of both. `heap` must not be empty.
+fn select_heap<T, F>(heap: &mut [T], rest: &mut [T], is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = heap.len();
+    let mut bits = BitVec::zeros(len);
+    raw::rebuild(heap, &mut bits, is_less);
+    for item in rest {
+        if is_less(item, &heap[0]) {
+            core::mem::swap(item, &mut heap[0]);
+            // SAFETY: 0 < len == heap.len() == bits.len().
+            unsafe { raw::sift_down_range(heap, &mut bits, 0, len, is_less) };
+        }
+    }
+}
@@ -0,0 +1,24 @@
+//! `serde` support.
+
+use crate::WeakHeap;
+use alloc::vec::Vec;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+/// Serializes the elements as a sequence, in arbitrary order.
+impl<T: Serialize> Serialize for WeakHeap<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.data.iter())
+    }
+}
+
+/// Deserializes a sequence of elements and rebuilds the heap from it, with
+/// *n* - 1 comparisons.
+///
+/// The reverse bits are never read from the input, so a hostile input cannot
+/// produce a structurally broken heap.
+impl<'de, T: Ord + Deserialize<'de>> Deserialize<'de> for WeakHeap<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(WeakHeap::from)
+    }
+}
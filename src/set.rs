@@ -0,0 +1,325 @@
+//! A priority queue that rejects duplicate elements.
+
+use crate::{Iter, WeakHeap};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+use core::iter::FromIterator;
+use std::collections::HashSet;
+
+/// A priority queue implemented with a weak heap, which stores every element
+/// at most once.
+///
+/// A hash index is kept alongside the heap, so pushing an element that is
+/// already in the queue is detected in *O*(1) and leaves the queue unchanged.
+/// This is useful for frontiers of search algorithms, where re-inserting known
+/// nodes wastes work.
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::WeakHeapSet;
+///
+/// let mut set = WeakHeapSet::new();
+/// assert!(set.push(3));
+/// assert!(set.push(5));
+/// assert!(!set.push(3));
+///
+/// assert_eq!(set.len(), 2);
+/// assert_eq!(set.pop(), Some(5));
+/// assert_eq!(set.pop(), Some(3));
+/// assert_eq!(set.pop(), None);
+///
+/// // Once popped, an element can be pushed again.
+/// assert!(set.push(3));
+/// ```
+pub struct WeakHeapSet<T> {
+    heap: WeakHeap<T>,
+    index: HashSet<T>,
+}
+
+impl<T: Clone> Clone for WeakHeapSet<T> {
+    fn clone(&self) -> Self {
+        WeakHeapSet {
+            heap: self.heap.clone(),
+            index: self.index.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for WeakHeapSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WeakHeapSet").field(&self.heap).finish()
+    }
+}
+
+impl<T: Ord + Hash + Clone> Default for WeakHeapSet<T> {
+    /// Creates an empty `WeakHeapSet`.
+    #[inline]
+    fn default() -> WeakHeapSet<T> {
+        WeakHeapSet::new()
+    }
+}
+
+impl<T: Ord + Hash + Clone> WeakHeapSet<T> {
+    /// Creates an empty `WeakHeapSet` as a max-heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapSet;
+    /// let mut set = WeakHeapSet::new();
+    /// assert!(set.is_empty());
+    ///
+    /// set.push(4);
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    #[must_use]
+    pub fn new() -> WeakHeapSet<T> {
+        WeakHeapSet {
+            heap: WeakHeap::new(),
+            index: HashSet::new(),
+        }
+    }
+
+    /// Creates an empty `WeakHeapSet` with a specific capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapSet;
+    /// let mut set = WeakHeapSet::with_capacity(10);
+    /// set.push(4);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> WeakHeapSet<T> {
+        WeakHeapSet {
+            heap: WeakHeap::with_capacity(capacity),
+            index: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// Pushes an item onto the set, unless an equal item is already there.
+    ///
+    /// Returns whether the item was inserted.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapSet;
+    /// let mut set = WeakHeapSet::new();
+    /// assert!(set.push(3));
+    /// assert!(!set.push(3));
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Rejecting a duplicate is *O*(1), otherwise the cost is that of [`WeakHeap::push`].
+    pub fn push(&mut self, item: T) -> bool {
+        if self.index.contains(&item) {
+            return false;
+        }
+        self.index.insert(item.clone());
+        self.heap.push(item);
+        true
+    }
+
+    /// Pushes an item onto the set, replacing an equal item if there is one.
+    ///
+    /// Returns the replaced item. This is useful when equal items can still
+    /// be told apart, for example by data ignored by their `Eq` and `Ord`
+    /// implementations.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapSet;
+    /// let mut set = WeakHeapSet::new();
+    /// assert_eq!(set.replace(3), None);
+    /// assert_eq!(set.replace(3), Some(3));
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Replacing an item has to find its copy in the heap, which is *O*(*n*).
+    /// Otherwise the cost is that of [`WeakHeap::push`].
+    pub fn replace(&mut self, item: T) -> Option<T> {
+        if self.index.replace(item.clone()).is_none() {
+            self.heap.push(item);
+            return None;
+        }
+        // SAFETY: the item is replaced by an equal one, which doesn't break
+        //  the heap property.
+        let slot = unsafe { self.heap.as_mut_slice_unchecked() }
+            .iter_mut()
+            .find(|slot| **slot == item)
+            .expect("every indexed item is in the heap");
+        Some(core::mem::replace(slot, item))
+    }
+
+    /// Removes the greatest item from the set and returns it, or `None` if it
+    /// is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapSet;
+    /// let mut set: WeakHeapSet<_> = vec![1, 3, 3].into_iter().collect();
+    ///
+    /// assert_eq!(set.pop(), Some(3));
+    /// assert_eq!(set.pop(), Some(1));
+    /// assert_eq!(set.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost of `pop` on a set containing *n* elements is *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        let item = self.heap.pop()?;
+        self.index.remove(&item);
+        Some(item)
+    }
+
+    /// Returns `true` if the set contains an item equal to `value`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapSet;
+    /// let mut set = WeakHeapSet::new();
+    /// set.push(String::from("a"));
+    ///
+    /// assert!(set.contains("a"));
+    /// assert!(!set.contains("b"));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1).
+    #[must_use]
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.contains(value)
+    }
+
+    /// Consumes the `WeakHeapSet` and returns a vector in sorted
+    /// (ascending) order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapSet;
+    /// let set: WeakHeapSet<_> = vec![4, 1, 4, 3].into_iter().collect();
+    /// assert_eq!(set.into_sorted_vec(), [1, 3, 4]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.heap.into_sorted_vec()
+    }
+}
+
+impl<T> WeakHeapSet<T> {
+    /// Returns the greatest item in the set, or `None` if it is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapSet;
+    /// let mut set = WeakHeapSet::new();
+    /// assert_eq!(set.peek(), None);
+    ///
+    /// set.push(1);
+    /// set.push(5);
+    /// assert_eq!(set.peek(), Some(&5));
+    /// ```
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.peek()
+    }
+
+    /// Returns an iterator visiting all values in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.heap.iter()
+    }
+
+    /// Returns the number of elements in the set.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the set is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Drops all items from the set.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.index.clear();
+    }
+
+    /// Consumes the `WeakHeapSet` and returns the underlying vector
+    /// in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        self.heap.into_vec()
+    }
+}
+
+impl<T: Ord + Hash + Clone> FromIterator<T> for WeakHeapSet<T> {
+    /// Builds a `WeakHeapSet` from an iterator, skipping duplicates.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> WeakHeapSet<T> {
+        let mut index = HashSet::new();
+        let data: Vec<T> = iter
+            .into_iter()
+            .filter(|item| index.insert(item.clone()))
+            .collect();
+        WeakHeapSet {
+            heap: WeakHeap::from(data),
+            index,
+        }
+    }
+}
+
+impl<T: Ord + Hash + Clone> Extend<T> for WeakHeapSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a WeakHeapSet<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
@@ -0,0 +1,121 @@
+//! Weak heap primitives operating on borrowed slices.
+//!
+//! A weak heap is represented by a slice of elements and a slice of reverse bits
+//! of the same length. These functions let you maintain such a pair yourself,
+//! for example when building a custom container on top of the weak heap logic.
+//!
+//! # Examples
+//!
+//! ```
+//! use weakheap::slice::{heapify, sift_down, sift_up};
+//!
+//! let mut data = [3, 1, 4, 1, 5, 9, 2, 6];
+//! let mut bits = [false; 8];
+//!
+//! heapify(&mut data, &mut bits);
+//! assert_eq!(data[0], 9);
+//!
+//! // Decrease the greatest element and repair the heap.
+//! data[0] = 0;
+//! sift_down(&mut data, &mut bits, 0);
+//! assert_eq!(data[0], 6);
+//!
+//! // Increase an arbitrary element and repair the heap.
+//! data[7] = 10;
+//! assert_eq!(sift_up(&mut data, &mut bits, 7), 0);
+//! assert_eq!(data[0], 10);
+//! ```
+
+use crate::raw;
+use alloc::vec;
+
+/// Rearranges `data` into a weak heap (max-heap), resetting `bits`.
+///
+/// # Panics
+///
+/// Panics if `data` and `bits` have different lengths.
+///
+/// # Time complexity
+///
+/// Building a weak heap takes exactly *n* - 1 comparisons.
+pub fn heapify<T: Ord>(data: &mut [T], bits: &mut [bool]) {
+    assert_eq!(data.len(), bits.len(), "data and bits lengths differ");
+    raw::rebuild(data, bits, &mut raw::lt);
+}
+
+/// Moves the element at `pos` down, restoring the heap property after the
+/// element has been decreased.
+///
+/// # Panics
+///
+/// Panics if `data` and `bits` have different lengths or if `pos` is out of bounds.
+///
+/// # Time complexity
+///
+/// The worst case cost is *O*(log(*n*)).
+pub fn sift_down<T: Ord>(data: &mut [T], bits: &mut [bool], pos: usize) {
+    assert_eq!(data.len(), bits.len(), "data and bits lengths differ");
+    assert!(pos < data.len(), "position out of bounds");
+    // SAFETY: pos < data.len() and the lengths are equal.
+    unsafe { raw::sift_down_range(data, bits, pos, data.len(), &mut raw::lt) };
+}
+
+/// Moves the element at `pos` up, restoring the heap property after the
+/// element has been increased. Returns the new position of the element.
+///
+/// # Panics
+///
+/// Panics if `data` and `bits` have different lengths or if `pos` is out of bounds.
+///
+/// # Time complexity
+///
+/// The worst case cost is *O*(log(*n*)).
+pub fn sift_up<T: Ord>(data: &mut [T], bits: &mut [bool], pos: usize) -> usize {
+    assert_eq!(data.len(), bits.len(), "data and bits lengths differ");
+    assert!(pos < data.len(), "position out of bounds");
+    // SAFETY: pos < data.len() and the lengths are equal.
+    unsafe { raw::sift_up(data, bits, pos, &mut raw::lt) }
+}
+
+/// Moves the `k` greatest elements of `data` to its front, in arbitrary order,
+/// and returns them. If `k >= data.len()`, the whole slice is returned.
+///
+/// The rest of `data` is left in arbitrary order.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use weakheap::slice::k_largest_unordered;
+///
+/// let mut data = [3, 8, 1, 9, 4, 7];
+/// let best = k_largest_unordered(&mut data, 3);
+/// best.sort();
+/// assert_eq!(best, [7, 8, 9]);
+/// ```
+///
+/// # Time complexity
+///
+/// The `k` front elements are arranged into a weak min-heap (*k* - 1 comparisons),
+/// every other element is compared with its root once, and only the elements
+/// entering the heap cost *O*(log(*k*)) more. Unlike a sorted top-*k*, the
+/// result is never sorted.
+pub fn k_largest_unordered<T: Ord>(data: &mut [T], k: usize) -> &mut [T] {
+    let k = k.min(data.len());
+    if k == 0 {
+        return &mut data[..0];
+    }
+
+    let mut bits = vec![false; k];
+    let is_less = &mut |a: &T, b: &T| raw::lt(b, a);
+    raw::rebuild(&mut data[..k], &mut bits, is_less);
+    for pos in k..data.len() {
+        if raw::lt(&data[0], &data[pos]) {
+            data.swap(0, pos);
+            // SAFETY: 0 < k <= data.len() and bits.len() == k.
+            unsafe { raw::sift_down_range(&mut data[..k], &mut bits, 0, k, is_less) };
+        }
+    }
+    &mut data[..k]
+}
@@ -0,0 +1,167 @@
+//! In-place weak-heapsort of borrowed slices.
+//!
+//! Weak-heapsort makes at most *n* log2(*n*) + 0.1*n* comparisons, close to the
+//! information-theoretic lower bound, so it pays off when comparisons are
+//! expensive. It sorts in place, but allocates *n* bits for the reverse bits.
+//! Like other heapsorts, it is not stable.
+//!
+//! # Examples
+//!
+//! ```
+//! use weakheap::sort::{weak_heapsort, weak_heapsort_by_key};
+//!
+//! let mut data = [5, 2, 9, 1, 7];
+//! weak_heapsort(&mut data);
+//! assert_eq!(data, [1, 2, 5, 7, 9]);
+//!
+//! let mut words = ["banana", "Cherry", "apple"];
+//! weak_heapsort_by_key(&mut words, |s| s.to_lowercase());
+//! assert_eq!(words, ["apple", "banana", "Cherry"]);
+//! ```
+
+use crate::bits::BitVec;
+use crate::raw;
+use core::cmp::Ordering;
+
+/// Sorts `data` in ascending order.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use weakheap::sort::weak_heapsort;
+///
+/// let mut v = vec![-5, 4, 1, -3, 2];
+/// weak_heapsort(&mut v[1..]);
+/// assert_eq!(v, [-5, -3, 1, 2, 4]);
+/// ```
+///
+/// # Time complexity
+///
+/// *O*(*n* * log(*n*)) in the worst case, with at most *n* log2(*n*) + 0.1*n* comparisons.
+pub fn weak_heapsort<T: Ord>(data: &mut [T]) {
+    sort_front(data, data.len(), &mut raw::lt);
+}
+
+/// Sorts `data` in ascending order with respect to the comparator `cmp`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use weakheap::sort::weak_heapsort_by;
+///
+/// let mut v = [5, 4, 1, 3, 2];
+/// weak_heapsort_by(&mut v, |a, b| b.cmp(a));
+/// assert_eq!(v, [5, 4, 3, 2, 1]);
+/// ```
+pub fn weak_heapsort_by<T, F>(data: &mut [T], mut cmp: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    sort_front(data, data.len(), &mut |a, b| cmp(a, b) == Ordering::Less);
+}
+
+/// Sorts `data` in ascending order of the keys extracted with `key`.
+///
+/// The key is extracted on every comparison, see [`WeakHeapByKey`] for a
+/// heap caching the keys.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use weakheap::sort::weak_heapsort_by_key;
+///
+/// let mut v = [-5i32, 4, 1, -3, 2];
+/// weak_heapsort_by_key(&mut v, |x| x.abs());
+/// assert_eq!(v, [1, 2, -3, 4, -5]);
+/// ```
+///
+/// [`WeakHeapByKey`]: crate::WeakHeapByKey
+pub fn weak_heapsort_by_key<T, K, F>(data: &mut [T], mut key: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    sort_front(data, data.len(), &mut |a, b| raw::lt(&key(a), &key(b)));
+}
+
+/// Moves the `k` smallest elements of `data` to its front in ascending order,
+/// or sorts the whole slice if `k >= data.len()`. The rest of `data` is left
+/// in arbitrary order.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use weakheap::sort::partial_sort;
+///
+/// let mut v = [8, 3, 9, 1, 7, 2];
+/// partial_sort(&mut v, 3);
+/// assert_eq!(v[..3], [1, 2, 3]);
+/// ```
+///
+/// # Time complexity
+///
+/// The `k` front elements are arranged into a weak heap, every other element
+/// is compared with its root once and only the elements entering the heap
+/// cost *O*(log(*k*)) more, then the front is sorted with at most
+/// *k* log2(*k*) + 0.1*k* comparisons. Unlike `select_nth_unstable` followed
+/// by a sort, the worst case is *O*(*n*log(*k*)).
+pub fn partial_sort<T: Ord>(data: &mut [T], k: usize) {
+    sort_front(data, k, &mut raw::lt);
+}
+
+/// Moves the `k` smallest elements of `data` with respect to the comparator
+/// `cmp` to its front in ascending order, or sorts the whole slice if
+/// `k >= data.len()`. The rest of `data` is left in arbitrary order.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use weakheap::sort::partial_sort_by;
+///
+/// // The 2 largest, in descending order.
+/// let mut v = [8, 3, 9, 1, 7, 2];
+/// partial_sort_by(&mut v, 2, |a, b| b.cmp(a));
+/// assert_eq!(v[..2], [9, 8]);
+/// ```
+pub fn partial_sort_by<T, F>(data: &mut [T], k: usize, mut cmp: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    sort_front(data, k, &mut |a, b| cmp(a, b) == Ordering::Less);
+}
+
+// Moves the `k` smallest elements to the front of `data`, sorted.
+fn sort_front<T, F>(data: &mut [T], k: usize, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let k = k.min(data.len());
+    if k == 0 {
+        return;
+    }
+
+    let mut bits = BitVec::zeros(k);
+    raw::rebuild(&mut data[..k], &mut bits, is_less);
+    for pos in k..data.len() {
+        if is_less(&data[pos], &data[0]) {
+            data.swap(0, pos);
+            // SAFETY: 0 < k == bits.len().
+            unsafe { raw::sift_down_range(&mut data[..k], &mut bits, 0, k, is_less) };
+        }
+    }
+    for end in (1..k).rev() {
+        data.swap(0, end);
+        // SAFETY: 0 < end < k == bits.len().
+        unsafe { raw::sift_down_range(&mut data[..k], &mut bits, 0, end, is_less) };
+    }
+}
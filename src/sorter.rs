@@ -0,0 +1,139 @@
+//! Lazy, incremental sorting.
+
+use crate::raw;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A buffer which is sorted lazily, on demand.
+///
+/// The elements are arranged into a weak min-heap on construction, and every
+/// call to [`get_sorted`] performs only the extractions needed since the previous
+/// call. The sorted part is memoized, so paging through the first results of a
+/// large collection costs only *O*(*n* + *k*log(*n*)) for the *k* elements viewed.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::IncrementalSorter;
+///
+/// let mut sorter = IncrementalSorter::new(vec![5, 1, 4, 2, 3]);
+/// assert_eq!(sorter.get_sorted(0), Some(&1));
+/// assert_eq!(sorter.get_sorted(2), Some(&3));
+/// assert_eq!(sorter.sorted_len(), 3);
+///
+/// // Already sorted elements are not extracted again.
+/// assert_eq!(sorter.get_sorted(1), Some(&2));
+/// assert_eq!(sorter.get_sorted(5), None);
+///
+/// assert_eq!(sorter.into_sorted_vec(), vec![1, 2, 3, 4, 5]);
+/// ```
+///
+/// [`get_sorted`]: IncrementalSorter::get_sorted
+pub struct IncrementalSorter<T> {
+    // The heap occupies `data[..len - sorted]`, the `sorted` smallest
+    // elements follow it in descending order.
+    data: Vec<T>,
+    bit: Vec<bool>,
+    sorted: usize,
+}
+
+impl<T: Clone> Clone for IncrementalSorter<T> {
+    fn clone(&self) -> Self {
+        IncrementalSorter {
+            data: self.data.clone(),
+            bit: self.bit.clone(),
+            sorted: self.sorted,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for IncrementalSorter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncrementalSorter")
+            .field("data", &self.data)
+            .field("sorted", &self.sorted)
+            .finish()
+    }
+}
+
+impl<T: Ord> IncrementalSorter<T> {
+    /// Creates a sorter over the elements of `vec`.
+    ///
+    /// # Time complexity
+    ///
+    /// Building the underlying heap takes *n* - 1 comparisons.
+    #[must_use]
+    pub fn new(vec: Vec<T>) -> IncrementalSorter<T> {
+        let mut sorter = IncrementalSorter {
+            bit: vec![false; vec.len()],
+            data: vec,
+            sorted: 0,
+        };
+        raw::rebuild(&mut sorter.data, &mut sorter.bit, &mut |a: &T, b: &T| {
+            raw::lt(b, a)
+        });
+        sorter
+    }
+
+    /// Returns the `index`-th smallest element (counting from zero), or `None`
+    /// if `index` is out of bounds.
+    ///
+    /// # Time complexity
+    ///
+    /// Each element not sorted yet up to `index` costs *O*(log(*n*)), already
+    /// sorted elements are returned in *O*(1).
+    pub fn get_sorted(&mut self, index: usize) -> Option<&T> {
+        if index >= self.data.len() {
+            return None;
+        }
+        self.sort_until(index + 1);
+        Some(&self.data[self.data.len() - 1 - index])
+    }
+
+    /// Consumes the sorter and returns all elements in sorted (ascending) order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        self.sort_until(self.data.len());
+        self.data.reverse();
+        self.data
+    }
+
+    // Extracts elements until at least `count` of them are sorted.
+    fn sort_until(&mut self, count: usize) {
+        let len = self.data.len();
+        while self.sorted < count.min(len) {
+            let end = len - self.sorted - 1;
+            self.data.swap(0, end);
+            if end > 0 {
+                // SAFETY: 0 < end < self.data.len().
+                unsafe {
+                    raw::sift_down_range(&mut self.data, &mut self.bit, 0, end, &mut |a, b| {
+                        raw::lt(b, a)
+                    })
+                };
+            }
+            self.sorted += 1;
+        }
+    }
+}
+
+impl<T> IncrementalSorter<T> {
+    /// Returns the number of elements sorted so far.
+    #[must_use]
+    pub fn sorted_len(&self) -> usize {
+        self.sorted
+    }
+
+    /// Returns the number of elements in the sorter.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Checks if the sorter is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
@@ -0,0 +1,290 @@
+//! A priority queue popping equal elements in insertion order.
+
+use crate::WeakHeap;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::iter::FromIterator;
+
+// An element together with its insertion number. Among equal elements, the
+// one inserted first is the greatest.
+#[derive(Clone)]
+struct Entry<T> {
+    item: T,
+    seq: u64,
+}
+
+impl<T: Ord> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ord> Eq for Entry<T> {}
+
+impl<T: Ord> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.item
+            .cmp(&other.item)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// A priority queue implemented with a weak heap, which pops equal elements
+/// in the order they were pushed (first in, first out).
+///
+/// Every element is stored with a sequence number, so a comparison of equal
+/// elements is decided by the sequence numbers without comparing the
+/// elements again.
+///
+/// This will be a max-heap.
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Ordering;
+/// use weakheap::StableWeakHeap;
+///
+/// // Tasks are ordered by their priority only.
+/// #[derive(Debug)]
+/// struct Task(u8, &'static str);
+///
+/// impl PartialEq for Task {
+///     fn eq(&self, other: &Self) -> bool {
+///         self.0 == other.0
+///     }
+/// }
+/// impl Eq for Task {}
+/// impl PartialOrd for Task {
+///     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+///         Some(self.cmp(other))
+///     }
+/// }
+/// impl Ord for Task {
+///     fn cmp(&self, other: &Self) -> Ordering {
+///         self.0.cmp(&other.0)
+///     }
+/// }
+///
+/// let mut heap = StableWeakHeap::new();
+/// heap.push(Task(1, "first"));
+/// heap.push(Task(2, "urgent"));
+/// heap.push(Task(1, "second"));
+/// heap.push(Task(1, "third"));
+///
+/// let names: Vec<_> = std::iter::from_fn(|| heap.pop()).map(|task| task.1).collect();
+/// assert_eq!(names, ["urgent", "first", "second", "third"]);
+/// ```
+pub struct StableWeakHeap<T> {
+    heap: WeakHeap<Entry<T>>,
+    // The sequence number of the next pushed element.
+    next_seq: u64,
+}
+
+impl<T: Clone> Clone for StableWeakHeap<T> {
+    fn clone(&self) -> Self {
+        StableWeakHeap {
+            heap: self.heap.clone(),
+            next_seq: self.next_seq,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for StableWeakHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> Default for StableWeakHeap<T> {
+    /// Creates an empty `StableWeakHeap`.
+    #[inline]
+    fn default() -> StableWeakHeap<T> {
+        StableWeakHeap::new()
+    }
+}
+
+impl<T: Ord> StableWeakHeap<T> {
+    /// Creates an empty `StableWeakHeap` as a max-heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::StableWeakHeap;
+    /// let mut heap = StableWeakHeap::new();
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn new() -> StableWeakHeap<T> {
+        StableWeakHeap {
+            heap: WeakHeap::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Creates an empty `StableWeakHeap` with a specific capacity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::StableWeakHeap;
+    /// let mut heap = StableWeakHeap::with_capacity(10);
+    /// heap.push(4);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> StableWeakHeap<T> {
+        StableWeakHeap {
+            heap: WeakHeap::with_capacity(capacity),
+            next_seq: 0,
+        }
+    }
+
+    /// Pushes an item onto the heap. It is popped after the equal items
+    /// pushed before it.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::StableWeakHeap;
+    /// let mut heap = StableWeakHeap::new();
+    /// heap.push(3);
+    /// heap.push(5);
+    /// heap.push(1);
+    ///
+    /// assert_eq!(heap.len(), 3);
+    /// assert_eq!(heap.peek(), Some(&5));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost of `push` is *O*(1), the worst case is *O*(log(*n*)).
+    pub fn push(&mut self, item: T) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.heap.push(Entry { item, seq });
+    }
+
+    /// Removes the greatest item from the heap and returns it, or `None` if it
+    /// is empty. Among equal items, the one pushed first is removed.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::StableWeakHeap;
+    /// let mut heap: StableWeakHeap<_> = vec![1, 3].into_iter().collect();
+    ///
+    /// assert_eq!(heap.pop(), Some(3));
+    /// assert_eq!(heap.pop(), Some(1));
+    /// assert_eq!(heap.pop(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost of `pop` on a heap containing *n* elements is *O*(log(*n*)).
+    pub fn pop(&mut self) -> Option<T> {
+        let Entry { item, .. } = self.heap.pop()?;
+        if self.heap.is_empty() {
+            self.next_seq = 0;
+        }
+        Some(item)
+    }
+
+    /// Consumes the heap and returns a vector in sorted (ascending) order.
+    /// Equal items appear in the reverse of their insertion order, so the
+    /// vector is the reverse of the popping order.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::StableWeakHeap;
+    /// let heap: StableWeakHeap<_> = vec![4, 1, 3, 2].into_iter().collect();
+    ///
+    /// assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4]);
+    /// ```
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let entries = self.heap.into_sorted_vec();
+        entries.into_iter().map(|entry| entry.item).collect()
+    }
+}
+
+impl<T> StableWeakHeap<T> {
+    /// Returns the greatest item in the heap, the one pushed first among equal
+    /// items, or `None` if it is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.peek().map(|entry| &entry.item)
+    }
+
+    /// Returns an iterator visiting all values in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.heap.iter().map(|entry| &entry.item)
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Drops all items from the heap.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.next_seq = 0;
+    }
+
+    /// Consumes the heap and returns its elements in arbitrary order.
+    #[must_use = "`self` will be dropped if the result is not used"]
+    pub fn into_vec(self) -> Vec<T> {
+        let entries = self.heap.into_vec();
+        entries.into_iter().map(|entry| entry.item).collect()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for StableWeakHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> StableWeakHeap<T> {
+        let entries: Vec<_> = iter
+            .into_iter()
+            .zip(0..)
+            .map(|(item, seq)| Entry { item, seq })
+            .collect();
+        StableWeakHeap {
+            next_seq: entries.len() as u64,
+            heap: WeakHeap::from(entries),
+        }
+    }
+}
+
+impl<T: Ord> Extend<T> for StableWeakHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
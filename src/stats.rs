@@ -0,0 +1,89 @@
+//! Counters of the work done by the sifting routines.
+
+use std::cell::Cell;
+
+thread_local! {
+    static STATS: Cell<Stats> = const { Cell::new(Stats::new()) };
+}
+
+/// Counters of the work done by the sifting code of every container in this
+/// crate, collected on the current thread since the last [`reset_stats`].
+///
+/// Available with the `stats` feature. The counters are thread-local, so the
+/// work done by other threads doesn't show up in [`stats`]. To profile a
+/// single operation, reset the counters right before it.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::{reset_stats, stats, WeakHeap};
+///
+/// let mut heap = WeakHeap::from(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+///
+/// reset_stats();
+/// heap.pop();
+/// let pop = stats();
+/// assert_eq!(pop.sift_downs, 1);
+/// assert!(pop.sift_down_depth <= 3);
+/// assert!(pop.swaps <= pop.sift_down_depth);
+/// assert_eq!(pop.bit_flips, pop.swaps);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Stats {
+    /// Number of times an element was sifted up.
+    pub sift_ups: u64,
+    /// Number of levels the sifted up elements climbed in total.
+    pub sift_up_moves: u64,
+    /// Number of times an element was sifted down.
+    pub sift_downs: u64,
+    /// Number of levels visited on the way back from the bottom of the heap,
+    /// summed over all sift-downs. Each level costs one comparison.
+    pub sift_down_depth: u64,
+    /// Number of joins of a subheap with its distinguished ancestor, made
+    /// while building a heap from scratch. Each join costs one comparison.
+    pub joins: u64,
+    /// Number of elements swapped by sift-downs and joins.
+    pub swaps: u64,
+    /// Number of reverse bits flipped.
+    pub bit_flips: u64,
+}
+
+impl Stats {
+    const fn new() -> Stats {
+        Stats {
+            sift_ups: 0,
+            sift_up_moves: 0,
+            sift_downs: 0,
+            sift_down_depth: 0,
+            joins: 0,
+            swaps: 0,
+            bit_flips: 0,
+        }
+    }
+}
+
+/// Returns the counters collected on the current thread since the last call
+/// to [`reset_stats`].
+///
+/// Available with the `stats` feature. See [`Stats`] for an example.
+#[must_use]
+pub fn stats() -> Stats {
+    STATS.with(Cell::get)
+}
+
+/// Sets all the counters of the current thread to zero.
+///
+/// Available with the `stats` feature.
+pub fn reset_stats() {
+    STATS.with(|stats| stats.set(Stats::new()));
+}
+
+#[inline]
+pub(crate) fn record(update: impl FnOnce(&mut Stats)) {
+    STATS.with(|cell| {
+        let mut stats = cell.get();
+        update(&mut stats);
+        cell.set(stats);
+    });
+}
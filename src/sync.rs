@@ -0,0 +1,309 @@
+//! Multi-producer, single-consumer priority channels.
+//!
+//! A channel created by [`priority_channel`] or [`bounded_priority_channel`]
+//! buffers the sent values in a weak heap, so the [`Receiver`] always gets the
+//! greatest value sent so far, instead of the oldest one.
+//!
+//! The error types are the ones of [`std::sync::mpsc`], with the same
+//! meaning: sending fails once the receiver is dropped, and receiving fails
+//! once every sender is dropped and the buffer is empty.
+//!
+//! Available with the `std` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::thread;
+//! use weakheap::sync::priority_channel;
+//!
+//! let (tx, rx) = priority_channel();
+//! let handles: Vec<_> = (0..3)
+//!     .map(|id| {
+//!         let tx = tx.clone();
+//!         thread::spawn(move || tx.send(id).unwrap())
+//!     })
+//!     .collect();
+//! drop(tx);
+//! for handle in handles {
+//!     handle.join().unwrap();
+//! }
+//!
+//! assert_eq!(rx.iter().collect::<Vec<_>>(), [2, 1, 0]);
+//! ```
+
+use crate::{ignore_poison, WeakHeap};
+use core::fmt;
+use std::sync::mpsc::{RecvError, SendError, TryRecvError, TrySendError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+struct State<T> {
+    heap: WeakHeap<T>,
+    senders: usize,
+    receiver: bool,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    capacity: Option<usize>,
+    // Signaled when a value is sent or the last sender is dropped.
+    not_empty: Condvar,
+    // Signaled when a value is received or the receiver is dropped.
+    not_full: Condvar,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        ignore_poison(self.state.lock())
+    }
+
+    fn is_full(&self, state: &State<T>) -> bool {
+        self.capacity
+            .is_some_and(|capacity| state.heap.len() >= capacity)
+    }
+}
+
+/// Creates a priority channel with an unbounded buffer, so that sending never
+/// blocks.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::sync::priority_channel;
+///
+/// let (tx, rx) = priority_channel();
+/// tx.send(1).unwrap();
+/// tx.send(5).unwrap();
+/// tx.send(3).unwrap();
+///
+/// assert_eq!(rx.recv(), Ok(5));
+/// assert_eq!(rx.recv(), Ok(3));
+/// assert_eq!(rx.try_recv(), Ok(1));
+/// ```
+#[must_use]
+pub fn priority_channel<T: Ord>() -> (Sender<T>, Receiver<T>) {
+    channel(None)
+}
+
+/// Creates a priority channel whose buffer holds up to `capacity` values.
+/// Sending blocks while the buffer is full.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::mpsc::TrySendError;
+/// use weakheap::sync::bounded_priority_channel;
+///
+/// let (tx, rx) = bounded_priority_channel(2);
+/// tx.send(1).unwrap();
+/// tx.send(4).unwrap();
+/// assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+///
+/// assert_eq!(rx.recv(), Ok(4));
+/// tx.try_send(3).unwrap();
+/// assert_eq!(rx.recv(), Ok(3));
+/// ```
+#[must_use]
+pub fn bounded_priority_channel<T: Ord>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(
+        capacity > 0,
+        "the capacity of a priority channel must not be zero"
+    );
+    channel(Some(capacity))
+}
+
+fn channel<T: Ord>(capacity: Option<usize>) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            heap: WeakHeap::with_capacity(capacity.unwrap_or(0)),
+            senders: 1,
+            receiver: true,
+        }),
+        capacity,
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+    let sender = Sender {
+        shared: Arc::clone(&shared),
+    };
+    (sender, Receiver { shared })
+}
+
+/// The sending half of a priority channel. It can be cloned to send from
+/// several threads.
+///
+/// This `struct` is created by [`priority_channel`] or
+/// [`bounded_priority_channel`]. See their documentation for more.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Sender {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            self.shared.not_empty.notify_all();
+        }
+    }
+}
+
+impl<T: Ord> Sender<T> {
+    /// Sends a value, waiting while the buffer of a bounded channel is full.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back if the receiver has been dropped.
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost is *O*(1), the worst case is *O*(log(*n*)) for *n*
+    /// buffered values, not counting the wait.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut state = self.shared.lock();
+        while state.receiver && self.shared.is_full(&state) {
+            state = ignore_poison(self.shared.not_full.wait(state));
+        }
+        if !state.receiver {
+            return Err(SendError(value));
+        }
+        state.heap.push(value);
+        drop(state);
+        self.shared.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// Sends a value if it can be done without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back, wrapped in [`TrySendError::Full`] if the buffer
+    /// of a bounded channel is full, or in [`TrySendError::Disconnected`] if
+    /// the receiver has been dropped.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let mut state = self.shared.lock();
+        if !state.receiver {
+            return Err(TrySendError::Disconnected(value));
+        }
+        if self.shared.is_full(&state) {
+            return Err(TrySendError::Full(value));
+        }
+        state.heap.push(value);
+        drop(state);
+        self.shared.not_empty.notify_one();
+        Ok(())
+    }
+}
+
+/// The receiving half of a priority channel, which receives the greatest
+/// buffered value first.
+///
+/// This `struct` is created by [`priority_channel`] or
+/// [`bounded_priority_channel`]. See their documentation for more.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.lock().receiver = false;
+        self.shared.not_full.notify_all();
+    }
+}
+
+impl<T: Ord> Receiver<T> {
+    /// Receives the greatest buffered value, waiting until there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RecvError`] if the buffer is empty and every sender has
+    /// been dropped.
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)) for *n* buffered values, not
+    /// counting the wait.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(value) = state.heap.pop() {
+                drop(state);
+                self.shared.not_full.notify_one();
+                return Ok(value);
+            }
+            if state.senders == 0 {
+                return Err(RecvError);
+            }
+            state = ignore_poison(self.shared.not_empty.wait(state));
+        }
+    }
+
+    /// Receives the greatest buffered value if there is one, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryRecvError::Empty`] if the buffer is empty, or
+    /// [`TryRecvError::Disconnected`] if in addition every sender has been
+    /// dropped.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.shared.lock();
+        match state.heap.pop() {
+            Some(value) => {
+                drop(state);
+                self.shared.not_full.notify_one();
+                Ok(value)
+            }
+            None if state.senders == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Returns an iterator receiving values with [`recv`], which ends once
+    /// every sender has been dropped and the buffer is empty.
+    ///
+    /// [`recv`]: Receiver::recv
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        core::iter::from_fn(move || self.recv().ok())
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Returns the number of buffered values.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.shared.lock().heap.len()
+    }
+
+    /// Checks if the buffer is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.shared.lock().heap.is_empty()
+    }
+}
@@ -1,7 +1,16 @@
-use crate::{WeakHeap, WeakHeapPeekMut};
+use crate::bits::{BitVec, Bits};
+use crate::{
+    kmerge, select, slice, sort, AggregateWeakHeap, ArrayWeakHeap, BoundedWeakHeap, Count, Handle,
+    Heap, IncrementalSorter, IndexedWeakHeap, InterleavedWeakHeap, KeyedWeakHeap, LazyWeakHeap,
+    MedianHeap, MinMaxWeakHeap, MinWeakHeap, RealtimeWeakHeap, RelaxedWeakQueue, ReverseBits,
+    SegmentedWeakHeap, StableWeakHeap, Sum, TotalOrd, TryWeakHeap, WeakHeap, WeakHeapBy,
+    WeakHeapByKey, WeakHeapPeekMut, WeakHeapPendingPop, WeakHeapView, WeakQueue, WeakQueuePeekMut,
+};
+#[cfg(feature = "std")]
+use crate::{ConcurrentWeakHeap, CountingWeakHeap, WeakHeapMap, WeakHeapSet};
 use rand::{thread_rng, Rng};
 use std::collections::binary_heap::PeekMut;
-use std::collections::BinaryHeap;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
 
 #[test]
 fn test_creation() {
@@ -55,7 +64,7 @@ fn test_from() {
     }
     assert!(heap_from_iter.is_empty());
 
-    let mut heap_from_iter = WeakHeap::from_iter([3, 2, 5, 4, 1].into_iter());
+    let mut heap_from_iter = WeakHeap::from_iter([3, 2, 5, 4, 1]);
     let mut temp_heap = heap_from_vec.clone();
     while let Some((a, b)) = temp_heap.pop().zip(heap_from_iter.pop()) {
         assert_eq!(a, b);
@@ -67,7 +76,10 @@ fn test_from() {
 fn test_into_sorted_vec() {
     // Edge cases
     let elements: Vec<i32> = vec![];
-    assert_eq!(WeakHeap::from(elements).into_sorted_vec(), vec![],);
+    assert_eq!(
+        WeakHeap::from(elements).into_sorted_vec(),
+        Vec::<i32>::new(),
+    );
 
     let elements: Vec<i32> = vec![1];
     assert_eq!(WeakHeap::from(elements).into_sorted_vec(), vec![1],);
@@ -309,6 +321,73 @@ fn test_shrink_to_fit() {
     assert_eq!(heap.capacity(), 3);
 }
 
+#[test]
+fn test_bit_capacity_follows_data() {
+    fn check<T>(heap: &WeakHeap<T>) {
+        let usage = heap.memory_usage();
+        assert!(usage.bit_capacity >= usage.capacity);
+        assert!(usage.bit_capacity < usage.capacity + 64);
+    }
+
+    let mut heap = WeakHeap::new();
+    let mut last = heap.memory_usage();
+    for x in 0..1000 {
+        heap.push(x);
+        let usage = heap.memory_usage();
+        // The bits are only reallocated together with the elements.
+        if usage.capacity == last.capacity {
+            assert_eq!(usage.bit_bytes, last.bit_bytes);
+        }
+        check(&heap);
+        last = usage;
+    }
+
+    heap.shrink_to(500);
+    check(&heap);
+    heap.reserve(3000);
+    check(&heap);
+    heap.reserve_exact(5000);
+    check(&heap);
+    heap.try_reserve(7000).unwrap();
+    check(&heap);
+    heap.shrink_to_fit();
+    check(&heap);
+    heap.extend(0..100);
+    check(&heap);
+    heap.append_vec(&mut (0..300).collect());
+    check(&heap);
+    heap.append(&mut WeakHeap::from(vec![1; 2000]));
+    check(&heap);
+
+    check(&WeakHeap::<u8>::with_capacity(100));
+    check(&WeakHeap::from(Vec::<u8>::with_capacity(200)));
+    check(&WeakHeap::try_from_vec(Vec::<u8>::with_capacity(200)).unwrap());
+}
+
+#[test]
+fn test_memory_usage() {
+    let heap: WeakHeap<u32> = WeakHeap::new();
+    assert_eq!(heap.allocated_bytes(), 0);
+    assert_eq!(heap.memory_usage().bytes_per_element(), None);
+
+    let mut heap: WeakHeap<u32> = WeakHeap::with_capacity(100);
+    let usage = heap.memory_usage();
+    assert_eq!(usage.capacity, 100);
+    assert_eq!(usage.data_bytes, 400);
+    assert_eq!(usage.bit_capacity, 128);
+    assert_eq!(usage.bit_bytes, 16);
+    assert_eq!(heap.allocated_bytes(), 416);
+
+    heap.extend(0..100);
+    let usage = heap.memory_usage();
+    assert_eq!(usage.len, 100);
+    assert_eq!(usage.bytes_per_element(), Some(4.16));
+
+    heap.clear();
+    heap.shrink_to_fit();
+    assert_eq!(heap.allocated_bytes(), 0);
+}
+
 #[test]
 fn test_peek_mut() {
     let mut heap: WeakHeap<i32> = WeakHeap::new();
@@ -415,12 +494,91 @@ fn test_pushpop() {
     }
 }
 
+#[test]
+fn test_replace() {
+    let mut heap: WeakHeap<i64> = WeakHeap::new();
+    assert_eq!(heap.replace(5), None);
+    assert_eq!(heap.peek(), Some(&5));
+
+    // Random tests against pop and push
+    let mut rng = thread_rng();
+
+    for size in 0..=100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut heap1 = WeakHeap::from(elements); // replace
+        let mut heap2 = heap1.clone(); // pop and push
+
+        for _ in 0..size * 2 {
+            let item = rng.gen_range(-50..50);
+            let popped = heap2.pop();
+            heap2.push(item);
+            assert_eq!(heap1.replace(item), popped);
+            assert!(is_weak_heap(&heap1.data, &heap1.bit));
+            assert_eq!(heap1.len(), heap2.len());
+            assert_eq!(heap1.peek(), heap2.peek());
+        }
+
+        assert_eq!(heap1.into_sorted_vec(), heap2.into_sorted_vec());
+    }
+}
+
+#[test]
+fn test_pop_batch() {
+    let mut rng = thread_rng();
+
+    for size in 0..=100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut heap1 = WeakHeap::from(elements); // pop_batch
+        let mut heap2 = heap1.clone(); // pop
+
+        while !heap1.is_empty() {
+            let k = rng.gen_range(0..=size / 2 + 1);
+            let expected: Vec<i64> = (0..k).map_while(|_| heap2.pop()).collect();
+            assert_eq!(heap1.pop_batch(k), expected);
+            assert!(is_weak_heap(&heap1.data, &heap1.bit));
+            assert_eq!(heap1.peek(), heap2.peek());
+        }
+        assert!(heap2.is_empty());
+    }
+}
+
+#[test]
+fn test_push_batch() {
+    let mut rng = thread_rng();
+
+    for size in 0..=100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut heap = WeakHeap::from(elements.clone());
+        let mut reference = BinaryHeap::from(elements);
+
+        // Both small batches (sifting up) and large ones (rebuilding).
+        for batch_len in [0, 1, 3, size, 4 * size] {
+            let batch: Vec<i64> = (0..batch_len).map(|_| rng.gen_range(-50..50)).collect();
+            heap.push_batch(batch.iter().copied());
+            reference.extend(batch);
+            assert!(is_weak_heap(&heap.data, &heap.bit));
+            assert_eq!(heap.peek(), reference.peek());
+        }
+        assert_eq!(heap.into_sorted_vec(), reference.into_sorted_vec());
+    }
+
+    // A panicking iterator leaves a valid heap behind.
+    let mut heap = WeakHeap::from(vec![5, 2, 8]);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        heap.push_batch((0..10).map(|x| if x < 7 { x * 3 } else { panic!() }));
+    }));
+    assert!(result.is_err());
+    assert!(is_weak_heap(&heap.data, &heap.bit));
+    assert_eq!(heap.len(), 10);
+    assert_eq!(heap.peek(), Some(&18));
+}
+
 #[test]
 fn test_append() {
     let mut h1: WeakHeap<i64> = WeakHeap::new();
     let mut h2: WeakHeap<i64> = WeakHeap::new();
     h1.append(&mut h2);
-    assert_eq!(h1.into_sorted_vec(), vec![]);
+    assert_eq!(h1.into_sorted_vec(), Vec::<i64>::new());
 
     // Random tests against BinaryHeap
     let mut rng = thread_rng();
@@ -448,6 +606,7 @@ fn test_append() {
             wh1.append(&mut wh2);
             bh1.append(&mut bh2);
 
+            assert!(is_weak_heap(&wh1.data, &wh1.bit));
             assert_eq!(wh1.peek(), bh1.peek());
             assert_eq!(wh1.len(), bh1.len());
             assert!(wh2.is_empty());
@@ -456,6 +615,86 @@ fn test_append() {
     }
 }
 
+#[test]
+fn test_split_off() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let mut elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let at = rng.gen_range(0..=size);
+        let mut heap = WeakHeap::from(elements.clone());
+        let other = heap.split_off(at);
+
+        assert_eq!(heap.len(), at);
+        assert_eq!(other.len(), size - at);
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+        assert!(is_weak_heap(&other.data, &other.bit));
+
+        let mut merged = heap.into_sorted_vec();
+        merged.extend(other.into_sorted_vec());
+        merged.sort_unstable();
+        elements.sort_unstable();
+        assert_eq!(merged, elements);
+    }
+}
+
+#[test]
+#[should_panic(expected = "should be <= len")]
+fn test_split_off_out_of_bounds() {
+    let mut heap = WeakHeap::from(vec![1, 2, 3]);
+    let _ = heap.split_off(4);
+}
+
+#[test]
+fn test_meld() {
+    let mut rng = thread_rng();
+    for _ in 0..200 {
+        let size1 = rng.gen_range(0..100);
+        let size2 = rng.gen_range(0..100);
+        let elements1: Vec<i64> = (0..size1).map(|_| rng.gen_range(-30..=30)).collect();
+        let elements2: Vec<i64> = (0..size2).map(|_| rng.gen_range(-30..=30)).collect();
+
+        let mut wh1 = WeakHeap::from(elements1.clone());
+        wh1.reserve(rng.gen_range(0..200));
+        let wh2 = WeakHeap::from(elements2.clone());
+        let capacity = wh1.capacity().max(wh2.capacity());
+
+        let melded = wh1.meld(wh2);
+        assert!(is_weak_heap(&melded.data, &melded.bit));
+        if size1 + size2 <= capacity {
+            assert_eq!(melded.capacity(), capacity);
+        }
+
+        let mut bin_heap = BinaryHeap::from(elements1);
+        bin_heap.extend(elements2);
+        assert_eq!(melded.into_sorted_vec(), bin_heap.into_sorted_vec());
+    }
+}
+
+#[test]
+fn test_merge_many() {
+    assert!(WeakHeap::<i64>::merge_many(Vec::new()).is_empty());
+
+    let mut rng = thread_rng();
+    for count in 0..20 {
+        let parts: Vec<Vec<i64>> = (0..count)
+            .map(|_| {
+                let size = rng.gen_range(0..50);
+                (0..size).map(|_| rng.gen_range(-30..=30)).collect()
+            })
+            .collect();
+        let mut expected: Vec<i64> = parts.iter().flatten().copied().collect();
+        expected.sort_unstable();
+
+        let merged = WeakHeap::merge_many(parts.iter().cloned().map(WeakHeap::from).collect());
+        assert!(is_weak_heap(&merged.data, &merged.bit));
+        assert_eq!(merged.into_sorted_vec(), expected);
+
+        let collected: WeakHeap<i64> = parts.into_iter().map(WeakHeap::from).collect();
+        assert!(is_weak_heap(&collected.data, &collected.bit));
+        assert_eq!(collected.into_sorted_vec(), expected);
+    }
+}
+
 #[test]
 fn test_extend() {
     let mut heap: WeakHeap<i64> = WeakHeap::new();
@@ -466,7 +705,7 @@ fn test_extend() {
     heap.extend(Vec::<i64>::new());
     assert_eq!(heap.len(), 1);
 
-    heap.extend(vec![7, 9, 2, 1].into_iter());
+    heap.extend(vec![7, 9, 2, 1]);
     assert_eq!(heap.into_sorted_vec(), vec![0, 1, 2, 7, 9]);
 
     // Random tests against BinaryHeap
@@ -484,6 +723,7 @@ fn test_extend() {
         weak_heap.extend(elements.clone());
         bin_heap.extend(elements);
 
+        assert!(is_weak_heap(&weak_heap.data, &weak_heap.bit));
         assert_eq!(weak_heap.len(), bin_heap.len());
         assert_eq!(weak_heap.peek(), bin_heap.peek());
         assert_eq!(
@@ -531,6 +771,28 @@ fn append_vec() {
     assert_eq!(weak_heap.into_sorted_vec(), all_elements);
 }
 
+#[test]
+fn test_heap_trait() {
+    fn drain_generic<H: Heap<i32>>(mut heap: H) -> Vec<i32> {
+        assert!(heap.is_empty());
+        heap.extend(vec![5, 1, 8]);
+        heap.push(3);
+        assert_eq!(heap.len(), 4);
+        assert_eq!(heap.peek(), Some(&8));
+        let mut out = Vec::new();
+        while let Some(x) = heap.pop() {
+            out.push(x);
+        }
+        heap.push(1);
+        heap.clear();
+        assert_eq!(heap.peek(), None);
+        out
+    }
+
+    assert_eq!(drain_generic(WeakHeap::new()), vec![8, 5, 3, 1]);
+    assert_eq!(drain_generic(BinaryHeap::new()), vec![8, 5, 3, 1]);
+}
+
 #[test]
 fn test_into_iter() {
     let heap: WeakHeap<i32> = WeakHeap::new();
@@ -584,13 +846,47 @@ fn test_iter() {
         }
 
         let heap = WeakHeap::from(elements);
-        let mut content: Vec<i64> = heap.iter().map(|x| *x).collect();
+        let mut content: Vec<i64> = heap.iter().copied().collect();
         content.sort();
 
         assert_eq!(content, heap.into_sorted_vec());
     }
 }
 
+#[test]
+fn test_exact_size_iterators() {
+    let mut heap = WeakHeap::from(vec![4, 1, 7, 3, 9]);
+    let slice = heap.clone().into_vec();
+
+    let mut iter = heap.iter();
+    assert_eq!(iter.len(), 5);
+    assert_eq!(iter.nth(1), Some(&slice[1]));
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.nth_back(0), Some(&slice[4]));
+    assert_eq!(iter.clone().count(), 2);
+    assert_eq!(iter.nth(5), None);
+    assert_eq!(iter.len(), 0);
+
+    let mut into_iter = heap.clone().into_iter();
+    assert_eq!(into_iter.size_hint(), (5, Some(5)));
+    assert_eq!(into_iter.nth(2), Some(slice[2]));
+    assert_eq!(into_iter.len(), 2);
+    assert_eq!(into_iter.clone().last(), Some(slice[4]));
+    assert_eq!(into_iter.nth_back(1), Some(slice[3]));
+    assert_eq!(into_iter.count(), 0);
+
+    let mut drain = heap.drain();
+    assert_eq!(drain.len(), 5);
+    assert_eq!(drain.nth(3), Some(slice[3]));
+    assert_eq!(drain.len(), 1);
+    assert_eq!(drain.last(), Some(slice[4]));
+    assert!(heap.is_empty());
+
+    heap.extend(0..10);
+    assert_eq!(heap.drain().count(), 10);
+    assert!(heap.is_empty());
+}
+
 #[test]
 fn test_drain() {
     let mut rng = rand::thread_rng();
@@ -677,7 +973,7 @@ fn test_into_iter_ref() {
         }
 
         let heap = WeakHeap::from(elements);
-        let mut content: Vec<i64> = (&heap).into_iter().map(|x| *x).collect();
+        let mut content: Vec<i64> = (&heap).into_iter().copied().collect();
         content.sort();
 
         assert_eq!(content, heap.into_sorted_vec());
@@ -694,7 +990,2702 @@ fn test_extend_ref() {
     heap.extend(Vec::<i64>::new());
     assert_eq!(heap.len(), 1);
 
-    heap.extend(vec![&7, &9, &2, &1].into_iter());
+    heap.extend(vec![&7, &9, &2, &1]);
     heap.extend(vec![&4, &3, &6, &5]);
     assert_eq!(heap.into_sorted_vec(), vec![0, 1, 2, 3, 4, 5, 6, 7, 9]);
 }
+
+// Checks that every element is not greater than its distinguished ancestor.
+fn is_weak_heap<T: Ord, B: Bits + ?Sized>(data: &[T], bits: &B) -> bool {
+    assert_eq!(data.len(), bits.len());
+    (1..data.len()).all(|pos| {
+        let mut cur = pos;
+        // SAFETY: cur / 2 < pos < bits.len().
+        while cur / 2 > 0 && cur % 2 == unsafe { bits.get_unchecked(cur / 2) } as usize {
+            cur /= 2;
+        }
+        data[cur / 2] >= data[pos]
+    })
+}
+
+#[test]
+fn test_slice_primitives() {
+    let mut rng = thread_rng();
+    for size in 1..=50 {
+        let mut data: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut bits = vec![false; size];
+
+        slice::heapify(&mut data, &mut bits);
+        assert!(is_weak_heap(&data, &bits));
+
+        for _ in 0..size {
+            let pos = rng.gen_range(0..size);
+            let old = data[pos];
+            data[pos] = rng.gen_range(-30..=30);
+            if data[pos] < old {
+                slice::sift_down(&mut data, &mut bits, pos);
+            } else {
+                slice::sift_up(&mut data, &mut bits, pos);
+            }
+            assert!(is_weak_heap(&data, &bits));
+        }
+
+        let mut sorted = data.clone();
+        sorted.sort();
+        for end in (1..size).rev() {
+            data.swap(0, end);
+            slice::sift_down(&mut data[..end], &mut bits[..end], 0);
+        }
+        assert_eq!(data, sorted);
+    }
+}
+
+#[test]
+fn test_remove_min() {
+    let mut heap: WeakHeap<i32> = WeakHeap::new();
+    assert_eq!(heap.peek_min(), None);
+    assert_eq!(heap.remove_min(), None);
+
+    let mut rng = thread_rng();
+    for size in 0..=50 {
+        let mut elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut heap = WeakHeap::from(elements.clone());
+        elements.sort();
+
+        for &x in elements.iter() {
+            assert_eq!(heap.peek_min(), Some(&x));
+            assert_eq!(heap.remove_min(), Some(x));
+            assert!(is_weak_heap(&heap.data, &heap.bit));
+        }
+        assert!(heap.is_empty());
+    }
+
+    // Interleaved with `push` and `pop`.
+    let mut heap = WeakHeap::new();
+    let mut model = Vec::new();
+    for _ in 0..500 {
+        match rng.gen_range(0..3) {
+            0 => {
+                let x = rng.gen_range(-30..=30);
+                heap.push(x);
+                model.push(x);
+            }
+            1 => {
+                model.sort();
+                assert_eq!(heap.pop(), model.pop());
+            }
+            _ => {
+                model.sort_by(|a, b| b.cmp(a));
+                assert_eq!(heap.remove_min(), model.pop());
+            }
+        }
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+    }
+}
+
+#[test]
+fn test_heapify_appended() {
+    let mut rng = thread_rng();
+    for size in 0..=30 {
+        for additional in 0..=30 {
+            let mut elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+            let mut heap = WeakHeap::from(elements.clone());
+            heap.reserve(additional);
+
+            for slot in heap.spare_capacity_mut().iter_mut().take(additional) {
+                let x = rng.gen_range(-30..=30);
+                slot.write(x);
+                elements.push(x);
+            }
+            unsafe { heap.heapify_appended(additional) };
+
+            assert_eq!(heap.len(), size + additional);
+            assert!(is_weak_heap(&heap.data, &heap.bit));
+            elements.sort();
+            assert_eq!(heap.into_sorted_vec(), elements);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_weak_heap_set() {
+    let mut rng = thread_rng();
+    for size in 0..=100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+
+        let mut set = WeakHeapSet::new();
+        let mut model = std::collections::BTreeSet::new();
+        for &x in elements.iter() {
+            assert_eq!(set.push(x), model.insert(x));
+            assert_eq!(set.len(), model.len());
+            assert!(set.contains(&x));
+        }
+
+        let collected: WeakHeapSet<i64> = elements.iter().copied().collect();
+        assert_eq!(
+            collected.into_sorted_vec(),
+            model.iter().copied().collect::<Vec<_>>()
+        );
+
+        while let Some(x) = set.pop() {
+            assert_eq!(Some(x), model.pop_last());
+            assert!(!set.contains(&x));
+        }
+        assert!(model.is_empty());
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_weak_heap_set_replace() {
+    // Equal by the key only, the tag tells the copies apart.
+    #[derive(Clone, Debug)]
+    struct Tagged(i64, u32);
+
+    impl PartialEq for Tagged {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+    impl Eq for Tagged {}
+    impl std::hash::Hash for Tagged {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.0.hash(state);
+        }
+    }
+    impl PartialOrd for Tagged {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Tagged {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    let mut rng = thread_rng();
+    let mut set = WeakHeapSet::new();
+    let mut model = std::collections::BTreeMap::new();
+    for tag in 0..500 {
+        let key = rng.gen_range(-30..=30);
+        let replaced = set.replace(Tagged(key, tag)).map(|Tagged(_, tag)| tag);
+        assert_eq!(replaced, model.insert(key, tag));
+        assert_eq!(set.len(), model.len());
+    }
+    while let Some(Tagged(key, tag)) = set.pop() {
+        assert_eq!(Some((key, tag)), model.pop_last());
+    }
+    assert!(model.is_empty());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_counting_weak_heap() {
+    let mut rng = thread_rng();
+    for size in 0..=100 {
+        let mut elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-5..=5)).collect();
+
+        let mut heap = CountingWeakHeap::new();
+        for &x in elements.iter() {
+            heap.push(x);
+        }
+        assert_eq!(heap.len(), size);
+        assert!(heap.distinct_len() <= 11);
+
+        let collected: CountingWeakHeap<i64> = elements.iter().copied().collect();
+        assert_eq!(collected.len(), size);
+        assert_eq!(collected.distinct_len(), heap.distinct_len());
+
+        elements.sort();
+        for &x in elements.iter() {
+            assert_eq!(heap.count(&x), elements.iter().filter(|&&y| y == x).count());
+        }
+        while let Some(x) = heap.pop() {
+            assert_eq!(Some(x), elements.pop());
+            assert_eq!(heap.len(), elements.len());
+        }
+        assert!(elements.is_empty());
+        assert!(heap.is_empty());
+    }
+}
+
+#[test]
+fn test_split_to_workers() {
+    let mut rng = thread_rng();
+    for size in 0..=50 {
+        for n in 1..=10 {
+            let mut elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+            let shards = WeakHeap::from(elements.clone()).split_to_workers(n);
+            assert_eq!(shards.len(), n);
+
+            let max = shards.iter().map(|h| h.len()).max().unwrap();
+            let min = shards.iter().map(|h| h.len()).min().unwrap();
+            assert!(max - min <= 1);
+
+            let mut content = Vec::new();
+            for shard in shards {
+                assert!(is_weak_heap(&shard.data, &shard.bit));
+                content.extend(shard.into_vec());
+            }
+            content.sort();
+            elements.sort();
+            assert_eq!(content, elements);
+        }
+    }
+}
+
+#[test]
+fn test_progress() {
+    use std::ops::ControlFlow;
+
+    let mut rng = thread_rng();
+    for size in 0..=50 {
+        for every in 1..=5 {
+            let mut elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+            let stop = rng.gen_range(0..=size);
+
+            let mut last = 0;
+            let result = WeakHeap::from_vec_with_progress(elements.clone(), every, |p| {
+                assert_eq!(p.total, size);
+                assert_eq!(p.done, last + every);
+                last = p.done;
+                if p.done < stop {
+                    ControlFlow::Continue(())
+                } else {
+                    ControlFlow::Break(())
+                }
+            });
+            let heap = match result {
+                Ok(heap) => heap,
+                Err(vec) => WeakHeap::from(vec),
+            };
+            assert!(is_weak_heap(&heap.data, &heap.bit));
+
+            let heap = match heap.into_sorted_vec_with_progress(every, |p| {
+                if p.done < stop {
+                    ControlFlow::Continue(())
+                } else {
+                    ControlFlow::Break(())
+                }
+            }) {
+                Ok(sorted) => WeakHeap::from(sorted),
+                Err(heap) => heap,
+            };
+            assert!(is_weak_heap(&heap.data, &heap.bit));
+            assert_eq!(heap.len(), size);
+
+            let (mut left, mut right) = (heap.into_vec(), elements.clone());
+            left.sort();
+            right.sort();
+            assert_eq!(left, right);
+
+            let mut heap = WeakHeap::new();
+            let mut other = elements.clone();
+            while heap
+                .append_vec_with_progress(&mut other, every, |p| {
+                    if p.done < stop {
+                        ControlFlow::Continue(())
+                    } else {
+                        ControlFlow::Break(())
+                    }
+                })
+                .is_break()
+            {
+                assert!(is_weak_heap(&heap.data, &heap.bit));
+                assert_eq!(heap.len() + other.len(), size);
+            }
+            assert!(other.is_empty());
+            elements.sort();
+            assert_eq!(heap.into_sorted_vec(), elements);
+        }
+    }
+}
+
+#[test]
+fn test_try_operations() {
+    let mut heap = WeakHeap::try_from_vec(vec![3, 1, 2]).unwrap();
+    heap.try_push(5).unwrap();
+    assert_eq!(heap.peek(), Some(&5));
+
+    let mut other = WeakHeap::from(vec![4, 0, 7, 8, 9]);
+    heap.try_append(&mut other).unwrap();
+    assert!(other.is_empty());
+
+    let mut v = vec![6, -1];
+    heap.try_append_vec(&mut v).unwrap();
+    assert!(v.is_empty());
+    assert!(is_weak_heap(&heap.data, &heap.bit));
+
+    // A failed reservation leaves the heap unchanged.
+    let mut huge = WeakHeap::<u64>::new();
+    assert!(huge.try_reserve(usize::MAX).is_err());
+    assert!(huge.try_reserve_exact(usize::MAX).is_err());
+    assert!(heap.try_reserve(usize::MAX).is_err());
+    assert_eq!(heap.len(), 11);
+    assert_eq!(heap.clone().into_vec().len(), heap.bit.len());
+
+    let err = heap.try_reserve(usize::MAX).unwrap_err();
+    assert!(!err.to_string().is_empty());
+    #[cfg(feature = "std")]
+    assert!(std::error::Error::source(&err).is_some());
+
+    assert_eq!(
+        heap.into_sorted_vec(),
+        vec![-1, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9]
+    );
+}
+
+#[test]
+fn test_incremental_sorter() {
+    let mut rng = thread_rng();
+    for size in 0..=50 {
+        let mut elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut sorter = IncrementalSorter::new(elements.clone());
+        elements.sort();
+
+        for _ in 0..10 {
+            let index = rng.gen_range(0..=size);
+            assert_eq!(sorter.get_sorted(index), elements.get(index));
+            if index < size {
+                assert!(sorter.sorted_len() > index);
+            }
+        }
+        assert_eq!(sorter.len(), size);
+        assert_eq!(sorter.into_sorted_vec(), elements);
+    }
+}
+
+#[test]
+fn test_begin_pop() {
+    let mut heap: WeakHeap<i32> = WeakHeap::new();
+    assert!(heap.begin_pop().is_none());
+
+    let mut rng = thread_rng();
+    for size in 1..=50 {
+        let mut elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut heap = WeakHeap::from(elements.clone());
+        elements.sort();
+
+        // Aborting restores the exact layout.
+        let (data, bit) = (heap.data.clone(), heap.bit.clone());
+        let pending = heap.begin_pop().unwrap();
+        assert_eq!(Some(&*pending), elements.last());
+        drop(pending);
+        assert_eq!((&heap.data, &heap.bit), (&data, &bit));
+
+        // Modifying the item before aborting re-sifts it.
+        let mut pending = heap.begin_pop().unwrap();
+        *pending = -100;
+        WeakHeapPendingPop::abort(pending);
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+        *elements.last_mut().unwrap() = -100;
+        elements.sort();
+
+        while !heap.is_empty() {
+            let pending = heap.begin_pop().unwrap();
+            assert_eq!(Some(WeakHeapPendingPop::commit(pending)), elements.pop());
+            assert!(is_weak_heap(&heap.data, &heap.bit));
+        }
+        assert!(elements.is_empty());
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_pop_with_budget() {
+    use std::time::{Duration, Instant};
+
+    let mut rng = thread_rng();
+    for size in 0..=50 {
+        let mut elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut heap = WeakHeap::from(elements.clone());
+        elements.sort();
+
+        let mut processed = Vec::new();
+        let budget = rng.gen_range(0..=size);
+        assert_eq!(heap.pop_with_budget(budget, |x| processed.push(x)), budget);
+        assert_eq!(heap.len(), size - budget);
+
+        let past = Instant::now();
+        assert_eq!(heap.pop_until_deadline(past, |x| processed.push(x)), 0);
+
+        let deadline = Instant::now() + Duration::from_secs(3600);
+        let count = heap.pop_until_deadline(deadline, |x| processed.push(x));
+        assert_eq!(count, size - budget);
+        assert!(heap.is_empty());
+
+        elements.reverse();
+        assert_eq!(processed, elements);
+    }
+}
+
+#[test]
+fn test_aggregate_weak_heap() {
+    let mut rng = thread_rng();
+    let mut heap: AggregateWeakHeap<i64, (Sum<i64>, Count)> = AggregateWeakHeap::new();
+    let mut elements = Vec::new();
+    for _ in 0..200 {
+        if rng.gen_bool(0.6) {
+            let x = rng.gen_range(-50..=50);
+            heap.push(x);
+            elements.push(x);
+        } else {
+            elements.sort();
+            assert_eq!(heap.pop(), elements.pop());
+        }
+        assert_eq!(
+            heap.aggregate(),
+            &(Sum(elements.iter().sum()), Count(elements.len()))
+        );
+    }
+
+    let collected: AggregateWeakHeap<i64, Sum<i64>> = elements.iter().copied().collect();
+    assert_eq!(collected.aggregate(), &Sum(elements.iter().sum()));
+    assert_eq!(collected.len(), elements.len());
+
+    heap.clear();
+    assert_eq!(heap.aggregate(), &(Sum(0), Count(0)));
+}
+
+#[test]
+fn test_keyed_weak_heap() {
+    let mut rng = thread_rng();
+    let mut heap = KeyedWeakHeap::new();
+    let mut entries = Vec::new();
+    for _ in 0..300 {
+        if rng.gen_bool(0.6) {
+            let key = rng.gen_range(0..100);
+            heap.push(key, vec![key; 16]);
+            entries.push(key);
+        } else {
+            entries.sort();
+            let popped = heap.pop();
+            assert_eq!(popped.as_ref().map(|(k, _)| *k), entries.pop());
+            if let Some((key, payload)) = popped {
+                assert_eq!(payload, vec![key; 16]);
+            }
+        }
+        assert_eq!(heap.len(), entries.len());
+        assert!(heap.iter().all(|(k, v)| v[0] == *k));
+    }
+
+    entries.sort();
+    let sorted = heap.into_sorted_vec();
+    assert_eq!(sorted.iter().map(|(k, _)| *k).collect::<Vec<_>>(), entries);
+    assert!(sorted.iter().all(|(k, v)| *v == vec![*k; 16]));
+}
+
+#[test]
+fn test_try_weak_heap() {
+    use std::cell::Cell;
+
+    let mut rng = thread_rng();
+    for size in 0..=40 {
+        let elements: Vec<i32> = (0..size).map(|_| rng.gen_range(-20..=20)).collect();
+        // Every comparison fails with a probability of 1/8.
+        let seed = Cell::new(rng.gen::<u32>());
+        let cmp = |a: &i32, b: &i32| {
+            seed.set(seed.get().wrapping_mul(1_103_515_245).wrapping_add(12_345));
+            if (seed.get() >> 16) % 8 == 0 {
+                Err(())
+            } else {
+                Ok(a.cmp(b))
+            }
+        };
+
+        let mut heap = TryWeakHeap::new(cmp);
+        let mut expected = Vec::new();
+        for &x in &elements {
+            match heap.try_push(x) {
+                Ok(()) => expected.push(x),
+                Err((item, ())) => assert_eq!(item, x),
+            }
+            assert_eq!(heap.len(), expected.len());
+        }
+
+        expected.sort();
+        while !expected.is_empty() {
+            if let Ok(top) = heap.try_pop() {
+                assert_eq!(top, expected.pop());
+            }
+            assert_eq!(heap.len(), expected.len());
+        }
+        assert_eq!(heap.try_pop(), Ok(None));
+
+        match TryWeakHeap::try_from_vec(elements.clone(), cmp) {
+            Ok(heap) => assert_eq!(heap.peek(), elements.iter().max()),
+            Err((mut vec, ())) => {
+                let mut sorted = elements.clone();
+                sorted.sort();
+                vec.sort();
+                assert_eq!(vec, sorted);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_from_refs() {
+    let mut rng = thread_rng();
+    for size in 0..=50 {
+        let data: Vec<String> = (0..size)
+            .map(|_| rng.gen_range(0..100).to_string())
+            .collect();
+        let heap = WeakHeap::from_refs(&data);
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+
+        let mut expected: Vec<&String> = data.iter().collect();
+        expected.sort();
+        let sorted = heap.into_sorted_refs();
+        assert_eq!(sorted, expected);
+        assert!(sorted
+            .iter()
+            .all(|r| data.iter().any(|s| std::ptr::eq(*r, s))));
+    }
+}
+
+#[test]
+fn test_rebuild_after_mutation() {
+    let mut rng = thread_rng();
+    for size in 0..=50 {
+        let mut heap: WeakHeap<i32> = (0..size).map(|_| rng.gen_range(-50..50)).collect();
+
+        unsafe { heap.as_mut_slice_unchecked() }
+            .iter_mut()
+            .for_each(|x| *x = rng.gen_range(-50..50));
+        heap.rebuild();
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+
+        let start = rng.gen_range(0..=size);
+        let slice = unsafe { heap.as_mut_slice_unchecked() };
+        slice[start..]
+            .iter_mut()
+            .for_each(|x| *x = rng.gen_range(-100..100));
+        heap.rebuild_tail(start);
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+    }
+}
+
+#[test]
+fn test_segmented_weak_heap() {
+    let mut rng = thread_rng();
+    let mut heap = SegmentedWeakHeap::new();
+    let mut elements = Vec::new();
+    // Large enough to span several segments and to shrink back to zero.
+    for round in 0..3 {
+        for _ in 0..10_000 {
+            let x = rng.gen_range(0..1_000);
+            heap.push(x);
+            elements.push(x);
+        }
+        elements.sort();
+        let keep = if round == 2 { 0 } else { 5_000 };
+        while elements.len() > keep {
+            assert_eq!(heap.pop(), elements.pop());
+        }
+        assert_eq!(heap.len(), elements.len());
+    }
+    assert_eq!(heap.pop(), None);
+    assert!(heap.is_empty());
+
+    let data: Vec<i32> = (0..9_000).map(|_| rng.gen()).collect();
+    let mut sorted = data.clone();
+    sorted.sort();
+    let heap: SegmentedWeakHeap<_> = data.into_iter().collect();
+    assert_eq!(heap.into_sorted_vec(), sorted);
+}
+
+#[test]
+fn test_segmented_weak_heap_boundary() {
+    let mut heap: SegmentedWeakHeap<usize> = (0..4096).collect();
+    let capacity = heap.capacity();
+    assert_eq!(capacity, 4096);
+
+    // Crossing the boundary back and forth allocates a single segment.
+    for _ in 0..100 {
+        heap.push(5000);
+        assert_eq!(heap.capacity(), 2 * capacity);
+        assert_eq!(heap.pop(), Some(5000));
+        assert_eq!(heap.capacity(), 2 * capacity);
+    }
+    assert_eq!(heap.len(), 4096);
+
+    // Only one empty segment is kept when shrinking.
+    let mut heap: SegmentedWeakHeap<usize> = (0..3 * 4096).collect();
+    while heap.len() > 4096 {
+        heap.pop();
+    }
+    assert_eq!(heap.capacity(), 2 * capacity);
+    while heap.pop().is_some() {}
+    assert_eq!(heap.capacity(), capacity);
+    heap.push(1);
+    assert_eq!(heap.capacity(), capacity);
+    assert_eq!(heap.peek(), Some(&1));
+}
+
+#[test]
+fn test_segmented_weak_heap_extend() {
+    let mut rng = thread_rng();
+    for size in [0, 1, 100, 4096, 4097, 10_000] {
+        let data: Vec<i32> = (0..size).map(|_| rng.gen_range(-500..500)).collect();
+        let mut heap = SegmentedWeakHeap::from(data.clone());
+        let mut model = data;
+
+        // Both small batches (sifting up) and large ones (rebuilding).
+        for batch_len in [0, 1, 3, size, 3 * size] {
+            let batch: Vec<i32> = (0..batch_len).map(|_| rng.gen_range(-600..600)).collect();
+            heap.extend(batch.iter().copied());
+            model.extend(batch);
+            assert_eq!(heap.len(), model.len());
+            assert_eq!(heap.peek(), model.iter().max());
+        }
+        model.sort();
+        assert_eq!(heap.into_sorted_vec(), model);
+    }
+
+    // A panicking iterator leaves a valid heap behind.
+    let mut heap = SegmentedWeakHeap::from(vec![5, 2, 8]);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        heap.extend((0..10).map(|x| if x < 7 { x * 3 } else { panic!() }));
+    }));
+    assert!(result.is_err());
+    assert_eq!(heap.len(), 10);
+    assert_eq!(heap.into_sorted_vec(), [0, 2, 3, 5, 6, 8, 9, 12, 15, 18]);
+}
+
+#[test]
+fn test_realtime_weak_heap() {
+    let mut rng = thread_rng();
+    for work in 0..4 {
+        let mut heap = RealtimeWeakHeap::with_capacity(64, work);
+        let mut elements = Vec::new();
+        for _ in 0..500 {
+            let before = heap.pending_len();
+            match rng.gen_range(0..4) {
+                0 => {
+                    let x = rng.gen_range(0..50);
+                    match heap.push(x) {
+                        Ok(()) => elements.push(x),
+                        Err(y) => assert_eq!((y, heap.len()), (x, 64)),
+                    }
+                }
+                1 => {
+                    let x = rng.gen_range(0..50);
+                    if heap.push_deferred(x).is_ok() {
+                        elements.push(x);
+                    }
+                }
+                2 => {
+                    if let Some(x) = heap.pop() {
+                        let pos = elements.iter().position(|&y| y == x).unwrap();
+                        elements.swap_remove(pos);
+                    }
+                }
+                _ => {
+                    if heap.step(usize::MAX) == 0 {
+                        assert_eq!(heap.peek(), elements.iter().max());
+                    }
+                }
+            }
+            assert_eq!(heap.len(), elements.len());
+            assert!(heap.pending_len() <= before + 1);
+            assert!(heap.peek() <= elements.iter().max());
+        }
+
+        heap.step(usize::MAX);
+        elements.sort();
+        while let Some(x) = heap.pop() {
+            assert_eq!(Some(x), elements.pop());
+        }
+        assert!(elements.is_empty());
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "paranoid"))]
+#[test]
+#[should_panic(expected = "inconsistent `Ord` implementation")]
+fn test_paranoid_detects_inconsistent_ord() {
+    use std::cmp::Ordering;
+
+    // Claims to be less than everything, including itself.
+    #[derive(Debug)]
+    struct Broken(i32);
+
+    impl PartialEq for Broken {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+    impl Eq for Broken {}
+    impl PartialOrd for Broken {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Broken {
+        fn cmp(&self, _other: &Self) -> Ordering {
+            Ordering::Less
+        }
+    }
+
+    let mut heap = WeakHeap::new();
+    for x in 0..10 {
+        heap.push(Broken(x));
+    }
+}
+
+#[cfg(feature = "critical-section")]
+#[test]
+fn test_isr_queue() {
+    use crate::IsrQueue;
+
+    static QUEUE: IsrQueue<i32, 1000> = IsrQueue::new();
+
+    let handles: Vec<_> = (0..4)
+        .map(|t| {
+            std::thread::spawn(move || {
+                for x in 0..250 {
+                    QUEUE.push_from_isr(t * 1000 + x).unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(QUEUE.len(), 1000);
+    assert_eq!(QUEUE.push_from_isr(-1), Err(-1));
+
+    let mut last = i32::MAX;
+    while let Some(x) = QUEUE.pop() {
+        assert!(x <= last);
+        last = x;
+    }
+    assert!(QUEUE.is_empty());
+}
+
+#[test]
+fn test_k_largest_unordered() {
+    let mut rng = thread_rng();
+    for size in 0..=60 {
+        let elements: Vec<i32> = (0..size).map(|_| rng.gen_range(-20..=20)).collect();
+        let mut sorted = elements.clone();
+        sorted.sort();
+
+        for k in [0, 1, size / 2, size, size + 3] {
+            let expected = &sorted[size - k.min(size)..];
+
+            let mut data = elements.clone();
+            let best = slice::k_largest_unordered(&mut data, k);
+            best.sort();
+            assert_eq!(best, expected);
+
+            let mut best = WeakHeap::from(elements.clone()).into_k_largest_unordered(k);
+            best.sort();
+            assert_eq!(best, expected);
+        }
+    }
+}
+
+#[test]
+fn test_into_iter_sorted() {
+    let mut rng = thread_rng();
+    for size in 0..=50 {
+        let mut elements: Vec<i32> = (0..size).map(|_| rng.gen_range(-20..=20)).collect();
+        let heap = WeakHeap::from(elements.clone());
+        elements.sort_by(|a, b| b.cmp(a));
+
+        let mut iter = heap.into_iter_sorted();
+        let take = rng.gen_range(0..=size);
+        assert_eq!(
+            iter.by_ref().take(take).collect::<Vec<_>>(),
+            elements[..take]
+        );
+        assert_eq!(iter.len(), size - take);
+        assert_eq!(iter.collect::<Vec<_>>(), elements[take..]);
+    }
+}
+
+#[test]
+fn test_drain_sorted() {
+    let mut rng = thread_rng();
+    for size in 0..=50 {
+        let mut elements: Vec<i32> = (0..size).map(|_| rng.gen_range(-20..=20)).collect();
+        let mut heap = WeakHeap::from(elements.clone());
+        elements.sort_by(|a, b| b.cmp(a));
+
+        let take = rng.gen_range(0..=size);
+        let mut drain = heap.drain_sorted();
+        assert_eq!(drain.len(), size);
+        assert_eq!(
+            drain.by_ref().take(take).collect::<Vec<_>>(),
+            elements[..take]
+        );
+        drop(drain);
+        assert!(heap.is_empty());
+
+        heap.extend(elements.iter().copied());
+        assert_eq!(heap.drain_sorted().collect::<Vec<_>>(), elements);
+        assert!(heap.is_empty());
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+    }
+}
+
+#[test]
+fn test_retain() {
+    let mut rng = thread_rng();
+    for size in 0..=60 {
+        let elements: Vec<i32> = (0..size).map(|_| rng.gen_range(-50..=50)).collect();
+        let threshold = rng.gen_range(-60..=60);
+        let mut heap = WeakHeap::from(elements.clone());
+        heap.retain(|&x| x < threshold || x % 3 == 0);
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+
+        let mut expected: Vec<i32> = elements
+            .into_iter()
+            .filter(|&x| x < threshold || x % 3 == 0)
+            .collect();
+        expected.sort();
+        assert_eq!(heap.into_sorted_vec(), expected);
+    }
+
+    // A panicking predicate leaves a valid heap behind.
+    let mut heap: WeakHeap<i32> = (0..100).collect();
+    let mut calls = 0;
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        heap.retain(|&x| {
+            calls += 1;
+            assert!(calls < 50);
+            x % 2 == 0
+        });
+    }));
+    assert!(result.is_err());
+    assert_eq!(heap.check_integrity(), Ok(()));
+    assert_eq!(heap.len(), 75);
+}
+
+#[test]
+fn test_extract_if() {
+    let mut rng = thread_rng();
+    for size in 0..=60 {
+        let elements: Vec<i32> = (0..size).map(|_| rng.gen_range(-50..=50)).collect();
+        let threshold = rng.gen_range(-60..=60);
+
+        let mut heap = WeakHeap::from(elements.clone());
+        let mut extracted: Vec<i32> = heap.extract_if(|&x| x > threshold).collect();
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+
+        let (mut expected, mut kept): (Vec<i32>, Vec<i32>) =
+            elements.iter().partition(|&&x| x > threshold);
+        extracted.sort();
+        expected.sort();
+        kept.sort();
+        assert_eq!(extracted, expected);
+        assert_eq!(heap.clone().into_sorted_vec(), kept);
+
+        // Stopping early keeps the unvisited elements.
+        let mut heap = WeakHeap::from(elements.clone());
+        let taken: Vec<i32> = heap.extract_if(|&x| x % 2 == 0).take(2).collect();
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+        assert_eq!(heap.len() + taken.len(), size);
+    }
+}
+
+#[test]
+fn test_weak_heap_by() {
+    let mut rng = thread_rng();
+    for size in 0..=50 {
+        let elements: Vec<(i32, u8)> = (0..size)
+            .map(|_| (rng.gen_range(-20..=20), rng.gen()))
+            .collect();
+        // A min-heap on the first field.
+        let cmp = |a: &(i32, u8), b: &(i32, u8)| b.0.cmp(&a.0);
+
+        let mut heap = WeakHeapBy::new_by(cmp);
+        heap.extend(elements[..size / 2].iter().copied());
+        for &x in &elements[size / 2..] {
+            heap.push(x);
+        }
+        assert_eq!(heap.len(), size);
+
+        let mut expected: Vec<i32> = elements.iter().map(|x| x.0).collect();
+        expected.sort();
+        let popped: Vec<i32> = std::iter::from_fn(|| heap.pop()).map(|x| x.0).collect();
+        assert_eq!(popped, expected);
+
+        let heap = WeakHeapBy::from_vec_by(elements.clone(), cmp);
+        expected.reverse();
+        let sorted: Vec<i32> = heap.into_sorted_vec().iter().map(|x| x.0).collect();
+        assert_eq!(sorted, expected);
+    }
+}
+
+#[test]
+fn test_weak_heap_by_key() {
+    use std::cell::Cell;
+
+    let mut rng = thread_rng();
+    for size in 0..=50 {
+        let elements: Vec<String> = (0..size)
+            .map(|_| rng.gen_range(0..1000).to_string())
+            .collect();
+        let calls = Cell::new(0);
+        let key = |s: &String| {
+            calls.set(calls.get() + 1);
+            s.len()
+        };
+
+        let mut heap = WeakHeapByKey::new(key);
+        heap.extend(elements.iter().cloned());
+        assert_eq!(calls.get(), size);
+
+        let mut expected: Vec<usize> = elements.iter().map(String::len).collect();
+        expected.sort();
+        while let Some((k, s)) = heap.pop_with_key() {
+            assert_eq!(k, s.len());
+            assert_eq!(Some(k), expected.pop());
+        }
+        // Keys are never recomputed while sifting.
+        assert_eq!(calls.get(), size);
+
+        let heap = WeakHeapByKey::from_vec(elements.clone(), key);
+        let sorted: Vec<usize> = heap.into_sorted_vec().iter().map(String::len).collect();
+        let mut lens: Vec<usize> = elements.iter().map(String::len).collect();
+        lens.sort();
+        assert_eq!(sorted, lens);
+    }
+}
+
+#[test]
+fn test_min_max_weak_heap() {
+    let mut rng = thread_rng();
+    let mut heap = MinMaxWeakHeap::new();
+    let mut elements = Vec::new();
+    for _ in 0..2000 {
+        match rng.gen_range(0..5) {
+            0 | 1 => {
+                let x = rng.gen_range(-100..=100);
+                heap.push(x);
+                elements.push(x);
+            }
+            2 => {
+                elements.sort();
+                assert_eq!(heap.pop_max(), elements.pop());
+            }
+            3 => {
+                elements.sort();
+                let min = if elements.is_empty() {
+                    None
+                } else {
+                    Some(elements.remove(0))
+                };
+                assert_eq!(heap.pop_min(), min);
+            }
+            _ => {
+                assert_eq!(heap.peek_max(), elements.iter().max());
+                assert_eq!(heap.peek_min(), elements.iter().min());
+            }
+        }
+        assert_eq!(heap.len(), elements.len());
+    }
+
+    elements.sort();
+    assert_eq!(heap.clone().into_sorted_vec(), elements);
+    let mut all = heap.into_vec();
+    all.sort();
+    assert_eq!(all, elements);
+}
+
+#[test]
+fn test_indexed_weak_heap() {
+    let mut rng = thread_rng();
+    let mut heap = IndexedWeakHeap::new();
+    let mut live: Vec<(Handle, i32)> = Vec::new();
+    for _ in 0..2000 {
+        match rng.gen_range(0..6) {
+            0 | 1 => {
+                let x = rng.gen_range(-100..=100);
+                live.push((heap.push(x), x));
+            }
+            2 => {
+                let popped = heap.pop_with_handle();
+                let max = live.iter().map(|&(_, x)| x).max();
+                assert_eq!(popped.map(|(_, x)| x), max);
+                if let Some((handle, _)) = popped {
+                    live.retain(|&(h, _)| h != handle);
+                    assert!(!heap.contains(handle));
+                }
+            }
+            3 if !live.is_empty() => {
+                let i = rng.gen_range(0..live.len());
+                let (handle, x) = live.swap_remove(i);
+                assert_eq!(heap.remove(handle), Some(x));
+                assert_eq!(heap.remove(handle), None);
+            }
+            4 if !live.is_empty() => {
+                let i = rng.gen_range(0..live.len());
+                let x = rng.gen_range(-100..=100);
+                assert_eq!(heap.update(live[i].0, x), live[i].1);
+                live[i].1 = x;
+            }
+            5 if !live.is_empty() => {
+                let i = rng.gen_range(0..live.len());
+                let (handle, old) = live[i];
+                let x = rng.gen_range(-100..=100);
+                if x >= old {
+                    assert_eq!(heap.increase_key(handle, x), old);
+                } else {
+                    assert_eq!(heap.decrease_key(handle, x), old);
+                }
+                live[i].1 = x;
+            }
+            _ => {}
+        }
+        assert_eq!(heap.len(), live.len());
+        assert_eq!(heap.peek(), live.iter().map(|(_, x)| x).max());
+        for &(handle, x) in &live {
+            assert_eq!(heap.get(handle), Some(&x));
+        }
+    }
+
+    let mut elements: Vec<i32> = live.iter().map(|&(_, x)| x).collect();
+    elements.sort();
+    assert_eq!(heap.clone().into_sorted_vec(), elements);
+    let mut all = heap.into_vec();
+    all.sort();
+    assert_eq!(all, elements);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_weak_heap_map() {
+    let mut rng = thread_rng();
+    let mut map = WeakHeapMap::new();
+    let mut reference = std::collections::HashMap::new();
+    for _ in 0..2000 {
+        let key = rng.gen_range(0..50);
+        match rng.gen_range(0..4) {
+            0 | 1 => {
+                let priority = rng.gen_range(-100..=100);
+                assert_eq!(map.push(key, priority), reference.insert(key, priority));
+            }
+            2 => {
+                assert_eq!(map.remove(&key), reference.remove(&key));
+            }
+            _ => {
+                let popped = map.pop();
+                match popped {
+                    Some((key, priority)) => {
+                        assert_eq!(reference.values().max(), Some(&priority));
+                        assert_eq!(reference.remove(&key), Some(priority));
+                    }
+                    None => assert!(reference.is_empty()),
+                }
+            }
+        }
+        assert_eq!(map.len(), reference.len());
+        assert_eq!(map.get_priority(&key), reference.get(&key));
+        assert_eq!(map.peek().map(|(_, p)| p), reference.values().max());
+    }
+
+    let mut entries: Vec<_> = reference.into_iter().collect();
+    entries.sort_by_key(|&(_, priority)| priority);
+    let sorted = map.into_sorted_vec();
+    assert_eq!(
+        sorted.iter().map(|&(_, p)| p).collect::<Vec<_>>(),
+        entries.iter().map(|&(_, p)| p).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_bounded_weak_heap() {
+    let mut rng = thread_rng();
+    for bound in [0, 1, 2, 7, 64] {
+        let mut heap = BoundedWeakHeap::new(bound);
+        let mut all = Vec::new();
+        let mut rejected = Vec::new();
+        for _ in 0..500 {
+            let x = rng.gen_range(0..1000);
+            all.push(x);
+            rejected.extend(heap.push(x));
+            assert!(heap.len() <= bound);
+            assert_eq!(heap.len() + rejected.len(), all.len());
+        }
+        assert_eq!(heap.is_full(), bound <= all.len());
+
+        all.sort();
+        let best = all.split_off(all.len() - bound);
+        assert_eq!(heap.peek_min(), best.first());
+        rejected.sort();
+        assert_eq!(rejected, all);
+        assert_eq!(heap.clone().into_sorted_vec(), best);
+        let mut popped = Vec::new();
+        while let Some(x) = heap.pop_min() {
+            popped.push(x);
+        }
+        assert_eq!(popped, best);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    let mut rng = thread_rng();
+    for size in 0..50 {
+        let elements: Vec<i32> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let heap = WeakHeap::from(elements.clone());
+
+        let json = serde_json::to_string(&heap).unwrap();
+        let restored: WeakHeap<i32> = serde_json::from_str(&json).unwrap();
+        assert!(is_weak_heap(&restored.data, &restored.bit));
+        assert_eq!(restored.into_sorted_vec(), heap.into_sorted_vec());
+
+        // An arbitrary sequence is accepted and rebuilt into a valid heap.
+        let json = serde_json::to_string(&elements).unwrap();
+        let restored: WeakHeap<i32> = serde_json::from_str(&json).unwrap();
+        assert!(is_weak_heap(&restored.data, &restored.bit));
+        let mut sorted = elements;
+        sorted.sort();
+        assert_eq!(restored.into_sorted_vec(), sorted);
+    }
+    assert!(serde_json::from_str::<WeakHeap<i32>>("{\"data\": [1]}").is_err());
+}
+
+#[test]
+fn test_bit_vec() {
+    let mut rng = thread_rng();
+    let mut bits = BitVec::new();
+    let mut expected: Vec<bool> = Vec::new();
+    for _ in 0..5000 {
+        match rng.gen_range(0..6) {
+            0 | 1 => {
+                let value = rng.gen();
+                bits.push(value);
+                expected.push(value);
+            }
+            2 => assert_eq!(bits.pop(), expected.pop()),
+            3 => {
+                let len = rng.gen_range(0..=expected.len() + 100);
+                let value = rng.gen();
+                bits.resize(len, value);
+                expected.resize(len, value);
+            }
+            4 => {
+                let len = rng.gen_range(0..=expected.len());
+                bits.truncate(len);
+                expected.truncate(len);
+            }
+            5 => {
+                let len = rng.gen_range(0..100);
+                let mut other = BitVec::zeros(len);
+                if len > 0 {
+                    // SAFETY: 0 < len == other.len().
+                    unsafe { other.flip_unchecked(len - 1) };
+                }
+                let mut extra = vec![false; len];
+                if let Some(last) = extra.last_mut() {
+                    *last = true;
+                }
+                bits.append(&mut other);
+                expected.append(&mut extra);
+                assert_eq!(other.len(), 0);
+            }
+            _ => {}
+        }
+        assert_eq!(bits.len(), expected.len());
+        assert!(bits.iter().eq(expected.iter().copied()));
+    }
+
+    let mut copy = BitVec::zeros(3);
+    copy.clone_from(&bits);
+    assert_eq!(copy, bits);
+    bits.reset();
+    assert!(bits.iter().all(|bit| !bit));
+}
+
+#[test]
+fn test_interleaved_weak_heap() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut heap = InterleavedWeakHeap::from(elements.clone());
+        let mut reference = BinaryHeap::from(elements);
+
+        for _ in 0..50 {
+            if rng.gen_bool(0.5) {
+                let x = rng.gen_range(-30..=30);
+                heap.push(x);
+                reference.push(x);
+            } else {
+                assert_eq!(heap.pop(), reference.pop());
+            }
+            assert_eq!(heap.peek(), reference.peek());
+            assert_eq!(heap.len(), reference.len());
+        }
+        assert_eq!(heap.into_sorted_vec(), reference.into_sorted_vec());
+    }
+}
+
+#[test]
+fn test_weak_heapsort() {
+    let mut rng = thread_rng();
+    for size in 0..200 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+
+        let mut data = elements.clone();
+        sort::weak_heapsort(&mut data);
+        let mut expected = elements.clone();
+        expected.sort();
+        assert_eq!(data, expected);
+
+        let mut data = elements.clone();
+        sort::weak_heapsort_by(&mut data, |a, b| b.cmp(a));
+        expected.reverse();
+        assert_eq!(data, expected);
+
+        let mut data = elements.clone();
+        sort::weak_heapsort_by_key(&mut data, |x| x.abs());
+        assert!(data.windows(2).all(|w| w[0].abs() <= w[1].abs()));
+    }
+}
+
+#[test]
+fn test_partial_sort() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut expected = elements.clone();
+        expected.sort();
+
+        for k in [0, 1, 5, size / 2, size, size + 3] {
+            let k_len = k.min(size);
+            let mut data = elements.clone();
+            sort::partial_sort(&mut data, k);
+            assert_eq!(data[..k_len], expected[..k_len]);
+            data.sort();
+            assert_eq!(data, expected);
+
+            let mut data = elements.clone();
+            sort::partial_sort_by(&mut data, k, |a, b| b.cmp(a));
+            assert!(data[..k_len].iter().eq(expected.iter().rev().take(k_len)));
+        }
+    }
+}
+
+#[test]
+fn test_weak_heap_view() {
+    let mut rng = thread_rng();
+    for size in 0..60 {
+        let mut buffer: Vec<i64> = (0..size + 4).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut bits = vec![true; size];
+        let mut reference = BinaryHeap::from(buffer[2..size + 2].to_vec());
+
+        let mut view = if rng.gen_bool(0.5) {
+            WeakHeapView::new(&mut buffer[2..size + 2])
+        } else {
+            WeakHeapView::with_bits(&mut buffer[2..size + 2], &mut bits)
+        };
+        for _ in 0..100 {
+            match rng.gen_range(0..3) {
+                0 => {
+                    let pos = view.pop_to_end();
+                    assert_eq!(
+                        pos.map(|pos| view.tail()[pos - view.len()]),
+                        reference.pop()
+                    );
+                }
+                1 => {
+                    if let Some(pos) = view.push_from_end() {
+                        reference.push(view.as_slice()[pos]);
+                    } else {
+                        assert_eq!(view.len(), view.capacity());
+                    }
+                }
+                _ if !view.is_empty() => {
+                    let pos = rng.gen_range(0..view.len());
+                    let x = rng.gen_range(-30..=30);
+                    let mut items = reference.into_vec();
+                    let old = view.as_slice()[pos];
+                    let i = items.iter().position(|&y| y == old).unwrap();
+                    items[i] = x;
+                    reference = BinaryHeap::from(items);
+                    // SAFETY: the element is sifted right after the modification.
+                    let heap = unsafe { view.as_mut_slice_unchecked() };
+                    heap[pos] = x;
+                    view.sift(pos);
+                }
+                _ => {}
+            }
+            assert_eq!(view.len(), reference.len());
+            assert_eq!(view.peek(), reference.peek());
+        }
+        let len = view.len();
+        while view.pop_to_end().is_some() {}
+        assert!(view.tail()[..len].windows(2).all(|w| w[0] <= w[1]));
+    }
+}
+
+#[test]
+fn test_array_weak_heap() {
+    let mut rng = thread_rng();
+    let mut heap: ArrayWeakHeap<i64, 32> = ArrayWeakHeap::new();
+    let mut reference = BinaryHeap::new();
+    for _ in 0..2000 {
+        if rng.gen_bool(0.6) {
+            let x = rng.gen_range(-30..=30);
+            if reference.len() < heap.capacity() {
+                assert_eq!(heap.try_push(x), Ok(()));
+                reference.push(x);
+            } else {
+                assert!(heap.is_full());
+                assert_eq!(heap.try_push(x), Err(x));
+            }
+        } else {
+            assert_eq!(heap.pop(), reference.pop());
+        }
+        assert_eq!(heap.peek(), reference.peek());
+        assert_eq!(heap.len(), reference.len());
+    }
+    let mut copy = heap.clone();
+    while let Some(x) = reference.pop() {
+        assert_eq!(copy.pop(), Some(x));
+    }
+
+    // Every element is dropped exactly once.
+    let item = std::rc::Rc::new(());
+    let mut heap: ArrayWeakHeap<_, 4> = ArrayWeakHeap::new();
+    for _ in 0..3 {
+        heap.try_push(std::rc::Rc::clone(&item)).unwrap();
+    }
+    let copy = heap.clone();
+    assert_eq!(std::rc::Rc::strong_count(&item), 7);
+    drop(heap.pop());
+    drop(heap);
+    assert_eq!(std::rc::Rc::strong_count(&item), 4);
+    drop(copy);
+    assert_eq!(std::rc::Rc::strong_count(&item), 1);
+}
+
+#[test]
+fn test_from_sorted_vec() {
+    let mut rng = thread_rng();
+    for len in 0..100 {
+        let mut vec: Vec<i32> = (0..len).map(|_| rng.gen_range(-20..=20)).collect();
+        vec.sort_unstable();
+
+        let heap = WeakHeap::from_sorted_vec(vec.clone());
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+        assert_eq!(heap.into_sorted_vec(), vec);
+
+        let mut desc = vec.clone();
+        desc.reverse();
+        let mut heap = WeakHeap::from_sorted_vec_desc(desc);
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+        let x = rng.gen_range(-20..=20);
+        heap.push(x);
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+        assert_eq!(
+            heap.pop(),
+            vec.last().map_or(Some(x), |&max| Some(max.max(x)))
+        );
+    }
+}
+
+#[test]
+fn test_raw_parts() {
+    let mut rng = thread_rng();
+    for len in 0..200 {
+        let mut heap: WeakHeap<i32> = (0..len).map(|_| rng.gen_range(-50..=50)).collect();
+        let (data, bits) = heap.clone().into_raw_parts();
+        assert_eq!(data, heap.data);
+        assert!(bits.iter().eq(heap.bit.iter()));
+        assert_eq!(
+            ReverseBits::from_words(bits.as_words().to_vec(), bits.len()),
+            bits
+        );
+
+        // SAFETY: the parts come from `into_raw_parts`.
+        let mut restored = unsafe { WeakHeap::from_raw_parts(data, bits) };
+        assert!(is_weak_heap(&restored.data, &restored.bit));
+        while let Some(x) = heap.pop() {
+            assert_eq!(restored.pop(), Some(x));
+        }
+        assert!(restored.is_empty());
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "the parts do not form a weak heap")]
+fn test_from_raw_parts_invalid() {
+    // SAFETY: not upheld on purpose, the debug assertion catches it first.
+    let _ = unsafe { WeakHeap::from_raw_parts(vec![1, 2], [false, false].into_iter().collect()) };
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "the parts do not form a weak heap")]
+fn test_from_raw_parts_root_bit_set() {
+    // SAFETY: not upheld on purpose, the debug assertion catches it first.
+    let _ = unsafe { WeakHeap::from_raw_parts(vec![2, 1], [true, false].into_iter().collect()) };
+}
+
+#[test]
+fn test_kmerge() {
+    assert_eq!(kmerge(Vec::<Vec<i32>>::new()).next(), None);
+
+    let mut rng = thread_rng();
+    for count in 0..20 {
+        let mut parts: Vec<Vec<i32>> = (0..count)
+            .map(|_| {
+                let size = rng.gen_range(0..30);
+                (0..size).map(|_| rng.gen_range(-50..=50)).collect()
+            })
+            .collect();
+        for part in &mut parts {
+            part.sort_unstable();
+        }
+        let mut expected: Vec<i32> = parts.iter().flatten().copied().collect();
+        expected.sort_unstable();
+
+        let merged = kmerge(parts);
+        assert_eq!(merged.size_hint(), (expected.len(), Some(expected.len())));
+        assert_eq!(merged.collect::<Vec<_>>(), expected);
+    }
+}
+
+#[test]
+fn test_nlargest_nsmallest() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut sorted = elements.clone();
+        sorted.sort_unstable();
+
+        for k in [0, 1, 5, size / 2, size, size + 3] {
+            let expected: Vec<i64> = sorted.iter().rev().take(k).copied().collect();
+            assert_eq!(WeakHeap::nlargest(k, elements.iter().copied()), expected);
+
+            let expected: Vec<i64> = sorted.iter().take(k).copied().collect();
+            assert_eq!(WeakHeap::nsmallest(k, elements.iter().copied()), expected);
+        }
+    }
+    assert_eq!(WeakHeap::nlargest(usize::MAX, [2, 3, 1]), [3, 2, 1]);
+}
+
+#[test]
+fn test_peek_n() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let heap = WeakHeap::from(elements.clone());
+        let mut sorted = elements;
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+
+        for k in [0, 1, 5, size / 2, size, size + 3] {
+            let expected: Vec<&i64> = sorted.iter().take(k).collect();
+            assert_eq!(heap.peek_n(k), expected);
+        }
+    }
+}
+
+#[test]
+fn test_sorted_iter() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut heap = WeakHeap::from(elements.clone());
+        let mut sorted = elements;
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut iter = heap.sorted_iter();
+        assert_eq!(iter.len(), size);
+        if size > 0 {
+            assert_eq!(iter.next(), sorted.first());
+            assert_eq!(iter.len(), size - 1);
+        }
+        let rest: Vec<i64> = iter.clone().copied().collect();
+        assert_eq!(rest, sorted[size.min(1)..]);
+        assert_eq!(iter.count(), size.saturating_sub(1));
+
+        // The heap keeps serving while its items are listed.
+        let listed: Vec<i64> = heap.sorted_iter().copied().collect();
+        assert_eq!(listed, sorted);
+        assert_eq!(heap.pop(), sorted.first().copied());
+    }
+}
+
+#[test]
+fn test_contains() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let heap = WeakHeap::from(elements.clone());
+        #[cfg(feature = "std")]
+        let counting: CountingWeakHeap<_> = elements.iter().copied().collect();
+        for value in -35..=35 {
+            let expected = elements.contains(&value);
+            assert_eq!(heap.contains(&value), expected);
+            #[cfg(feature = "std")]
+            assert_eq!(counting.contains(&value), expected);
+        }
+    }
+}
+
+#[test]
+fn test_remove() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut heap = WeakHeap::from(elements.clone());
+        let mut remaining = elements;
+        for _ in 0..size + 5 {
+            let value = rng.gen_range(-35..=35);
+            let expected = remaining
+                .iter()
+                .position(|&x| x == value)
+                .map(|i| remaining.swap_remove(i));
+            assert_eq!(heap.remove(&value), expected);
+            assert!(is_weak_heap(&heap.data, &heap.bit));
+        }
+        remaining.sort_unstable();
+        assert_eq!(heap.into_sorted_vec(), remaining);
+    }
+}
+
+#[test]
+fn test_into_sorted_vec_limit() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let heap = WeakHeap::from(elements.clone());
+        let mut sorted = elements;
+        sorted.sort_unstable();
+
+        for k in [0, 1, 5, size / 2, size, size + 3] {
+            let expected = &sorted[size - k.min(size)..];
+            assert_eq!(heap.clone().into_sorted_vec_limit(k), expected);
+        }
+    }
+}
+
+#[test]
+fn test_select_nth() {
+    let mut rng = thread_rng();
+    for size in 1..300usize {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut sorted = elements.clone();
+        sorted.sort_unstable();
+
+        for n in [0, 1, size / 3, size / 2, size.saturating_sub(2), size - 1] {
+            let n = n.min(size - 1);
+            let mut data = elements.clone();
+            let (smaller, nth, larger) = select::nth(&mut data, n);
+            assert_eq!(*nth, sorted[n]);
+            assert!(smaller.iter().all(|x| x <= nth));
+            assert!(larger.iter().all(|x| x >= nth));
+            data.sort_unstable();
+            assert_eq!(data, sorted);
+        }
+    }
+}
+
+#[test]
+fn test_median_heap() {
+    let mut rng = thread_rng();
+    let mut heap = MedianHeap::new();
+    let mut reference: Vec<i64> = Vec::new();
+    for _ in 0..2000 {
+        if rng.gen_bool(0.6) {
+            let x = rng.gen_range(-30..=30);
+            heap.push(x);
+            let pos = reference.partition_point(|&y| y <= x);
+            reference.insert(pos, x);
+        } else {
+            let expected =
+                (!reference.is_empty()).then(|| reference.remove((reference.len() - 1) / 2));
+            assert_eq!(heap.pop_median(), expected);
+        }
+        assert_eq!(heap.len(), reference.len());
+        assert_eq!(
+            heap.median(),
+            reference
+                .len()
+                .checked_sub(1)
+                .map(|last| &reference[last / 2])
+        );
+    }
+}
+
+#[test]
+fn test_stable_weak_heap() {
+    // Ordered by the key only, the payload records the insertion order.
+    #[derive(Debug)]
+    struct Item(i64, usize);
+
+    impl PartialEq for Item {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+    impl Eq for Item {}
+    impl PartialOrd for Item {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Item {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    let mut rng = thread_rng();
+    let mut heap = StableWeakHeap::new();
+    let mut reference: Vec<(i64, usize)> = Vec::new();
+    for n in 0..3000 {
+        if rng.gen_bool(0.6) {
+            let key = rng.gen_range(0..5);
+            heap.push(Item(key, n));
+            reference.push((key, n));
+        } else {
+            // The greatest key, the smallest insertion number among equal keys.
+            let best = (0..reference.len())
+                .max_by_key(|&i| (reference[i].0, std::cmp::Reverse(reference[i].1)));
+            let expected = best.map(|i| reference.remove(i));
+            assert_eq!(heap.pop().map(|Item(key, n)| (key, n)), expected);
+        }
+        assert_eq!(heap.len(), reference.len());
+    }
+
+    let heap: StableWeakHeap<Item> = (0..20).map(|n| Item(n % 3, n as usize)).collect();
+    let sorted: Vec<_> = heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Item(key, n)| (key, n))
+        .collect();
+    assert!(sorted
+        .windows(2)
+        .all(|w| w[0].0 < w[1].0 || (w[0].0 == w[1].0 && w[0].1 > w[1].1)));
+}
+
+#[test]
+fn test_lazy_weak_heap() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut heap: LazyWeakHeap<_> = elements.iter().copied().collect();
+        let mut live = elements;
+        for step in 0..20 {
+            if step % 3 == 0 {
+                let limit = rng.gen_range(-30..=30);
+                let before = live.len();
+                live.retain(|&x| x < limit);
+                assert_eq!(heap.mark_deleted(|&x| x >= limit), before - live.len());
+            } else if step % 3 == 1 {
+                let item = rng.gen_range(-30..=30);
+                heap.push(item);
+                live.push(item);
+            } else {
+                live.sort_unstable();
+                assert_eq!(heap.pop(), live.pop());
+            }
+            live.sort_unstable();
+            assert_eq!(heap.len(), live.len());
+            assert_eq!(heap.peek(), live.last());
+        }
+        assert_eq!(heap.into_sorted_vec(), live);
+    }
+}
+
+#[test]
+fn test_relaxed_weak_queue() {
+    let mut rng = thread_rng();
+    for _ in 0..50 {
+        let mut queue = RelaxedWeakQueue::new();
+        let mut model: Vec<(Handle, i64)> = Vec::new();
+        for _ in 0..300 {
+            match rng.gen_range(0..10) {
+                0..=3 => {
+                    let item = rng.gen_range(-1000..1000);
+                    model.push((queue.push(item), item));
+                }
+                4..=6 if !model.is_empty() => {
+                    let i = rng.gen_range(0..model.len());
+                    let (handle, item) = model[i];
+                    let new = item + rng.gen_range(0..500);
+                    assert_eq!(queue.increase_key(handle, new), item);
+                    model[i].1 = new;
+                }
+                7 if !model.is_empty() => {
+                    let (handle, item) = model.swap_remove(rng.gen_range(0..model.len()));
+                    assert_eq!(queue.remove(handle), Some(item));
+                    assert_eq!(queue.remove(handle), None);
+                }
+                _ => {
+                    let expected = model.iter().map(|&(_, item)| item).max();
+                    let popped = queue.pop_with_handle();
+                    assert_eq!(popped.map(|(_, item)| item), expected);
+                    if let Some((handle, _)) = popped {
+                        let i = model.iter().position(|&(h, _)| h == handle).unwrap();
+                        assert_eq!(model.swap_remove(i).1, expected.unwrap());
+                    }
+                }
+            }
+            assert_eq!(queue.len(), model.len());
+            assert_eq!(queue.peek(), model.iter().map(|(_, item)| item).max());
+            for &(handle, item) in &model {
+                assert_eq!(queue.get(handle), Some(&item));
+            }
+        }
+        let mut rest: Vec<i64> = model.iter().map(|&(_, item)| item).collect();
+        rest.sort_unstable();
+        assert_eq!(queue.into_sorted_vec(), rest);
+    }
+}
+
+#[test]
+fn test_weak_queue() {
+    let mut rng = thread_rng();
+    for _ in 0..50 {
+        let mut queue = WeakQueue::new();
+        let mut model = Vec::new();
+        for _ in 0..300 {
+            if rng.gen_range(0..3) == 0 {
+                model.sort_unstable();
+                assert_eq!(queue.pop(), model.pop());
+            } else {
+                let item = rng.gen_range(-100..100);
+                queue.push(item);
+                model.push(item);
+            }
+            assert_eq!(queue.len(), model.len());
+            assert_eq!(queue.peek(), model.iter().max());
+        }
+        let mut items: Vec<_> = queue.iter().copied().collect();
+        items.sort_unstable();
+        model.sort_unstable();
+        assert_eq!(items, model);
+        assert_eq!(queue.into_sorted_vec(), model);
+    }
+}
+
+#[test]
+fn test_weak_queue_api() {
+    let mut rng = thread_rng();
+    for size in 0..60 {
+        let elements: Vec<i32> = (0..size).map(|_| rng.gen_range(-50..50)).collect();
+        let mut queue = WeakQueue::from(elements.clone());
+        let mut model = elements.clone();
+        for _ in 0..size {
+            let value = rng.gen_range(-60..60);
+            match rng.gen_range(0..4) {
+                0 => {
+                    // Pushes without a pop keep some elements in the buffer.
+                    queue.push(value);
+                    model.push(value);
+                }
+                1 => {
+                    model.sort_unstable();
+                    let popped = queue.peek_mut().map(WeakQueuePeekMut::pop);
+                    assert_eq!(popped, model.pop());
+                }
+                2 => {
+                    if let Some(mut top) = queue.peek_mut() {
+                        *top = value;
+                        model.sort_unstable();
+                        *model.last_mut().unwrap() = value;
+                    }
+                }
+                _ => {
+                    if let Some(mut top) = queue.peek_mut() {
+                        *top = value;
+                        let popped = WeakQueuePeekMut::pop(top);
+                        assert_eq!(popped, value);
+                        model.sort_unstable();
+                        model.pop();
+                    }
+                }
+            }
+            assert_eq!(queue.len(), model.len());
+            assert_eq!(queue.peek(), model.iter().max());
+        }
+
+        let mut other: WeakQueue<i32> = elements.iter().map(|x| x * 2).collect();
+        queue.append(&mut other);
+        model.extend(elements.iter().map(|x| x * 2));
+        assert!(other.is_empty());
+
+        let mut drained: Vec<_> = queue.clone().drain().collect();
+        let mut items: Vec<_> = queue.clone().into_iter().collect();
+        drained.sort_unstable();
+        items.sort_unstable();
+        model.sort_unstable();
+        assert_eq!(drained, model);
+        assert_eq!(items, model);
+        assert_eq!(queue.into_sorted_vec(), model);
+    }
+
+    // A leaked guard keeps every element, and the next pop is the greatest.
+    let mut queue = WeakQueue::from([3, 9, 4, 1]);
+    queue.push(7);
+    let mut top = queue.peek_mut().unwrap();
+    *top = 0;
+    std::mem::forget(top);
+    assert_eq!(queue.pop(), Some(7));
+    assert_eq!(queue.len(), 4);
+    assert_eq!(queue.into_sorted_vec(), [0, 1, 3, 4]);
+}
+
+#[test]
+fn test_drain_while() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let limit = rng.gen_range(-35..=35);
+        let mut heap = WeakHeap::from(elements.clone());
+        let mut sorted = elements;
+        sorted.sort_unstable();
+        let split = sorted.partition_point(|&x| x < limit);
+
+        let drained: Vec<_> = heap.drain_while(|&x| x >= limit).collect();
+        let expected: Vec<_> = sorted[split..].iter().rev().copied().collect();
+        assert_eq!(drained, expected);
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+        assert_eq!(heap.into_sorted_vec(), &sorted[..split]);
+    }
+
+    // The iterator stops for good at the first mismatch.
+    let mut heap = WeakHeap::from(vec![5, 4, 3]);
+    let mut iter = heap.drain_while(|&x| x != 4);
+    assert_eq!(iter.next(), Some(5));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next(), None);
+    assert_eq!(heap.len(), 2);
+}
+
+#[test]
+fn test_prune() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let bound = rng.gen_range(-35..=35);
+        let mut heap = WeakHeap::from(elements.clone());
+        let capacity = heap.capacity();
+        heap.prune(&bound);
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+        assert_eq!(heap.capacity(), capacity);
+
+        let mut expected: Vec<_> = elements.into_iter().filter(|&x| x >= bound).collect();
+        expected.sort_unstable();
+        assert_eq!(heap.into_sorted_vec(), expected);
+    }
+}
+
+#[test]
+fn test_pop_all_max() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(0..5)).collect();
+        let mut heap = WeakHeap::from(elements.clone());
+        let mut sorted = elements;
+        sorted.sort_unstable();
+        while let Some(&max) = sorted.last() {
+            let split = sorted.partition_point(|&x| x < max);
+            assert_eq!(heap.pop_all_max(), &sorted[split..]);
+            assert!(is_weak_heap(&heap.data, &heap.bit));
+            sorted.truncate(split);
+            assert_eq!(heap.len(), sorted.len());
+        }
+        assert!(heap.pop_all_max().is_empty());
+    }
+}
+
+#[test]
+fn test_truncate_top() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut sorted = elements.clone();
+        sorted.sort_unstable();
+        for k in [0, 1, 5, size / 2, size, size + 3] {
+            let mut heap = WeakHeap::from(elements.clone());
+            heap.truncate_top(k);
+            assert!(is_weak_heap(&heap.data, &heap.bit));
+            assert_eq!(heap.into_sorted_vec(), &sorted[size - k.min(size)..]);
+        }
+    }
+}
+
+#[test]
+fn test_multiset_eq() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(0..10)).collect();
+        let heap = WeakHeap::from(elements.clone());
+        let mut pushed = WeakHeap::new();
+        pushed.extend(elements.iter().rev().copied());
+
+        assert_eq!(heap, heap.clone());
+        assert_eq!(heap, pushed);
+        if let Some(top) = pushed.pop() {
+            assert_ne!(heap, pushed);
+            pushed.push(top + 1);
+            assert_ne!(heap, pushed);
+        }
+    }
+}
+
+#[test]
+fn test_binary_heap_conversions() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut sorted = elements.clone();
+        sorted.sort_unstable();
+
+        let heap = WeakHeap::from(BinaryHeap::from(elements.clone()));
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+        assert_eq!(heap.into_sorted_vec(), sorted);
+
+        let binary = BinaryHeap::from(WeakHeap::from(elements));
+        assert_eq!(binary.into_sorted_vec(), sorted);
+    }
+}
+
+#[test]
+fn test_from_btree() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let set: BTreeSet<i64> = (0..size).map(|_| rng.gen_range(-100..=100)).collect();
+        let heap = WeakHeap::from(set.clone());
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+        assert_eq!(
+            heap.into_sorted_vec(),
+            set.iter().copied().collect::<Vec<_>>()
+        );
+
+        let map: BTreeMap<i64, i64> = set.iter().map(|&k| (k, rng.gen_range(0..10))).collect();
+        let heap = WeakHeap::from(map.clone());
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+        assert_eq!(heap.into_sorted_vec(), map.into_iter().collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn test_weak_heap_macro() {
+    let heap = crate::weak_heap![3, 1, 4, 1, 5, 9, 2, 6];
+    assert!(is_weak_heap(&heap.data, &heap.bit));
+    assert_eq!(heap.capacity(), 8);
+    assert_eq!(heap.into_sorted_vec(), [1, 1, 2, 3, 4, 5, 6, 9]);
+
+    let mut heap = crate::weak_heap![min: 3, 1, 4,];
+    assert_eq!(heap.pop(), Some(std::cmp::Reverse(1)));
+    assert_eq!(heap.pop(), Some(std::cmp::Reverse(3)));
+
+    let heap: WeakHeap<i32> = crate::weak_heap![];
+    assert!(heap.is_empty());
+    let heap: WeakHeap<std::cmp::Reverse<i32>> = crate::weak_heap![min:];
+    assert!(heap.is_empty());
+}
+
+#[test]
+fn test_min_weak_heap() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-30..=30)).collect();
+        let mut sorted = elements.clone();
+        sorted.sort_unstable();
+
+        let heap = MinWeakHeap::from(elements.clone());
+        assert_eq!(heap.len(), size);
+        assert_eq!(heap.peek(), sorted.first());
+        assert_eq!(heap.clone().into_sorted_vec(), sorted);
+
+        let mut pushed = MinWeakHeap::new();
+        pushed.extend(elements);
+        let popped: Vec<_> = std::iter::from_fn(|| pushed.pop()).collect();
+        assert_eq!(popped, sorted);
+    }
+}
+
+#[test]
+fn test_total_ord() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<f64> = (0..size).map(|_| rng.gen_range(-1e3..1e3)).collect();
+        let mut sorted = elements.clone();
+        sorted.sort_by(f64::total_cmp);
+
+        let heap: WeakHeap<_> = elements.iter().copied().map(TotalOrd).collect();
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+        let result: Vec<f64> = heap.into_sorted_vec().into_iter().map(|x| x.0).collect();
+        assert_eq!(result, sorted);
+    }
+
+    let mut heap = WeakHeap::new();
+    for x in [
+        0.0f32,
+        -0.0,
+        f32::NAN,
+        -f32::NAN,
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+    ] {
+        heap.push(TotalOrd(x));
+    }
+    assert!(heap.pop().unwrap().0.is_nan());
+    assert_eq!(heap.pop(), Some(TotalOrd(f32::INFINITY)));
+    let zero = heap.pop().unwrap().0;
+    assert!(zero == 0.0 && zero.is_sign_positive());
+    let zero = heap.pop().unwrap().0;
+    assert!(zero == 0.0 && zero.is_sign_negative());
+    assert_eq!(heap.pop(), Some(TotalOrd(f32::NEG_INFINITY)));
+    assert!(heap.pop().unwrap().0.is_nan());
+    assert!(heap.is_empty());
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn test_stats() {
+    use crate::{reset_stats, stats, Stats};
+
+    reset_stats();
+    assert_eq!(stats(), Stats::default());
+
+    let mut heap = WeakHeap::from((0..1000).collect::<Vec<_>>());
+    let built = stats();
+    assert_eq!(built.joins, 999);
+    assert_eq!(built.swaps, built.bit_flips);
+    assert_eq!(built.sift_ups + built.sift_downs, 0);
+
+    reset_stats();
+    heap.push(1000);
+    let pushed = stats();
+    assert_eq!(pushed.sift_ups, 1);
+    assert!(pushed.sift_up_moves >= 1 && pushed.sift_up_moves <= 10);
+    assert_eq!(pushed.swaps + pushed.bit_flips + pushed.sift_downs, 0);
+
+    reset_stats();
+    let mut popped = 0;
+    while heap.pop().is_some() {
+        popped += 1;
+    }
+    let drained = stats();
+    assert_eq!(drained.sift_downs, popped - 2);
+    assert!(drained.sift_down_depth <= drained.sift_downs * 10);
+    assert!(drained.swaps <= drained.sift_down_depth);
+    assert_eq!(drained.swaps, drained.bit_flips);
+}
+
+#[test]
+fn test_debug_tree() {
+    let heap: WeakHeap<i32> = WeakHeap::new();
+    assert_eq!(format!("{:#?}", heap), "[]");
+
+    let heap = WeakHeap::from(vec![7]);
+    assert_eq!(format!("{:#?}", heap), "[0] 7\n");
+
+    let mut rng = thread_rng();
+    for size in 1..100 {
+        let heap: WeakHeap<i32> = (0..size).map(|_| rng.gen_range(0..100)).collect();
+        let tree = format!("{:#?}", heap);
+        let lines: Vec<&str> = tree.lines().collect();
+        assert_eq!(lines.len(), heap.len());
+
+        // Every index appears once, with its element and reverse bit.
+        let mut seen = vec![false; heap.len()];
+        for line in lines {
+            let start = line.find('[').unwrap();
+            let end = line.find(']').unwrap();
+            let pos: usize = line[start + 1..end].parse().unwrap();
+            assert!(!seen[pos]);
+            seen[pos] = true;
+            let rest = line[end + 2..].trim_end_matches(" (reversed)");
+            assert_eq!(rest.parse::<i32>().unwrap(), heap.data[pos]);
+            assert_eq!(
+                line.ends_with(" (reversed)"),
+                heap.bit.get(pos) == Some(true)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_check_integrity() {
+    use crate::WeakHeapError;
+
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i32> = (0..size).map(|_| rng.gen_range(0..50)).collect();
+        let mut heap = WeakHeap::from(elements.clone());
+        assert_eq!(heap.check_integrity(), Ok(()));
+        heap.extend(elements.iter().copied());
+        heap.pop();
+        heap.retain(|&x| x % 3 != 0);
+        heap.extract_if(|&x| x % 2 == 0).for_each(drop);
+        assert_eq!(heap.check_integrity(), Ok(()));
+
+        let (data, bits) = heap.into_raw_parts();
+        let heap = WeakHeap::try_from_raw_parts(data, bits).unwrap();
+        assert_eq!(heap.check_integrity(), Ok(()));
+    }
+
+    // The only element is removed from a two element heap with a set bit.
+    let mut heap =
+        unsafe { WeakHeap::from_raw_parts(vec![2, 1], [false, true].into_iter().collect()) };
+    heap.extract_if(|&x| x == 2).for_each(drop);
+    assert_eq!(heap.check_integrity(), Ok(()));
+
+    assert_eq!(
+        WeakHeap::try_from_raw_parts(vec![1, 2], [false; 2].into_iter().collect()),
+        Err(WeakHeapError::HeapOrder {
+            pos: 1,
+            ancestor: 0
+        })
+    );
+    assert_eq!(
+        WeakHeap::try_from_raw_parts(vec![2, 1], [true, false].into_iter().collect()),
+        Err(WeakHeapError::RootBitSet)
+    );
+    assert_eq!(
+        WeakHeap::try_from_raw_parts(vec![2, 1], [false].into_iter().collect()),
+        Err(WeakHeapError::LengthMismatch { len: 2, bits: 1 })
+    );
+    assert_eq!(
+        WeakHeap::try_from_raw_parts(vec![3, 1, 2], [false, true, false].into_iter().collect()),
+        Err(WeakHeapError::HeapOrder {
+            pos: 2,
+            ancestor: 1
+        })
+    );
+}
+
+#[cfg(all(feature = "debug-invariants", debug_assertions))]
+#[test]
+#[should_panic(expected = "weak heap corrupted after `push`")]
+fn test_debug_invariants() {
+    use std::cell::Cell;
+    use std::cmp::Ordering;
+
+    // A key which can be changed while in the heap.
+    #[derive(Debug)]
+    struct Key(Cell<i32>);
+
+    impl PartialEq for Key {
+        fn eq(&self, other: &Self) -> bool {
+            self.0.get() == other.0.get()
+        }
+    }
+    impl Eq for Key {}
+    impl PartialOrd for Key {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Key {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.get().cmp(&other.0.get())
+        }
+    }
+
+    let mut heap: WeakHeap<Key> = (0..10).map(|x| Key(Cell::new(x))).collect();
+    heap.pop();
+    heap.append_vec(&mut vec![Key(Cell::new(3))]);
+    *heap.peek_mut().unwrap() = Key(Cell::new(20));
+
+    heap.iter().find(|key| key.0.get() == 0).unwrap().0.set(100);
+    heap.push(Key(Cell::new(-1)));
+}
+
+#[test]
+fn test_peek_mut_leak() {
+    let mut heap = WeakHeap::from(vec![1, 5, 2, 4, 3]);
+
+    // Leaking an unmodified guard changes nothing.
+    std::mem::forget(heap.peek_mut().unwrap());
+    assert_eq!(heap.len(), 5);
+    assert_eq!(heap.check_integrity(), Ok(()));
+
+    // Leaking a modified guard leaves a usable heap holding the modified item.
+    let mut top = heap.peek_mut().unwrap();
+    *top = 0;
+    std::mem::forget(top);
+    assert_eq!(heap.len(), 1);
+    assert_eq!(heap.peek(), Some(&0));
+    assert_eq!(heap.check_integrity(), Ok(()));
+    heap.extend([3, 1, 2]);
+    assert_eq!(heap.into_sorted_vec(), [0, 1, 2, 3]);
+
+    // The same with more than one word of reverse bits.
+    let mut heap: WeakHeap<i32> = (0..200).collect();
+    let mut top = heap.peek_mut().unwrap();
+    *top = -1;
+    std::mem::forget(top);
+    assert_eq!(heap.check_integrity(), Ok(()));
+    heap.extend(0..100);
+    assert_eq!(heap.check_integrity(), Ok(()));
+    assert_eq!(heap.into_sorted_vec(), (-1..100).collect::<Vec<_>>());
+
+    // A modified guard which is dropped restores every element.
+    let mut heap = WeakHeap::from(vec![1, 5, 2, 4, 3]);
+    let mut top = heap.peek_mut().unwrap();
+    *top = 0;
+    assert_eq!(*top, 0);
+    *top = 6;
+    assert_eq!(format!("{:?}", top), "WeakHeapPeekMut(6)");
+    drop(top);
+    assert_eq!(heap.check_integrity(), Ok(()));
+    let mut top = heap.peek_mut().unwrap();
+    *top = 0;
+    assert_eq!(WeakHeapPeekMut::pop(top), 0);
+    assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4]);
+}
+
+#[test]
+fn test_begin_pop_leak() {
+    let mut heap = WeakHeap::from(vec![1, 5, 3, 9, 2]);
+
+    // Leaking an unmodified guard keeps every element in order.
+    std::mem::forget(heap.begin_pop().unwrap());
+    assert_eq!(heap.check_integrity(), Ok(()));
+    assert_eq!(heap.len(), 5);
+    assert_eq!(heap.pop(), Some(9));
+    assert_eq!(heap.pop(), Some(5));
+
+    // Leaking a modified guard leaves the modified item in a valid heap.
+    let mut pending = heap.begin_pop().unwrap();
+    *pending = 0;
+    std::mem::forget(pending);
+    assert_eq!(heap.check_integrity(), Ok(()));
+    heap.extend([4, 6]);
+    assert_eq!(heap.into_sorted_vec(), [0, 4, 6]);
+}
+
+#[test]
+fn test_peek_mut_refresh() {
+    let mut rng = thread_rng();
+    for size in 1..100 {
+        let elements: Vec<i32> = (0..size).map(|_| rng.gen_range(0..1000)).collect();
+        let mut heap = WeakHeap::from(elements.clone());
+        let mut expected = elements;
+
+        let mut top = heap.peek_mut().unwrap();
+        for _ in 0..3 * size {
+            // Unmodified refreshes keep the same item.
+            WeakHeapPeekMut::refresh(&mut top);
+            let max = expected.iter().copied().max().unwrap();
+            assert_eq!(*top, max);
+
+            let pos = expected.iter().position(|&x| x == max).unwrap();
+            expected[pos] = max / 2;
+            *top = max / 2;
+            WeakHeapPeekMut::refresh(&mut top);
+        }
+        drop(top);
+
+        assert_eq!(heap.check_integrity(), Ok(()));
+        expected.sort_unstable();
+        assert_eq!(heap.into_sorted_vec(), expected);
+    }
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn test_peek_mut_deferred_sift() {
+    use crate::{reset_stats, stats};
+
+    let mut heap: WeakHeap<i32> = (0..1000).collect();
+    reset_stats();
+    let mut top = heap.peek_mut().unwrap();
+    for _ in 0..100 {
+        *top -= 10;
+    }
+    assert_eq!(stats().sift_downs, 0);
+    drop(top);
+    assert_eq!(stats().sift_downs, 1);
+    assert_eq!(heap.check_integrity(), Ok(()));
+    assert!(heap.iter().any(|&x| x == 999 - 1000));
+    assert_eq!(heap.peek(), Some(&998));
+}
+
+#[test]
+fn test_iter_mut() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i32> = (0..size).map(|_| rng.gen_range(-50..50)).collect();
+        let mut heap = WeakHeap::from(elements.clone());
+
+        let mut iter = heap.iter_mut();
+        assert_eq!(iter.len(), size);
+        for x in &mut iter {
+            *x = -*x / 2;
+        }
+        drop(iter);
+        assert_eq!(heap.check_integrity(), Ok(()));
+
+        let mut expected: Vec<i32> = elements.iter().map(|x| -x / 2).collect();
+        expected.sort_unstable();
+        assert_eq!(heap.clone().into_sorted_vec(), expected);
+
+        // Partial iteration from the back.
+        let mut iter = heap.iter_mut();
+        if let Some(x) = iter.next_back() {
+            *x += 100;
+        }
+        drop(iter);
+        assert_eq!(heap.check_integrity(), Ok(()));
+        if size > 0 {
+            assert!(*heap.peek().unwrap() >= 75);
+        }
+        assert_eq!(heap.len(), size);
+    }
+
+    // Leaking the iterator leaves an empty, usable heap.
+    let mut heap = WeakHeap::from(vec![1, 5, 2]);
+    let mut iter = heap.iter_mut();
+    *iter.next().unwrap() = 0;
+    std::mem::forget(iter);
+    assert!(heap.is_empty());
+    heap.push(3);
+    assert_eq!(heap.check_integrity(), Ok(()));
+    assert_eq!(heap.pop(), Some(3));
+}
+
+#[test]
+fn test_map() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i32> = (0..size).map(|_| rng.gen_range(-50..50)).collect();
+        let mut heap = WeakHeap::with_capacity(2 * size);
+        heap.extend(elements.iter().copied());
+
+        let heap = heap.map(|x| (x * x, x.to_string()));
+        assert!(heap.capacity() >= 2 * size);
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+
+        let mut expected: Vec<_> = elements.iter().map(|x| (x * x, x.to_string())).collect();
+        expected.sort();
+        assert_eq!(heap.into_sorted_vec(), expected);
+    }
+}
+
+#[test]
+fn test_map_in_place() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i32> = (0..size).map(|_| rng.gen_range(-50..50)).collect();
+        let mut heap = WeakHeap::from(elements.clone());
+        heap.map_in_place(|x| *x = (*x * 7) % 13);
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+
+        let mut expected: Vec<_> = elements.iter().map(|x| (x * 7) % 13).collect();
+        expected.sort_unstable();
+        assert_eq!(heap.into_sorted_vec(), expected);
+    }
+
+    // A panicking closure still leaves a valid heap.
+    let mut heap = WeakHeap::from((0..100).collect::<Vec<_>>());
+    let mut calls = 0;
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        heap.map_in_place(|x| {
+            calls += 1;
+            if calls == 50 {
+                panic!("stop");
+            }
+            *x = -*x;
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(heap.len(), 100);
+    assert_eq!(heap.check_integrity(), Ok(()));
+}
+
+#[test]
+fn test_retain_mut() {
+    let mut rng = thread_rng();
+    for size in 0..100 {
+        let elements: Vec<i32> = (0..size).map(|_| rng.gen_range(0..10)).collect();
+        let mut heap = WeakHeap::from(elements.clone());
+        heap.retain_mut(|x| {
+            *x = 9 - *x;
+            *x % 3 != 0
+        });
+        assert!(is_weak_heap(&heap.data, &heap.bit));
+
+        let mut expected: Vec<_> = elements
+            .iter()
+            .map(|x| 9 - x)
+            .filter(|x| x % 3 != 0)
+            .collect();
+        expected.sort_unstable();
+        assert_eq!(heap.into_sorted_vec(), expected);
+    }
+
+    // A panicking closure leaves a valid heap behind, modifications included.
+    let mut heap: WeakHeap<i32> = (0..100).collect();
+    let mut calls = 0;
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        heap.retain_mut(|x| {
+            calls += 1;
+            assert!(calls < 50);
+            *x += 1000;
+            *x % 2 == 0
+        });
+    }));
+    assert!(result.is_err());
+    assert_eq!(heap.check_integrity(), Ok(()));
+    assert_eq!(heap.len(), 75);
+    assert!(*heap.peek().unwrap() >= 1000);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_par_into_sorted_vec() {
+    use crate::par::PAR_THRESHOLD;
+
+    let mut rng = thread_rng();
+    for size in [
+        0,
+        1,
+        100,
+        PAR_THRESHOLD - 1,
+        PAR_THRESHOLD,
+        3 * PAR_THRESHOLD + 7,
+    ] {
+        let elements: Vec<i64> = (0..size).map(|_| rng.gen_range(-1000..1000)).collect();
+        let heap = WeakHeap::from(elements.clone());
+        let mut expected = elements;
+        expected.sort_unstable();
+        assert_eq!(heap.par_into_sorted_vec(), expected);
+    }
+}
+
+#[cfg(feature = "asyncqueue")]
+#[test]
+fn test_async_weak_heap() {
+    use crate::AsyncWeakHeap;
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    let queue = Arc::new(AsyncWeakHeap::new());
+    let consumers: Vec<_> = (0..4)
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                (0..250)
+                    .map(|_| block_on(queue.pop()))
+                    .collect::<Vec<i32>>()
+            })
+        })
+        .collect();
+    for x in 0..1000 {
+        queue.push(x);
+    }
+
+    let mut popped: Vec<i32> = consumers
+        .into_iter()
+        .flat_map(|c| c.join().unwrap())
+        .collect();
+    popped.sort_unstable();
+    assert_eq!(popped, (0..1000).collect::<Vec<_>>());
+    assert!(queue.is_empty());
+
+    // An abandoned `pop` loses nothing.
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut pop = std::pin::pin!(queue.pop());
+    assert_eq!(pop.as_mut().poll(&mut cx), Poll::Pending);
+    queue.push(5);
+    queue.push(7);
+    assert_eq!(block_on(queue.pop()), 7);
+    assert_eq!(queue.len(), 1);
+    let queue = Arc::try_unwrap(queue).unwrap();
+    assert_eq!(queue.into_inner().into_vec(), [5]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_priority_channel() {
+    use crate::sync::{bounded_priority_channel, priority_channel};
+    use std::sync::mpsc::{RecvError, SendError, TryRecvError, TrySendError};
+    use std::thread;
+
+    // Values sent before receiving come out in priority order.
+    let (tx, rx) = priority_channel();
+    let mut rng = thread_rng();
+    let values: Vec<i32> = (0..500).map(|_| rng.gen_range(0..100)).collect();
+    for &x in &values {
+        tx.send(x).unwrap();
+    }
+    assert_eq!(rx.len(), values.len());
+    let mut sorted = values.clone();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    let received: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+    assert_eq!(received, sorted);
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    drop(tx);
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    assert_eq!(rx.recv(), Err(RecvError));
+
+    // Several producers on a bounded channel.
+    let (tx, rx) = bounded_priority_channel(4);
+    let producers: Vec<_> = (0..4)
+        .map(|id| {
+            let tx = tx.clone();
+            thread::spawn(move || {
+                for x in 0..250 {
+                    tx.send(id * 1000 + x).unwrap();
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+    let mut received: Vec<i32> = rx.iter().collect();
+    for producer in producers {
+        producer.join().unwrap();
+    }
+    received.sort_unstable();
+    let expected: Vec<i32> = (0..4)
+        .flat_map(|id| (0..250).map(move |x| id * 1000 + x))
+        .collect();
+    assert_eq!(received, expected);
+
+    // A full channel, then a dropped receiver.
+    let (tx, rx) = bounded_priority_channel(1);
+    tx.try_send(1).unwrap();
+    assert_eq!(tx.try_send(2), Err(TrySendError::Full(2)));
+    let blocked = thread::spawn(move || tx.send(3));
+    drop(rx);
+    assert_eq!(blocked.join().unwrap(), Err(SendError(3)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_concurrent_weak_heap() {
+    use std::sync::Arc;
+    use std::thread;
+
+    let queue = Arc::new(ConcurrentWeakHeap::with_shards(4));
+    let workers: Vec<_> = (0..4)
+        .map(|id| {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || {
+                let mut popped = Vec::new();
+                for x in 0..500 {
+                    queue.push(id * 1000 + x);
+                    if x % 3 == 0 {
+                        popped.extend(queue.pop_relaxed());
+                    }
+                }
+                popped
+            })
+        })
+        .collect();
+    let mut all: Vec<i32> = workers
+        .into_iter()
+        .flat_map(|w| w.join().unwrap())
+        .collect();
+    let left = queue.len();
+    assert_eq!(all.len() + left, 2000);
+
+    // The exact pop returns the remaining elements in order.
+    let mut previous = None;
+    while let Some(x) = queue.pop() {
+        assert!(previous.is_none_or(|p| p >= x));
+        previous = Some(x);
+        all.push(x);
+    }
+    assert!(queue.is_empty());
+    all.sort_unstable();
+    let expected: Vec<i32> = (0..4)
+        .flat_map(|id| (0..500).map(move |x| id * 1000 + x))
+        .collect();
+    assert_eq!(all, expected);
+
+    let queue = ConcurrentWeakHeap::with_shards(3);
+    queue.extend_from(0..10);
+    queue.push(20);
+    assert_eq!(queue.into_heap().into_sorted_vec().last(), Some(&20));
+}
+
+#[cfg(feature = "proptest")]
+#[test]
+fn test_proptest_strategies() {
+    use crate::proptest::{adversarial, duplicate_heavy, weak_heap};
+    use ::proptest::prelude::*;
+    use ::proptest::test_runner::TestRunner;
+
+    fn check(heap: WeakHeap<i16>) -> Result<(), TestCaseError> {
+        prop_assert_eq!(heap.check_integrity(), Ok(()));
+        let mut sorted = heap.clone().into_vec();
+        sorted.sort_unstable();
+        prop_assert_eq!(heap.into_sorted_vec(), sorted);
+        Ok(())
+    }
+
+    let mut runner = TestRunner::default();
+    runner.run(&weak_heap(any::<i16>(), 0..200), check).unwrap();
+    runner
+        .run(&adversarial(any::<i16>(), 0..200), check)
+        .unwrap();
+    runner.run(&any::<WeakHeap<i16>>(), check).unwrap();
+    runner
+        .run(&duplicate_heavy(any::<i16>(), 4, 1..200), |heap| {
+            let mut distinct = heap.clone().into_sorted_vec();
+            distinct.dedup();
+            prop_assert!(distinct.len() <= 4);
+            check(heap)
+        })
+        .unwrap();
+}
+
+#[cfg(feature = "quickcheck")]
+#[test]
+fn test_quickcheck_arbitrary() {
+    use quickcheck::{Arbitrary, Gen, QuickCheck};
+
+    fn sorts(heap: WeakHeap<i16>) -> bool {
+        let mut sorted = heap.clone().into_vec();
+        sorted.sort_unstable();
+        heap.check_integrity().is_ok() && heap.into_sorted_vec() == sorted
+    }
+    QuickCheck::new()
+        .tests(200)
+        .quickcheck(sorts as fn(WeakHeap<i16>) -> bool);
+
+    let mut g = Gen::new(50);
+    for _ in 0..20 {
+        let heap = WeakHeap::<i16>::arbitrary(&mut g);
+        for smaller in heap.shrink().take(100) {
+            assert_eq!(smaller.check_integrity(), Ok(()));
+            assert!(smaller.len() <= heap.len());
+        }
+    }
+    let heap = WeakHeap::from(vec![3, 1, 2]);
+    assert!(heap.shrink().any(|smaller| smaller.len() < heap.len()));
+}
@@ -0,0 +1,92 @@
+//! A total order for floating point numbers.
+
+use core::cmp::Ordering;
+use core::fmt;
+
+/// A wrapper which orders `f32` and `f64` values by their total order, so
+/// that they can be stored in a [`WeakHeap`].
+///
+/// The comparison is [`f64::total_cmp`] (or [`f32::total_cmp`]), the
+/// `totalOrder` predicate of IEEE 754:
+///
+/// * `-0.0` is less than `+0.0`;
+/// * a NaN with the sign bit cleared is greater than `+∞`, so it is popped
+///   first from a max-heap;
+/// * a NaN with the sign bit set is less than `-∞`.
+///
+/// Two `TotalOrd` values are equal only if their bits are equal, so
+/// `TotalOrd(f64::NAN) == TotalOrd(f64::NAN)` holds while `0.0 == -0.0` does
+/// not.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::{TotalOrd, WeakHeap};
+///
+/// let mut heap: WeakHeap<_> = [1.5, -2.0, 0.25].into_iter().map(TotalOrd).collect();
+/// heap.push(TotalOrd(f64::INFINITY));
+///
+/// assert_eq!(heap.pop(), Some(TotalOrd(f64::INFINITY)));
+/// assert_eq!(heap.pop().map(TotalOrd::into_inner), Some(1.5));
+///
+/// heap.push(TotalOrd(f64::NAN));
+/// assert!(heap.pop().unwrap().0.is_nan());
+/// ```
+///
+/// [`WeakHeap`]: crate::WeakHeap
+#[derive(Clone, Copy, Default)]
+pub struct TotalOrd<T>(pub T);
+
+impl<T> TotalOrd<T> {
+    /// Returns the wrapped value.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::TotalOrd;
+    /// assert_eq!(TotalOrd(2.5f32).into_inner(), 2.5);
+    /// ```
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TotalOrd<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T> From<T> for TotalOrd<T> {
+    fn from(value: T) -> TotalOrd<T> {
+        TotalOrd(value)
+    }
+}
+
+macro_rules! total_ord_impls {
+    ($($t:ty)*) => {$(
+        impl PartialEq for TotalOrd<$t> {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+
+        impl Eq for TotalOrd<$t> {}
+
+        impl PartialOrd for TotalOrd<$t> {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for TotalOrd<$t> {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.total_cmp(&other.0)
+            }
+        }
+    )*};
+}
+
+total_ord_impls! { f32 f64 }
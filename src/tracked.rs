@@ -0,0 +1,262 @@
+//! Weak heaps of handles with tracked positions, shared by the addressable
+//! containers of this crate.
+//!
+//! The elements live in a [`Slab`] and are identified by the index of their
+//! slot, the handle. A [`Tracked`] heap arranges handles and records the
+//! position of every handle, so that an arbitrary element can be located,
+//! re-sifted or removed in *O*(log(*n*)). Comparisons go through an
+//! `is_less` predicate over handles.
+
+use crate::raw;
+use alloc::vec::Vec;
+
+// Position of a handle which is not in the heap.
+const NONE: usize = usize::MAX;
+
+/// A weak heap of handles which knows the position of every handle.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Tracked {
+    heap: Vec<usize>,
+    bit: Vec<bool>,
+    // Position of every handle in `heap`, indexed by handle.
+    pos: Vec<usize>,
+}
+
+impl Tracked {
+    pub(crate) fn with_capacity(capacity: usize) -> Tracked {
+        Tracked {
+            heap: Vec::with_capacity(capacity),
+            bit: Vec::with_capacity(capacity),
+            pos: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Returns the handle at the root.
+    pub(crate) fn top(&self) -> Option<usize> {
+        self.heap.first().copied()
+    }
+
+    /// Returns the handles in heap order.
+    pub(crate) fn handles(&self) -> &[usize] {
+        &self.heap
+    }
+
+    pub(crate) fn contains(&self, handle: usize) -> bool {
+        self.pos.get(handle).is_some_and(|&pos| pos != NONE)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.heap.clear();
+        self.bit.clear();
+        self.pos.clear();
+    }
+
+    /// Inserts a handle which is not in the heap yet.
+    pub(crate) fn push<F>(&mut self, handle: usize, is_less: &mut F)
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        debug_assert!(!self.contains(handle));
+        if self.pos.len() <= handle {
+            self.pos.resize(handle + 1, NONE);
+        }
+        let pos = self.heap.len();
+        self.heap.push(handle);
+        self.bit.push(false);
+        self.pos[handle] = pos;
+        self.sift_up(pos, is_less);
+    }
+
+    /// Removes a handle from the heap.
+    pub(crate) fn remove<F>(&mut self, handle: usize, is_less: &mut F)
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        let pos = self.pos[handle];
+        debug_assert_ne!(pos, NONE);
+        let last = self.heap.len() - 1;
+        self.swap(pos, last);
+        self.heap.pop();
+        self.bit.pop();
+        self.pos[handle] = NONE;
+        if pos < last {
+            self.sift(pos, is_less);
+        }
+    }
+
+    /// Restores the heap property after the element of `handle` has changed.
+    pub(crate) fn update<F>(&mut self, handle: usize, is_less: &mut F)
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        self.sift(self.pos[handle], is_less);
+    }
+
+    /// Restores the heap property after the element of `handle` has increased.
+    pub(crate) fn increased<F>(&mut self, handle: usize, is_less: &mut F)
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        self.sift_up(self.pos[handle], is_less);
+    }
+
+    /// Restores the heap property after the element of `handle` has decreased.
+    pub(crate) fn decreased<F>(&mut self, handle: usize, is_less: &mut F)
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        self.sift_down(self.pos[handle], is_less);
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.pos[self.heap[a]] = a;
+        self.pos[self.heap[b]] = b;
+    }
+
+    fn d_ancestor(&self, pos: usize) -> usize {
+        debug_assert!(0 < pos && pos < self.bit.len());
+        // SAFETY: 0 < pos < self.bit.len() is checked by the callers.
+        unsafe { raw::d_ancestor(&self.bit, pos) }
+    }
+
+    fn sift<F>(&mut self, pos: usize, is_less: &mut F)
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        if pos > 0 && is_less(self.heap[self.d_ancestor(pos)], self.heap[pos]) {
+            self.sift_up(pos, is_less);
+        } else {
+            self.sift_down(pos, is_less);
+        }
+    }
+
+    fn sift_up<F>(&mut self, mut pos: usize, is_less: &mut F)
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        while pos > 0 {
+            let ancestor = self.d_ancestor(pos);
+            if is_less(self.heap[ancestor], self.heap[pos]) {
+                self.swap(ancestor, pos);
+                pos = ancestor;
+            } else {
+                break;
+            }
+        }
+    }
+
+    // Same as `raw::sift_down_range`, keeping the positions up to date.
+    fn sift_down<F>(&mut self, pos: usize, is_less: &mut F)
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        let end = self.heap.len();
+        let mut cur = if pos == 0 {
+            1
+        } else {
+            2 * pos + 1 - self.bit[pos] as usize
+        };
+        if cur >= end {
+            return;
+        }
+
+        while cur * 2 + (self.bit[cur] as usize) < end {
+            cur = 2 * cur + (self.bit[cur] as usize);
+        }
+
+        while cur > pos {
+            if is_less(self.heap[pos], self.heap[cur]) {
+                self.bit[cur] ^= true;
+                self.swap(pos, cur);
+            }
+            cur /= 2;
+        }
+    }
+}
+
+/// Storage for elements identified by stable slot indices.
+#[derive(Clone, Debug)]
+pub(crate) struct Slab<T> {
+    slots: Vec<Option<T>>,
+    // Indices of the vacant slots.
+    free: Vec<usize>,
+}
+
+impl<T> Default for Slab<T> {
+    fn default() -> Self {
+        Slab {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+}
+
+impl<T> Slab<T> {
+    pub(crate) fn with_capacity(capacity: usize) -> Slab<T> {
+        Slab {
+            slots: Vec::with_capacity(capacity),
+            free: Vec::new(),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, item: T) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.slots[index] = Some(item);
+                index
+            }
+            None => {
+                self.slots.push(Some(item));
+                self.slots.len() - 1
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, index: usize) -> T {
+        let item = self.slots[index].take().expect("vacant slot");
+        self.free.push(index);
+        item
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<&T> {
+        self.slots.get(index).and_then(Option::as_ref)
+    }
+
+    pub(crate) fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.slots.get_mut(index).and_then(Option::as_mut)
+    }
+
+    /// Returns the element of an occupied slot.
+    pub(crate) fn at(&self, index: usize) -> &T {
+        self.get(index).expect("vacant slot")
+    }
+
+    /// Returns the element of an occupied slot mutably.
+    pub(crate) fn at_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("vacant slot")
+    }
+
+    /// Returns an iterator over the occupied slots and their elements.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (usize, &T)> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| Some((index, slot.as_ref()?)))
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.slots.clear();
+        self.free.clear();
+    }
+
+    /// Removes all the elements, in slot order.
+    pub(crate) fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.free.clear();
+        self.slots.drain(..).flatten()
+    }
+}
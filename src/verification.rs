@@ -0,0 +1,187 @@
+//! Bounded verification of the unsafe sifting code.
+//!
+//! Every check below is written once over a small array of bytes. With
+//! [Kani](https://github.com/model-checking/kani) (`cargo kani`) the checks are
+//! proven for all possible inputs, while `cargo test` runs them exhaustively
+//! over a small value domain.
+
+use crate::bits::Bits;
+use crate::raw::{self, Hole};
+use crate::WeakHeap;
+
+const N: usize = 5;
+
+// Checks that every element is not greater than its distinguished ancestor.
+fn is_weak_heap<B: Bits + ?Sized>(data: &[u8], bit: &B) -> bool {
+    data.len() == bit.len()
+        && (1..data.len()).all(|pos| {
+            // SAFETY: 0 < pos < bit.len().
+            let ancestor = unsafe { raw::d_ancestor(bit, pos) };
+            data[ancestor] >= data[pos]
+        })
+}
+
+fn is_permutation(a: &[u8], b: &[u8]) -> bool {
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort_unstable();
+    b.sort_unstable();
+    a == b
+}
+
+/// Pushing an element onto a valid heap keeps it valid.
+fn check_sift_up(input: [u8; N], len: usize) {
+    let len = len % N + 1;
+    let mut data = input[..len].to_vec();
+    let mut bit = vec![false; len];
+    raw::rebuild(&mut data[..len - 1], &mut bit[..len - 1], &mut u8::lt);
+
+    // SAFETY: len - 1 < data.len().
+    let pos = unsafe { raw::sift_up(&mut data, &bit, len - 1, &mut u8::lt) };
+
+    assert!(pos < len);
+    assert!(data[pos] == input[len - 1]);
+    assert!(is_weak_heap(&data, &bit));
+    assert!(is_permutation(&data, &input[..len]));
+}
+
+/// Replacing the root of a valid heap and sifting it down keeps the heap valid.
+fn check_sift_down_range(input: [u8; N], root: u8, len: usize) {
+    let len = len % N + 1;
+    let mut data = input[..len].to_vec();
+    let mut bit = vec![false; len];
+    raw::rebuild(&mut data, &mut bit, &mut u8::lt);
+
+    data[0] = root;
+    let expected = data.clone();
+    // SAFETY: 0 < len == data.len().
+    unsafe { raw::sift_down_range(&mut data, &mut bit, 0, len, &mut u8::lt) };
+
+    assert!(is_weak_heap(&data, &bit));
+    assert!(is_permutation(&data, &expected));
+}
+
+/// One weak-heapsort step moves the greatest element to the end and leaves a
+/// valid heap in front of it.
+fn check_extract_to_end(input: [u8; N], len: usize) {
+    let len = len % (N - 1) + 2;
+    let mut data = input[..len].to_vec();
+    let mut bit = vec![false; len];
+    raw::rebuild(&mut data, &mut bit, &mut u8::lt);
+
+    let max = data[0];
+    // SAFETY: 0 < len - 1 < data.len().
+    unsafe { raw::extract_to_end(&mut data, &mut bit, len - 1, &mut u8::lt) };
+
+    assert!(data[len - 1] == max);
+    assert!(is_weak_heap(&data[..len - 1], &bit[..len - 1]));
+    assert!(is_permutation(&data, &input[..len]));
+}
+
+/// Moving a hole around and dropping it neither loses nor duplicates elements.
+fn check_hole(input: [u8; N], moves: [usize; 3]) {
+    let mut data = input;
+    {
+        // SAFETY: 0 < N.
+        let mut hole = unsafe { Hole::new(&mut data, 0) };
+        for index in moves {
+            let index = index % N;
+            if index != hole.pos() {
+                // SAFETY: index < N and index != hole.pos().
+                unsafe { hole.move_to(index) };
+            }
+        }
+    }
+
+    let mut expected = input;
+    let mut pos = 0;
+    for index in moves {
+        let index = index % N;
+        if index != pos {
+            expected[pos] = expected[index];
+            pos = index;
+        }
+    }
+    expected[pos] = input[0];
+    assert!(data == expected);
+}
+
+/// `pop` returns the greatest element and leaves a valid heap.
+fn check_pop(input: [u8; N], len: usize) {
+    let len = len % (N + 1);
+    let mut heap = WeakHeap::from(input[..len].to_vec());
+
+    let max = input[..len].iter().max().copied();
+    assert!(heap.pop() == max);
+    assert!(heap.len() == len.saturating_sub(1));
+    assert!(is_weak_heap(&heap.data, &heap.bit));
+}
+
+#[cfg(kani)]
+mod proofs {
+    use super::*;
+
+    #[kani::proof]
+    #[kani::unwind(7)]
+    fn sift_up() {
+        check_sift_up(kani::any(), kani::any());
+    }
+
+    #[kani::proof]
+    #[kani::unwind(7)]
+    fn sift_down_range() {
+        check_sift_down_range(kani::any(), kani::any(), kani::any());
+    }
+
+    #[kani::proof]
+    #[kani::unwind(7)]
+    fn extract_to_end() {
+        check_extract_to_end(kani::any(), kani::any());
+    }
+
+    #[kani::proof]
+    #[kani::unwind(7)]
+    fn hole() {
+        check_hole(kani::any(), kani::any());
+    }
+
+    #[kani::proof]
+    #[kani::unwind(7)]
+    fn pop() {
+        check_pop(kani::any(), kani::any());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // All arrays of length `N` with elements in `0..3`.
+    fn inputs() -> impl Iterator<Item = [u8; N]> {
+        (0..3usize.pow(N as u32)).map(|mut code| {
+            let mut input = [0; N];
+            for x in input.iter_mut() {
+                *x = (code % 3) as u8;
+                code /= 3;
+            }
+            input
+        })
+    }
+
+    #[test]
+    fn exhaustive_checks() {
+        for input in inputs() {
+            for len in 0..=N {
+                check_sift_up(input, len);
+                check_pop(input, len);
+                check_extract_to_end(input, len);
+                for root in 0..3 {
+                    check_sift_down_range(input, root, len);
+                }
+            }
+            for moves in [[0, 1, 2], [3, 1, 0], [4, 4, 2], [2, 0, 4]] {
+                check_hole(input, moves);
+            }
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! A weak heap over a borrowed slice.
+
+use crate::bits::{BitVec, Bits};
+use crate::raw;
+use core::fmt;
+
+// The reverse bits of a view, owned or provided by the caller.
+enum BitBuf<'a> {
+    Owned(BitVec),
+    Borrowed(&'a mut [bool]),
+}
+
+impl Bits for BitBuf<'_> {
+    #[inline]
+    fn len(&self) -> usize {
+        match self {
+            BitBuf::Owned(bits) => bits.len(),
+            BitBuf::Borrowed(bits) => bits.len(),
+        }
+    }
+
+    #[inline]
+    unsafe fn get_unchecked(&self, pos: usize) -> bool {
+        match self {
+            BitBuf::Owned(bits) => Bits::get_unchecked(bits, pos),
+            BitBuf::Borrowed(bits) => Bits::get_unchecked(&**bits, pos),
+        }
+    }
+
+    #[inline]
+    unsafe fn flip_unchecked(&mut self, pos: usize) {
+        match self {
+            BitBuf::Owned(bits) => bits.flip_unchecked(pos),
+            BitBuf::Borrowed(bits) => Bits::flip_unchecked(&mut **bits, pos),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            BitBuf::Owned(bits) => bits.reset(),
+            BitBuf::Borrowed(bits) => Bits::reset(&mut **bits),
+        }
+    }
+}
+
+/// A weak heap (max-heap) arranged inside a caller-owned mutable slice.
+///
+/// The heap occupies a prefix of the slice, the remaining elements are left
+/// untouched. [`pop_to_end`] moves the greatest element right behind the
+/// heap, shrinking it, and [`push_from_end`] grows the heap by the element
+/// right behind it. Nothing is ever moved out of the slice, so a view can
+/// work on a sub-range of a large buffer.
+///
+/// The reverse bits are either allocated by the view ([`new`]) or provided
+/// by the caller ([`with_bits`]), in which case the view doesn't allocate.
+///
+/// # Examples
+///
+/// ```
+/// use weakheap::WeakHeapView;
+///
+/// let mut buffer = [0, 0, 3, 1, 4, 1, 5, 0];
+/// let mut view = WeakHeapView::new(&mut buffer[2..7]);
+/// assert_eq!(view.peek(), Some(&5));
+///
+/// // Popping everything sorts the range in place.
+/// while view.pop_to_end().is_some() {}
+/// assert_eq!(buffer, [0, 0, 1, 1, 3, 4, 5, 0]);
+/// ```
+///
+/// [`new`]: WeakHeapView::new
+/// [`with_bits`]: WeakHeapView::with_bits
+/// [`pop_to_end`]: WeakHeapView::pop_to_end
+/// [`push_from_end`]: WeakHeapView::push_from_end
+pub struct WeakHeapView<'a, T> {
+    data: &'a mut [T],
+    bit: BitBuf<'a>,
+    len: usize,
+}
+
+impl<T: fmt::Debug> fmt::Debug for WeakHeapView<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<'a, T: Ord> WeakHeapView<'a, T> {
+    /// Arranges the whole `data` into a weak heap, allocating the reverse bits.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapView;
+    ///
+    /// let mut data = [2, 7, 1];
+    /// let view = WeakHeapView::new(&mut data);
+    /// assert_eq!(view.peek(), Some(&7));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// Building a weak heap takes exactly *n* - 1 comparisons.
+    pub fn new(data: &'a mut [T]) -> WeakHeapView<'a, T> {
+        let bit = BitBuf::Owned(BitVec::zeros(data.len()));
+        WeakHeapView::build(data, bit)
+    }
+
+    /// Arranges the whole `data` into a weak heap, storing the reverse bits
+    /// in `bits`. The previous contents of `bits` are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` and `bits` have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapView;
+    ///
+    /// let mut data = [2, 7, 1];
+    /// let mut bits = [false; 3];
+    /// let view = WeakHeapView::with_bits(&mut data, &mut bits);
+    /// assert_eq!(view.peek(), Some(&7));
+    /// ```
+    pub fn with_bits(data: &'a mut [T], bits: &'a mut [bool]) -> WeakHeapView<'a, T> {
+        assert_eq!(data.len(), bits.len(), "data and bits lengths differ");
+        WeakHeapView::build(data, BitBuf::Borrowed(bits))
+    }
+
+    fn build(data: &'a mut [T], mut bit: BitBuf<'a>) -> WeakHeapView<'a, T> {
+        raw::rebuild(data, &mut bit, &mut raw::lt);
+        let len = data.len();
+        WeakHeapView { data, bit, len }
+    }
+
+    /// Moves the greatest element of the heap right behind it, shrinking the
+    /// heap by one. Returns the new position of the element, or `None` if the
+    /// heap is empty.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapView;
+    ///
+    /// let mut data = [1, 3, 2];
+    /// let mut view = WeakHeapView::new(&mut data);
+    ///
+    /// assert_eq!(view.pop_to_end(), Some(2));
+    /// assert_eq!(view.len(), 2);
+    /// assert_eq!(view.tail(), [3]);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    pub fn pop_to_end(&mut self) -> Option<usize> {
+        self.len = self.len.checked_sub(1)?;
+        self.data.swap(0, self.len);
+        if self.len > 0 {
+            // SAFETY: 0 < self.len < self.data.len() == self.bit.len().
+            unsafe { raw::sift_down_range(self.data, &mut self.bit, 0, self.len, &mut raw::lt) };
+        }
+        Some(self.len)
+    }
+
+    /// Grows the heap by the element right behind it. Returns the position
+    /// the element ends up at, or `None` if the heap already spans the whole
+    /// slice.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapView;
+    ///
+    /// let mut data = [1, 3, 2];
+    /// let mut view = WeakHeapView::new(&mut data);
+    /// view.pop_to_end();
+    ///
+    /// assert_eq!(view.push_from_end(), Some(0));
+    /// assert_eq!(view.peek(), Some(&3));
+    /// assert_eq!(view.push_from_end(), None);
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The expected cost is *O*(1), the worst case is *O*(log(*n*)).
+    pub fn push_from_end(&mut self) -> Option<usize> {
+        if self.len == self.data.len() {
+            return None;
+        }
+        let pos = self.len;
+        self.len += 1;
+        // SAFETY: pos < self.len <= self.data.len(), and sifting up only
+        //  reads the bits of the ancestors of `pos`.
+        Some(unsafe { raw::sift_up(&mut self.data[..self.len], &self.bit, pos, &mut raw::lt) })
+    }
+
+    /// Restores the heap property after the element at `pos` has been
+    /// modified through [`as_mut_slice_unchecked`], moving it up or down as
+    /// needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is not a position of the heap.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use weakheap::WeakHeapView;
+    ///
+    /// let mut data = [1, 3, 2];
+    /// let mut view = WeakHeapView::new(&mut data);
+    ///
+    /// // SAFETY: the element is sifted right after the modification.
+    /// let heap = unsafe { view.as_mut_slice_unchecked() };
+    /// heap[0] = 0;
+    /// view.sift(0);
+    /// assert_eq!(view.peek(), Some(&2));
+    /// ```
+    ///
+    /// # Time complexity
+    ///
+    /// The worst case cost is *O*(log(*n*)).
+    ///
+    /// [`as_mut_slice_unchecked`]: WeakHeapView::as_mut_slice_unchecked
+    pub fn sift(&mut self, pos: usize) {
+        assert!(pos < self.len, "position out of bounds");
+        // SAFETY: pos < self.len, and the bits past the heap are not read.
+        unsafe { raw::sift(&mut self.data[..self.len], &mut self.bit, pos, &mut raw::lt) };
+    }
+}
+
+impl<'a, T> WeakHeapView<'a, T> {
+    /// Returns the greatest item of the heap, or `None` if it is empty.
+    ///
+    /// # Time complexity
+    ///
+    /// Cost is *O*(1) in the worst case.
+    #[must_use]
+    pub fn peek(&self) -> Option<&T> {
+        self.as_slice().first()
+    }
+
+    /// Returns the elements of the heap, in heap order.
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        &self.data[..self.len]
+    }
+
+    /// Returns a mutable slice of the elements of the heap.
+    ///
+    /// # Safety
+    ///
+    /// Modifying an element may break the heap property. It must be restored
+    /// with [`sift`] for every modified position before any other operation
+    /// on the view.
+    ///
+    /// [`sift`]: WeakHeapView::sift
+    pub unsafe fn as_mut_slice_unchecked(&mut self) -> &mut [T] {
+        &mut self.data[..self.len]
+    }
+
+    /// Returns the elements behind the heap.
+    #[must_use]
+    pub fn tail(&self) -> &[T] {
+        &self.data[self.len..]
+    }
+
+    /// Returns the number of elements in the heap.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the heap is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the length of the underlying slice, the maximum size of the heap.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+}